@@ -44,6 +44,15 @@ fn main() {
         println!("cargo:rerun-if-env-changed=CARGO_APK2_ARTIFACT");
     }
 
+    // Platforms without a native backend, and builds asking for it, use the headless backend
+    println!("cargo:rustc-check-cfg=cfg(headless)");
+    let target_os = var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    if var("CARGO_FEATURE_HEADLESS").is_ok()
+        || !matches!(target_os.as_str(), "windows" | "macos" | "ios" | "android")
+    {
+        println!("cargo:rustc-cfg=headless");
+    }
+
     // 确保在目标平台变化时重新运行构建脚本
     println!("cargo:rerun-if-env-changed=TARGET");
 }
@@ -1 +1,10 @@
-include!("../../hello.rs");
\ No newline at end of file
+#[cfg(any(windows, target_os = "macos"))]
+include!("../../hello.rs");
+
+// Other desktops only have the headless backend, whose windows never show up on screen
+#[cfg(not(any(windows, target_os = "macos")))]
+fn main() {
+    eprintln!(
+        "There is no native window backend for this platform yet, run the example on Windows or macOS."
+    );
+}
@@ -0,0 +1,359 @@
+use {
+    crate::{frame::FrameClock, geometry::Rect},
+    std::{
+        cell::RefCell,
+        rc::Rc,
+        time::{Duration, Instant},
+    },
+};
+
+/// How an animation progresses over its duration.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    /// At the same pace throughout.
+    Linear,
+    /// Starts slowly, like CSS `ease-in`.
+    EaseIn,
+    /// Ends slowly, like CSS `ease-out`.
+    EaseOut,
+    /// Starts and ends slowly, like CSS `ease-in-out`.
+    #[default]
+    EaseInOut,
+    /// A CSS `cubic-bezier(x1, y1, x2, y2)` curve. The x coordinates are kept within 0 to 1, the y
+    /// coordinates may leave it to overshoot.
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    /// How far along the animation is at `progress` of its duration, both from 0 to 1.
+    pub fn apply(&self, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        match *self {
+            Self::Linear => progress,
+            Self::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, progress),
+            Self::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, progress),
+            Self::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, progress),
+            Self::CubicBezier(x1, y1, x2, y2) => {
+                cubic_bezier(x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2, progress)
+            }
+        }
+    }
+}
+
+// The y of the curve from (0, 0) to (1, 1) with control points (`x1`, `y1`) and (`x2`, `y2`) at `x`. With
+// the x coordinates within 0 to 1 the curve rises monotonically in x, so the parameter is found by bisection
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    let bezier = |a: f32, b: f32, t: f32| {
+        let u = 1.0 - t;
+        3.0 * a * u * u * t + 3.0 * b * u * t * t + t * t * t
+    };
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..24 {
+        let middle = (low + high) / 2.0;
+        if bezier(x1, x2, middle) < x {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    bezier(y1, y2, (low + high) / 2.0)
+}
+
+/// Values an `Animated` can move between.
+pub trait Interpolate: Clone + PartialEq {
+    /// The value `t` of the way from `self` to `to`. `t` runs from 0 to 1, but leaves that range with easing
+    /// curves that overshoot.
+    fn interpolate(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t as f64
+    }
+}
+
+impl Interpolate for i32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        (*self as f64).interpolate(&(*to as f64), t).round() as i32
+    }
+}
+
+impl Interpolate for u32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        (*self as f64)
+            .interpolate(&(*to as f64), t)
+            .round()
+            .max(0.0) as u32
+    }
+}
+
+impl<A: Interpolate, B: Interpolate> Interpolate for (A, B) {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        (self.0.interpolate(&to.0, t), self.1.interpolate(&to.1, t))
+    }
+}
+
+impl Interpolate for Rect {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        Self {
+            x: self.x.interpolate(&to.x, t),
+            y: self.y.interpolate(&to.y, t),
+            width: self.width.interpolate(&to.width, t),
+            height: self.height.interpolate(&to.height, t),
+        }
+    }
+}
+
+struct Inner<T> {
+    value: T,
+    // Where the running transition started
+    from: T,
+    target: T,
+    start: Instant,
+    duration: Duration,
+    easing: Easing,
+    running: bool,
+}
+
+/// A value that moves to its targets over time, e.g. the position, size or opacity of a `window`.
+///
+/// `animate_to` starts a transition, and `next` advances it with each frame of a `FrameClock`, so the value
+/// changes once per refresh of the display rather than on a timer. A new target replaces the running
+/// transition, which continues from wherever the value is, so the value never jumps. Keep it in a `#[field]`
+/// and assign what `next` returns to the variables the props are rendered from.
+#[derive(Clone)]
+pub struct Animated<T>(Rc<RefCell<Inner<T>>>);
+
+impl<T: Interpolate + Default> Default for Animated<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Interpolate> Animated<T> {
+    /// Resting at `value`.
+    pub fn new(value: T) -> Self {
+        Self(Rc::new(RefCell::new(Inner {
+            from: value.clone(),
+            target: value.clone(),
+            value,
+            start: Instant::now(),
+            duration: Duration::ZERO,
+            easing: Easing::Linear,
+            running: false,
+        })))
+    }
+
+    /// The value as of the last frame.
+    pub fn get(&self) -> T {
+        self.0.borrow().value.clone()
+    }
+
+    /// Where the value is headed, or where it rests.
+    pub fn target(&self) -> T {
+        self.0.borrow().target.clone()
+    }
+
+    /// Whether a transition is running.
+    pub fn is_running(&self) -> bool {
+        self.0.borrow().running
+    }
+
+    /// Move from the current value to `target` over `duration`, replacing the running transition. Nothing
+    /// changes if the value is already headed for or resting at `target`, so this can be called on every
+    /// render.
+    pub fn animate_to(&self, target: T, duration: Duration, easing: Easing) {
+        let mut inner = self.0.borrow_mut();
+        if inner.target == target && (inner.running || inner.value == target) {
+            return;
+        }
+        inner.from = inner.value.clone();
+        inner.target = target;
+        inner.start = Instant::now();
+        inner.duration = duration;
+        inner.easing = easing;
+        inner.running = true;
+    }
+
+    /// Jump to `value`, stopping the running transition.
+    pub fn set(&self, value: T) {
+        let mut inner = self.0.borrow_mut();
+        inner.target = value.clone();
+        inner.value = value;
+        inner.running = false;
+    }
+
+    /// Stop the running transition where the value is.
+    pub fn cancel(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.target = inner.value.clone();
+        inner.running = false;
+    }
+
+    /// Wait for the next frame of `clock` and return the value for its time, or `None` right away while no
+    /// transition is running. The last value returned is the target, or where `set` or `cancel` left the
+    /// value. Waits while the window is hidden, like `FrameClock::next_frame`.
+    pub async fn next(&self, clock: &FrameClock) -> Option<T> {
+        if !self.is_running() {
+            return None;
+        }
+        let frame = clock.next_frame().await;
+        let mut inner = self.0.borrow_mut();
+        if inner.running {
+            let elapsed = frame.time.saturating_duration_since(inner.start);
+            let progress = if inner.duration.is_zero() {
+                1.0
+            } else {
+                elapsed.as_secs_f32() / inner.duration.as_secs_f32()
+            };
+            if progress >= 1.0 {
+                inner.value = inner.target.clone();
+                inner.running = false;
+            } else {
+                let t = inner.easing.apply(progress);
+                inner.value = inner.from.interpolate(&inner.target, t);
+            }
+        }
+        Some(inner.value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            pin::pin,
+            task::{Context, Poll, Waker},
+        },
+    };
+
+    const EASINGS: [Easing; 5] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+    ];
+
+    // Run `next` with the clock producing a frame at `at`
+    fn frame_at<T: Interpolate>(
+        animated: &Animated<T>,
+        clock: &FrameClock,
+        at: Instant,
+    ) -> Option<T> {
+        let mut next = pin!(animated.next(clock));
+        let mut cx = Context::from_waker(Waker::noop());
+        if let Poll::Ready(value) = next.as_mut().poll(&mut cx) {
+            return value;
+        }
+        clock.tick(at).expect("a frame should be due");
+        match next.poll(&mut cx) {
+            Poll::Ready(value) => value,
+            Poll::Pending => panic!("the frame wasn't taken"),
+        }
+    }
+
+    fn start(animated: &Animated<f32>) -> Instant {
+        animated.0.borrow().start
+    }
+
+    fn visible_clock() -> FrameClock {
+        let clock = FrameClock::default();
+        clock.set_visible(true);
+        clock
+    }
+
+    #[test]
+    fn easings_start_at_0_and_end_at_1() {
+        for easing in EASINGS {
+            assert!(easing.apply(0.0).abs() < 1e-4, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-4, "{:?}", easing);
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0), "{:?}", easing);
+            assert_eq!(easing.apply(2.0), easing.apply(1.0), "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn easings_are_monotonic() {
+        for easing in EASINGS {
+            let values = (0..=100)
+                .map(|i| easing.apply(i as f32 / 100.0))
+                .collect::<Vec<_>>();
+            assert!(
+                values.windows(2).all(|w| w[1] >= w[0] - 1e-5),
+                "{:?}",
+                easing
+            );
+        }
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        // CSS `ease`
+        assert!((EASINGS[4].apply(0.5) - 0.8024).abs() < 1e-3);
+    }
+
+    #[test]
+    fn cubic_bezier_overshoots() {
+        let back = Easing::CubicBezier(0.34, 1.56, 0.64, 1.0);
+        let peak = (0..=100)
+            .map(|i| back.apply(i as f32 / 100.0))
+            .fold(f32::MIN, f32::max);
+        assert!(peak > 1.05);
+        assert!((back.apply(1.0) - 1.0).abs() < 1e-4);
+        // Control points beyond 0 to 1 in x are clamped, so the curve stays a function of x
+        let clamped = Easing::CubicBezier(-1.0, 0.0, 2.0, 1.0);
+        assert_eq!(
+            clamped.apply(0.3),
+            Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3)
+        );
+    }
+
+    #[test]
+    fn retargeting_continues_from_the_current_value() {
+        let clock = visible_clock();
+        let animated = Animated::new(0.0f32);
+        animated.animate_to(100.0, Duration::from_secs(1), Easing::Linear);
+        let first = start(&animated);
+        let halfway = frame_at(&animated, &clock, first + Duration::from_millis(500)).unwrap();
+        assert!((halfway - 50.0).abs() < 0.01);
+
+        animated.animate_to(0.0, Duration::from_secs(1), Easing::Linear);
+        assert_eq!(animated.get(), halfway);
+        assert_eq!(animated.target(), 0.0);
+        let second = start(&animated);
+        let value = frame_at(&animated, &clock, second + Duration::from_millis(600)).unwrap();
+        assert!((value - halfway * 0.4).abs() < 0.01);
+
+        let end = frame_at(&animated, &clock, second + Duration::from_secs(2));
+        assert_eq!(end, Some(0.0));
+        assert!(!animated.is_running());
+    }
+
+    #[test]
+    fn zero_duration_finishes_on_the_next_frame() {
+        let clock = visible_clock();
+        let animated = Animated::new(1.0f32);
+        animated.animate_to(10.0, Duration::ZERO, Easing::EaseInOut);
+        assert!(animated.is_running());
+        assert_eq!(animated.get(), 1.0);
+        assert_eq!(frame_at(&animated, &clock, Instant::now()), Some(10.0));
+        assert!(!animated.is_running());
+        assert_eq!(frame_at(&animated, &clock, Instant::now()), None);
+    }
+
+    #[test]
+    fn same_target_keeps_the_transition() {
+        let animated = Animated::new(0.0f32);
+        animated.animate_to(1.0, Duration::from_secs(1), Easing::Linear);
+        let first = start(&animated);
+        animated.animate_to(1.0, Duration::from_secs(5), Easing::EaseIn);
+        assert_eq!(start(&animated), first);
+        assert_eq!(animated.0.borrow().duration, Duration::from_secs(1));
+    }
+}
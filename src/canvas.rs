@@ -0,0 +1,399 @@
+#[cfg(feature = "text")]
+use {
+    crate::text::{Fonts, TextStyle},
+    tiny_skia::Point,
+};
+use {
+    crate::{
+        context::{Layer, RedrawHandle, WindowContext},
+        geometry,
+        layout::{LayoutNode, LayoutRect},
+        surface::PixelBuffer,
+    },
+    compo::prelude::*,
+    tiny_skia::{
+        Color, FillRule, FilterQuality, Mask, Paint, Path, PathBuilder, Pixmap, PixmapMut,
+        PixmapPaint, Rect, Stroke, Transform,
+    },
+};
+
+/// The drawing API of a `canvas`, on top of tiny-skia.
+///
+/// Coordinates are logical pixels from the top left corner of the canvas, the scale factor of the window
+/// is applied below the current transform. Everything is clipped to the canvas and to the current clip.
+/// Paths, paints, strokes, gradients and transforms are the ones of tiny-skia, which the crate re-exports.
+pub struct Painter<'a> {
+    pixmap: PixmapMut<'a>,
+    scale_factor: f32,
+    transform: Transform,
+    clip: Option<Mask>,
+    saved: Vec<(Transform, Option<Mask>)>,
+}
+
+impl<'a> Painter<'a> {
+    /// Draw into `pixmap` with `scale_factor` physical pixels per logical pixel, e.g. to render offscreen.
+    pub fn new(pixmap: PixmapMut<'a>, scale_factor: f32) -> Self {
+        Self {
+            pixmap,
+            scale_factor,
+            transform: Transform::identity(),
+            clip: None,
+            saved: Vec::new(),
+        }
+    }
+
+    /// In logical pixels.
+    pub fn width(&self) -> f32 {
+        self.pixmap.width() as f32 / self.scale_factor
+    }
+
+    /// In logical pixels.
+    pub fn height(&self) -> f32 {
+        self.pixmap.height() as f32 / self.scale_factor
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// The current transform, without the scale factor.
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    /// Apply `transform` before the current transform.
+    pub fn concat(&mut self, transform: Transform) {
+        self.transform = self.transform.pre_concat(transform);
+    }
+
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        self.transform = self.transform.pre_translate(dx, dy);
+    }
+
+    pub fn scale(&mut self, sx: f32, sy: f32) {
+        self.transform = self.transform.pre_scale(sx, sy);
+    }
+
+    /// Rotate clockwise around the origin.
+    pub fn rotate(&mut self, degrees: f32) {
+        self.concat(Transform::from_rotate(degrees));
+    }
+
+    /// Remember the transform and the clip, until the matching `restore`.
+    pub fn save(&mut self) {
+        self.saved.push((self.transform, self.clip.clone()));
+    }
+
+    /// Go back to the transform and the clip of the last `save`. Does nothing without one.
+    pub fn restore(&mut self) {
+        if let Some((transform, clip)) = self.saved.pop() {
+            self.transform = transform;
+            self.clip = clip;
+        }
+    }
+
+    /// Narrow the clip down to `rect`, in the current transform.
+    pub fn clip_rect(&mut self, rect: Rect) {
+        self.clip_path(&PathBuilder::from_rect(rect), FillRule::Winding);
+    }
+
+    /// Narrow the clip down to `path`, in the current transform.
+    pub fn clip_path(&mut self, path: &Path, fill_rule: FillRule) {
+        let transform = self.device_transform();
+        match &mut self.clip {
+            Some(clip) => clip.intersect_path(path, fill_rule, true, transform),
+            None => {
+                let Some(mut clip) = Mask::new(self.pixmap.width(), self.pixmap.height()) else {
+                    return;
+                };
+                clip.fill_path(path, fill_rule, true, transform);
+                self.clip = Some(clip);
+            }
+        }
+    }
+
+    /// Fill the whole canvas with `color`, regardless of the transform and the clip.
+    pub fn clear(&mut self, color: Color) {
+        self.pixmap.fill(color);
+    }
+
+    pub fn fill_path(&mut self, path: &Path, paint: &Paint, fill_rule: FillRule) {
+        let transform = self.device_transform();
+        self.pixmap
+            .fill_path(path, paint, fill_rule, transform, self.clip.as_ref());
+    }
+
+    pub fn stroke_path(&mut self, path: &Path, paint: &Paint, stroke: &Stroke) {
+        let transform = self.device_transform();
+        self.pixmap
+            .stroke_path(path, paint, stroke, transform, self.clip.as_ref());
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, paint: &Paint) {
+        let transform = self.device_transform();
+        self.pixmap
+            .fill_rect(rect, paint, transform, self.clip.as_ref());
+    }
+
+    /// Draw `image` stretched over `rect`.
+    pub fn draw_image(&mut self, image: &Pixmap, rect: Rect) {
+        self.draw_image_filtered(image, rect, FilterQuality::Bilinear);
+    }
+
+    /// Draw `image` stretched over `rect`, sampled with `quality` when scaled.
+    pub fn draw_image_filtered(&mut self, image: &Pixmap, rect: Rect, quality: FilterQuality) {
+        let transform = self
+            .device_transform()
+            .pre_translate(rect.x(), rect.y())
+            .pre_scale(
+                rect.width() / image.width() as f32,
+                rect.height() / image.height() as f32,
+            );
+        let paint = PixmapPaint {
+            quality,
+            ..Default::default()
+        };
+        self.pixmap
+            .draw_pixmap(0, 0, image.as_ref(), &paint, transform, self.clip.as_ref());
+    }
+
+    /// Draw `text` in `rect`, from its top left corner and with lines broken at its width if wrapping.
+    /// Glyphs outside of `rect` are cut off.
+    ///
+    /// The text follows the translation and the scale of the current transform, but isn't rotated or skewed.
+    /// Needs the `text` feature.
+    #[cfg(feature = "text")]
+    pub fn draw_text(&mut self, text: &str, style: &TextStyle, rect: Rect) {
+        let transform = self.device_transform();
+        let scale = transform.sx.hypot(transform.ky);
+        let Some(bounds) = rect.transform(transform) else {
+            return;
+        };
+        let mut origin = Point::from_xy(rect.x(), rect.y());
+        transform.map_point(&mut origin);
+
+        let fonts = Fonts::global();
+        let mut buffer = fonts.layout(text, style, scale, Some(rect.width() * scale));
+        let (width, height) = (self.pixmap.width() as i32, self.pixmap.height() as i32);
+        let left = (bounds.left().floor() as i32).max(0);
+        let top = (bounds.top().floor() as i32).max(0);
+        let right = (bounds.right().ceil() as i32).min(width);
+        let bottom = (bounds.bottom().ceil() as i32).min(height);
+        let (dx, dy) = (origin.x.round() as i32, origin.y.round() as i32);
+        let pixels = self.pixmap.data_mut();
+        let clip = self.clip.as_ref().map(Mask::data);
+        fonts.draw(&mut buffer, style.color, |x, y, w, h, color| {
+            let columns = (x + dx).max(left)..(x + dx + w as i32).min(right);
+            for row in (y + dy).max(top)..(y + dy + h as i32).min(bottom) {
+                for column in columns.clone() {
+                    let index = (row * width + column) as usize;
+                    let coverage = clip.map_or(255, |clip| clip[index]) as u32;
+                    let alpha = color.a() as u32 * coverage / 255;
+                    if alpha == 0 {
+                        continue;
+                    }
+                    // The pixmap is premultiplied
+                    let target = &mut pixels[index * 4..index * 4 + 4];
+                    let source = [color.r(), color.g(), color.b(), 255];
+                    for (target, source) in target.iter_mut().zip(source) {
+                        *target =
+                            ((source as u32 * alpha + *target as u32 * (255 - alpha)) / 255) as u8;
+                    }
+                }
+            }
+        });
+    }
+
+    /// The pixels of the canvas, to draw with tiny-skia directly. Transform and clip don't apply.
+    pub fn pixmap(&mut self) -> &mut PixmapMut<'a> {
+        &mut self.pixmap
+    }
+
+    fn device_transform(&self) -> Transform {
+        self.transform
+            .post_scale(self.scale_factor, self.scale_factor)
+    }
+}
+
+type Draw = Box<dyn FnMut(&mut Painter)>;
+
+#[derive(Default)]
+struct Inner {
+    draw: Option<Draw>,
+    // Of the canvas showing the drawing
+    redraw: Option<RedrawHandle>,
+}
+
+/// What a `canvas` shows, drawn by a callback on every frame of the canvas.
+///
+/// Pass it to the `drawing` prop of `canvas`. The callback receives a `Painter` and runs whenever the window
+/// redraws, e.g. after `set_draw` or `request_redraw`, so state shared with it through `Rc` or `Cell` is
+/// picked up by asking for a redraw. A drawing is shown by one canvas at a time.
+#[derive(Clone, Default)]
+pub struct Drawing(Rc<RefCell<Inner>>);
+
+impl Drawing {
+    pub fn new(draw: impl FnMut(&mut Painter) + 'static) -> Self {
+        let drawing = Self::default();
+        drawing.set_draw(draw);
+        drawing
+    }
+
+    /// Replace the draw callback and redraw.
+    pub fn set_draw(&self, draw: impl FnMut(&mut Painter) + 'static) {
+        self.0.borrow_mut().draw = Some(Box::new(draw));
+        self.request_redraw();
+    }
+
+    /// Redraw with the next frame of the window.
+    pub fn request_redraw(&self) {
+        if let Some(redraw) = &self.0.borrow().redraw {
+            redraw.request_redraw();
+        }
+    }
+
+    fn set_redraw(&self, redraw: RedrawHandle) {
+        self.0.borrow_mut().redraw = Some(redraw);
+    }
+
+    fn draw(&self, painter: &mut Painter) {
+        // The callback isn't borrowed while it runs, so it may use the drawing
+        let Some(mut draw) = self.0.borrow_mut().draw.take() else {
+            return;
+        };
+        draw(painter);
+        let mut inner = self.0.borrow_mut();
+        if inner.draw.is_none() {
+            inner.draw = Some(draw);
+        }
+    }
+}
+
+// A 2D vector drawing in a window, rasterized on the CPU with tiny-skia. Placed at `x`, `y` in logical pixels,
+// a `width` or `height` of 0 extends it to the right or bottom edge of the window. Placed by `layout` instead,
+// if given
+#[component]
+pub async fn canvas(
+    context: Option<&WindowContext>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    drawing: Option<&Drawing>,
+    layout: Option<&LayoutNode>,
+) {
+    #[field]
+    let layer: Option<Layer> = None;
+
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let drawing = drawing.cloned();
+    let layout = layout.cloned();
+    let (x, y, width, height) = (*x, *y, *width, *height);
+    let layer = layer.get_or_insert_with(|| context.add_layer());
+    if let Some(drawing) = &drawing {
+        drawing.set_redraw(layer.redraw_handle());
+    }
+    layer.set_draw(move |buffer: &mut PixelBuffer| {
+        paint_region(buffer, layout.as_ref(), (x, y, width, height), |painter| {
+            if let Some(drawing) = &drawing {
+                drawing.draw(painter);
+            }
+        });
+    });
+}
+
+// The part of a window at `x`, `y` of `width` x `height` logical pixels, 0 reaching the right or bottom edge of a
+// window of `window_width` x `window_height`. A `layout` node decides the part instead, if given
+pub(crate) fn region_rect(
+    layout: Option<&LayoutNode>,
+    (x, y, width, height): (f32, f32, f32, f32),
+    (window_width, window_height): (f32, f32),
+) -> LayoutRect {
+    if let Some(layout) = layout {
+        return layout.layout();
+    }
+    LayoutRect {
+        x,
+        y,
+        width: if width == 0.0 {
+            window_width - x
+        } else {
+            width
+        },
+        height: if height == 0.0 {
+            window_height - y
+        } else {
+            height
+        },
+    }
+}
+
+// Paint the part of a window from `region_rect` over what the layers below left there
+pub(crate) fn paint_region(
+    buffer: &mut PixelBuffer,
+    layout: Option<&LayoutNode>,
+    region: (f32, f32, f32, f32),
+    paint: impl FnOnce(&mut Painter),
+) {
+    let scale_factor = buffer.scale_factor as f32;
+    let window = (
+        buffer.width as f32 / scale_factor,
+        buffer.height as f32 / scale_factor,
+    );
+    let rect = region_rect(layout, region, window);
+    let physical =
+        |logical: f32, limit: u32| ((logical * scale_factor).round().max(0.0) as u32).min(limit);
+    let left = physical(rect.x, buffer.width);
+    let top = physical(rect.y, buffer.height);
+    let right = physical(rect.x + rect.width, buffer.width);
+    let bottom = physical(rect.y + rect.height, buffer.height);
+    let painted = geometry::Rect::new(
+        left as i32,
+        top as i32,
+        right.saturating_sub(left) as i32,
+        bottom.saturating_sub(top) as i32,
+    );
+    buffer.painted = Some(buffer.painted.unwrap_or_default().union(&painted));
+    // Outside the damaged areas the buffer already shows this region as painted in the last frame
+    let damage = buffer
+        .damage
+        .iter()
+        .map(|rect| rect.intersection(&painted))
+        .filter(|rect| !rect.is_empty())
+        .collect::<Vec<_>>();
+    let Some(mut pixmap) =
+        Pixmap::new(painted.width as u32, painted.height as u32).filter(|_| !damage.is_empty())
+    else {
+        return;
+    };
+
+    let rows = (top..bottom).map(|row| (row * buffer.width + left) as usize);
+    let stride = pixmap.width() as usize * 4;
+    for (start, target) in rows.zip(pixmap.data_mut().chunks_exact_mut(stride)) {
+        let source = &buffer.pixels[start..start + stride / 4];
+        for (&pixel, target) in source.iter().zip(target.chunks_exact_mut(4)) {
+            let [b, g, r, _] = pixel.to_le_bytes();
+            target.copy_from_slice(&[r, g, b, 0xFF]);
+        }
+    }
+    paint(&mut Painter::new(pixmap.as_mut(), scale_factor));
+    // Everything below is opaque, so the result is as well
+    for rect in damage {
+        for y in rect.y..rect.bottom() {
+            let start = (y as u32 * buffer.width) as usize + rect.x as usize;
+            let target = &mut buffer.pixels[start..start + rect.width as usize];
+            let offset =
+                ((y as u32 - top) * pixmap.width()) as usize + (rect.x as u32 - left) as usize;
+            let source = &pixmap.data()[offset * 4..(offset + rect.width as usize) * 4];
+            for (pixel, source) in target.iter_mut().zip(source.chunks_exact(4)) {
+                *pixel = u32::from_le_bytes([source[2], source[1], source[0], 0]);
+            }
+        }
+    }
+}
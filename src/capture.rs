@@ -0,0 +1,98 @@
+use std::{
+    error::Error,
+    fmt::{Display as FmtDisplay, Formatter, Result as FmtResult},
+    sync::Arc,
+};
+#[cfg(feature = "image")]
+use {
+    ::image::{ImageError, ImageFormat, RgbaImage},
+    std::{io, path::Path},
+};
+
+/// The contents of a window's client area, read back with `WindowContext::capture`,
+/// `SoftwareSurface::capture` or `NativeHandle::capture`, e.g. for screenshots in documentation or visual
+/// regression tests.
+///
+/// The pixels are RGBA, 4 bytes each, rows top to bottom without padding, so the pixel at (`x`, `y`) starts
+/// at `pixels[(y * width + x) * 4]`. Windows are opaque, so alpha is always 255.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Capture {
+    /// In physical pixels.
+    pub width: u32,
+    /// In physical pixels.
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Capture {
+    // Convert a frame in the layout of `PixelBuffer`
+    pub(crate) fn from_frame(frame: &[u32], width: u32, height: u32) -> Self {
+        let pixels = frame
+            .iter()
+            .flat_map(|pixel| {
+                let [b, g, r, _] = pixel.to_le_bytes();
+                [r, g, b, 0xFF]
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The pixel at (`x`, `y`) as `[r, g, b, a]`, `None` outside the capture.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[i..i + 4].try_into().ok()
+    }
+
+    /// Write the capture to a PNG file at `path`, replacing an existing file. Needs the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let image = RgbaImage::from_raw(self.width, self.height, self.pixels.clone())
+            .ok_or_else(|| io::Error::other("the pixels don't match the size of the capture"))?;
+        image
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(|e| match e {
+                ImageError::IoError(e) => e,
+                e => io::Error::other(e),
+            })
+    }
+}
+
+/// Why `NativeHandle::capture` can't read back a window.
+#[derive(Clone, Debug)]
+pub enum CaptureError {
+    /// The window doesn't exist, before it is shown and after it is hidden or closed.
+    Unavailable,
+    /// The platform doesn't let an app read back its own window: macOS, iOS, Android and the headless
+    /// backend. `WindowContext::capture` still reads back the software surface there.
+    NotSupported,
+    /// The platform failed to read back the window.
+    Platform(Arc<dyn Error + Send + Sync>),
+}
+
+impl FmtDisplay for CaptureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Unavailable => write!(f, "the window doesn't exist"),
+            Self::NotSupported => {
+                write!(f, "reading back windows isn't supported on this platform")
+            }
+            Self::Platform(e) => write!(f, "reading back the window failed: {}", e),
+        }
+    }
+}
+
+impl Error for CaptureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Platform(e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,608 @@
+#[cfg(feature = "layout")]
+use crate::layout::LayoutNode;
+use {
+    crate::{
+        capture::Capture,
+        events::{WindowEvent, WindowEvents},
+        frame::FrameClock,
+        geometry::Rect,
+        handle::NativeHandle,
+        keyboard::{Key, NamedKey},
+        surface::{Draw, PixelBuffer, SoftwareSurface},
+        text_input::ImeRequest,
+        theme::{ColorScheme, Theme},
+        window::system_color_scheme,
+    },
+    std::{
+        cell::{Cell, RefCell},
+        mem::take,
+        rc::{Rc, Weak},
+    },
+};
+
+// Rounds of repainting in a frame, for layers that move out of the damaged areas while drawing
+const MAX_PASSES: usize = 3;
+
+struct Slot {
+    id: u64,
+    // Taken out while it runs
+    draw: Option<Draw>,
+    focusable: bool,
+    ime: Option<ImeRequest>,
+    // What the layer painted in the last frame, in physical pixels. `None` before it painted, or if it
+    // doesn't paint through `paint_region`, so it may paint anywhere
+    bounds: Option<Rect>,
+}
+
+type ImeHandler = Box<dyn Fn(Option<ImeRequest>)>;
+
+struct Inner {
+    native_handle: NativeHandle,
+    surface: SoftwareSurface,
+    frame_clock: FrameClock,
+    // `None` for the background of the theme
+    background: Cell<Option<u32>>,
+    // The `theme` prop of the window, `None` to follow the color scheme
+    theme_prop: RefCell<Option<Theme>>,
+    color_scheme: Cell<ColorScheme>,
+    theme: RefCell<Rc<Theme>>,
+    // Drawn in this order, each over the previous ones, and focused in this order with Tab
+    layers: RefCell<Vec<Slot>>,
+    next_layer: Cell<u64>,
+    focused: Cell<Option<u64>>,
+    // Applies the IME request of the focused layer to the window
+    ime_handler: RefCell<Option<ImeHandler>>,
+    streams: RefCell<Vec<WindowEvents>>,
+    closed: Cell<bool>,
+    enabled: Cell<bool>,
+    #[cfg(feature = "layout")]
+    layout: RefCell<Option<LayoutNode>>,
+}
+
+/// What a `window` shares with the components rendered into it.
+///
+/// Pass the same context to the `context` prop of `window` and to the components that draw into the
+/// window or follow its events. The window takes its `native_handle`, `surface` and `frame_clock` from the
+/// context unless these props are set, keeps the size and scale factor up to date, and dispatches its events
+/// to every stream from `events`.
+///
+/// The context's surface draws the layers of the components on a `background` color, in the order the
+/// layers were added. When a layer asks for a redraw, only the area it covers is repainted, along with the
+/// other layers there.
+///
+/// The context also provides the `Theme` the components draw with, see `theme` and `with_theme`.
+#[derive(Clone)]
+pub struct WindowContext(Rc<Inner>, Option<Rc<Theme>>);
+
+impl Default for WindowContext {
+    fn default() -> Self {
+        let inner = Rc::new_cyclic(|this: &Weak<Inner>| {
+            let this = this.clone();
+            Inner {
+                native_handle: NativeHandle::default(),
+                surface: SoftwareSurface::new(move |buffer: &mut PixelBuffer| {
+                    if let Some(inner) = this.upgrade() {
+                        draw_layers(&inner, buffer);
+                    }
+                }),
+                frame_clock: FrameClock::default(),
+                background: Cell::new(None),
+                theme_prop: Default::default(),
+                color_scheme: Cell::new(ColorScheme::Light),
+                theme: Default::default(),
+                layers: Default::default(),
+                next_layer: Cell::new(0),
+                focused: Cell::new(None),
+                ime_handler: Default::default(),
+                streams: Default::default(),
+                closed: Cell::new(true),
+                enabled: Cell::new(true),
+                #[cfg(feature = "layout")]
+                layout: Default::default(),
+            }
+        });
+        Self(inner, None)
+    }
+}
+
+impl WindowContext {
+    pub fn native_handle(&self) -> &NativeHandle {
+        &self.0.native_handle
+    }
+
+    pub fn surface(&self) -> &SoftwareSurface {
+        &self.0.surface
+    }
+
+    pub fn frame_clock(&self) -> &FrameClock {
+        &self.0.frame_clock
+    }
+
+    /// The size of the client area in physical pixels, as of the last frame.
+    pub fn size(&self) -> (u32, u32) {
+        self.0.surface.size()
+    }
+
+    /// Physical pixels per logical pixel of the window's display.
+    pub fn scale_factor(&self) -> f64 {
+        self.0.surface.scale_factor()
+    }
+
+    /// The client area as of the last frame, as RGBA in physical pixels. `None` before the first frame.
+    ///
+    /// This is what the context's surface drew, so content that graphics libraries draw through the
+    /// `native_handle` isn't part of it. Call `request_redraw` and await `FrameClock::next_frame` first to
+    /// capture changes made since the last frame.
+    pub fn capture(&self) -> Option<Capture> {
+        self.0.surface.capture()
+    }
+
+    /// Whether the window currently exists.
+    pub fn is_open(&self) -> bool {
+        !self.0.closed.get()
+    }
+
+    /// The `enabled` prop of the window. Widgets in a disabled window are disabled as well.
+    pub fn is_enabled(&self) -> bool {
+        self.0.enabled.get()
+    }
+
+    /// A new stream of the window's events, from now on.
+    pub fn events(&self) -> WindowEvents {
+        let events = WindowEvents::default();
+        events.set_closed(self.0.closed.get());
+        self.0.streams.borrow_mut().push(events.clone());
+        events
+    }
+
+    /// Add a layer on top of the existing ones.
+    pub fn add_layer(&self) -> Layer {
+        let id = self.0.next_layer.get();
+        self.0.next_layer.set(id + 1);
+        self.0.layers.borrow_mut().push(Slot {
+            id,
+            draw: None,
+            focusable: false,
+            ime: None,
+            bounds: None,
+        });
+        Layer {
+            context: Rc::downgrade(&self.0),
+            id,
+        }
+    }
+
+    /// The `0x00RRGGBB` color the layers are drawn on, in place of the background of the window's theme.
+    pub fn set_background(&self, color: u32) {
+        self.0.background.set(Some(color));
+        self.request_redraw();
+    }
+
+    /// The color the layers are drawn on.
+    pub fn background(&self) -> u32 {
+        let background = self.0.background.get();
+        background.unwrap_or_else(|| self.0.theme.borrow().palette.background)
+    }
+
+    /// The theme to draw with: the one this context was made with by `with_theme`, or else the `theme` prop of
+    /// the window, or the built-in theme of the system's color scheme.
+    pub fn theme(&self) -> Rc<Theme> {
+        match &self.1 {
+            Some(theme) => theme.clone(),
+            None => self.0.theme.borrow().clone(),
+        }
+    }
+
+    /// The same context, but with `theme` for the components it is passed to, e.g. to give one part of the
+    /// window a darker look. The rest of the window keeps its theme.
+    pub fn with_theme(&self, theme: Theme) -> Self {
+        Self(self.0.clone(), Some(Rc::new(theme)))
+    }
+
+    /// Whether the system is in light or dark mode, as of when the window was shown or the last change.
+    pub fn color_scheme(&self) -> ColorScheme {
+        self.0.color_scheme.get()
+    }
+
+    /// The root of the layout of the window's content, laid out to the window's size before each frame.
+    /// Needs the `layout` feature.
+    #[cfg(feature = "layout")]
+    pub fn set_layout(&self, root: Option<&LayoutNode>) {
+        let mut layout = self.0.layout.borrow_mut();
+        if let Some(old) = layout.take() {
+            old.set_window(None);
+        }
+        if let Some(root) = root {
+            root.set_window(Some((self.0.frame_clock.clone(), self.0.surface.clone())));
+        }
+        *layout = root.cloned();
+    }
+
+    #[cfg(feature = "layout")]
+    pub fn layout(&self) -> Option<LayoutNode> {
+        self.0.layout.borrow().clone()
+    }
+
+    /// Redraw every layer with the next frame.
+    pub fn request_redraw(&self) {
+        self.0.surface.invalidate();
+        self.0.frame_clock.request_redraw();
+    }
+
+    /// Repaint `rect` of the window, in physical pixels, with the next frame, e.g. for the draw callback of a
+    /// layer that doesn't paint through the components of the crate. The layers there are drawn again.
+    pub fn damage(&self, rect: Rect) {
+        self.0.surface.damage(rect);
+        self.0.frame_clock.request_redraw();
+    }
+
+    /// Take the keyboard focus away from the focused layer, if any.
+    pub fn clear_focus(&self) {
+        set_focus(&self.0, None);
+    }
+
+    /// What the focused layer asks of the input method, see `Layer::set_ime`.
+    pub fn ime(&self) -> Option<ImeRequest> {
+        focused_ime(&self.0)
+    }
+
+    // The window applies IME requests with `handler`, in place of its `ime_enabled` and `ime_cursor_area` props
+    #[cfg_attr(not(all(windows, not(headless))), allow(dead_code))]
+    pub(crate) fn set_ime_handler(&self, handler: impl Fn(Option<ImeRequest>) + 'static) {
+        self.0.ime_handler.replace(Some(Box::new(handler)));
+    }
+
+    #[cfg_attr(headless, allow(dead_code))]
+    pub(crate) fn set_scale_factor(&self, scale_factor: f64) {
+        self.0.surface.set_scale_factor(scale_factor);
+    }
+
+    #[cfg_attr(
+        all(any(target_os = "macos", target_os = "ios"), not(headless)),
+        allow(dead_code)
+    )]
+    pub(crate) fn dispatch(&self, event: &WindowEvent) {
+        match event {
+            // Tab moves the focus through the focusable layers, Shift+Tab backwards
+            WindowEvent::KeyDown(key)
+                if key.logical_key == Key::Named(NamedKey::Tab)
+                    && !(key.modifiers.control()
+                        || key.modifiers.alt()
+                        || key.modifiers.super_key()) =>
+            {
+                self.move_focus(key.modifiers.shift());
+            }
+            // The layer under the pointer takes the focus when it handles the press
+            WindowEvent::PointerDown(_) => self.clear_focus(),
+            WindowEvent::ColorSchemeChanged(scheme) => self.set_color_scheme(*scheme),
+            _ => (),
+        }
+        let mut streams = self.0.streams.borrow_mut();
+        // Streams nobody reads anymore are let go
+        streams.retain(|events| !events.is_abandoned());
+        streams.iter().for_each(|events| events.push(event.clone()));
+    }
+
+    fn move_focus(&self, backwards: bool) {
+        let focusable = self
+            .0
+            .layers
+            .borrow()
+            .iter()
+            .filter(|slot| slot.focusable)
+            .map(|slot| slot.id)
+            .collect::<Vec<_>>();
+        if focusable.is_empty() {
+            return;
+        }
+        let current = self
+            .0
+            .focused
+            .get()
+            .and_then(|focused| focusable.iter().position(|id| *id == focused));
+        let next = match (current, backwards) {
+            (None, false) => 0,
+            (None, true) => focusable.len() - 1,
+            (Some(i), false) => (i + 1) % focusable.len(),
+            (Some(i), true) => (i + focusable.len() - 1) % focusable.len(),
+        };
+        set_focus(&self.0, Some(focusable[next]));
+    }
+
+    // The `theme` prop of the window
+    pub(crate) fn set_theme(&self, theme: Option<&Theme>) {
+        if self.0.theme_prop.borrow().as_ref() != theme {
+            self.0.theme_prop.replace(theme.cloned());
+            self.update_theme();
+        }
+    }
+
+    fn set_color_scheme(&self, scheme: ColorScheme) {
+        if self.0.color_scheme.replace(scheme) != scheme {
+            self.update_theme();
+        }
+    }
+
+    fn update_theme(&self) {
+        let theme = match &*self.0.theme_prop.borrow() {
+            Some(theme) => theme.clone(),
+            None => Theme::for_scheme(self.0.color_scheme.get()),
+        };
+        if **self.0.theme.borrow() != theme {
+            self.0.theme.replace(Rc::new(theme));
+            self.request_redraw();
+        }
+    }
+
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        if self.0.enabled.replace(enabled) != enabled {
+            self.request_redraw();
+        }
+    }
+
+    // A window was created for the context, or the existing one was destroyed
+    pub(crate) fn set_closed(&self, closed: bool) {
+        // Windows that can't report changes of the color scheme pick up the current one when they are shown
+        if self.0.closed.replace(closed) && !closed {
+            self.set_color_scheme(system_color_scheme());
+        }
+        if closed {
+            self.0.ime_handler.take();
+        }
+        self.0
+            .streams
+            .borrow()
+            .iter()
+            .for_each(|events| events.set_closed(closed));
+    }
+}
+
+/// The content of a component in a `WindowContext`, removed from the window when dropped.
+pub struct Layer {
+    context: Weak<Inner>,
+    id: u64,
+}
+
+impl Layer {
+    /// Replace the draw callback, which receives the whole window in physical pixels. The window redraws
+    /// with the next frame.
+    pub fn set_draw(&self, draw: impl FnMut(&mut PixelBuffer) + 'static) {
+        let Some(inner) = self.context.upgrade() else {
+            return;
+        };
+        if let Some(slot) = find(&mut inner.layers.borrow_mut(), self.id) {
+            slot.draw = Some(Box::new(draw));
+        }
+        damage_layer(&inner, self.id);
+    }
+
+    /// Let the layer take the keyboard focus, by `focus` or with Tab. Layers aren't focusable by default.
+    pub fn set_focusable(&self, focusable: bool) {
+        let Some(inner) = self.context.upgrade() else {
+            return;
+        };
+        if let Some(slot) = find(&mut inner.layers.borrow_mut(), self.id) {
+            slot.focusable = focusable;
+        }
+        if !focusable && inner.focused.get() == Some(self.id) {
+            set_focus(&inner, None);
+        }
+    }
+
+    /// Ask the input method for `ime` while the layer has the focus, e.g. to place the candidate window next
+    /// to the caret of a text field or to turn the input method off for a password. `None` leaves the input
+    /// method to the `ime_enabled` and `ime_cursor_area` props of the window.
+    pub fn set_ime(&self, ime: Option<ImeRequest>) {
+        let Some(inner) = self.context.upgrade() else {
+            return;
+        };
+        let old = focused_ime(&inner);
+        if let Some(slot) = find(&mut inner.layers.borrow_mut(), self.id) {
+            slot.ime = ime;
+        }
+        apply_ime(&inner, old);
+    }
+
+    /// Take the keyboard focus, if the layer is focusable.
+    pub fn focus(&self) {
+        let Some(inner) = self.context.upgrade() else {
+            return;
+        };
+        let focusable =
+            find(&mut inner.layers.borrow_mut(), self.id).is_some_and(|slot| slot.focusable);
+        if focusable {
+            set_focus(&inner, Some(self.id));
+        }
+    }
+
+    /// Whether the layer has the keyboard focus.
+    pub fn is_focused(&self) -> bool {
+        self.context
+            .upgrade()
+            .is_some_and(|inner| inner.focused.get() == Some(self.id))
+    }
+
+    /// Redraw the layer with the next frame, along with what lies below and above it.
+    pub fn request_redraw(&self) {
+        if let Some(inner) = self.context.upgrade() {
+            damage_layer(&inner, self.id);
+        }
+    }
+
+    // Redraws the layer without keeping it
+    #[cfg(feature = "canvas")]
+    pub(crate) fn redraw_handle(&self) -> RedrawHandle {
+        RedrawHandle {
+            context: self.context.clone(),
+            id: self.id,
+        }
+    }
+}
+
+// Asks a layer for a redraw, like `Layer::request_redraw`, as long as the layer exists
+#[cfg(feature = "canvas")]
+#[derive(Clone)]
+pub(crate) struct RedrawHandle {
+    context: Weak<Inner>,
+    id: u64,
+}
+
+#[cfg(feature = "canvas")]
+impl RedrawHandle {
+    pub(crate) fn request_redraw(&self) {
+        if let Some(inner) = self.context.upgrade() {
+            damage_layer(&inner, self.id);
+        }
+    }
+}
+
+impl Drop for Layer {
+    fn drop(&mut self) {
+        if let Some(inner) = self.context.upgrade() {
+            let ime = focused_ime(&inner);
+            damage_layer(&inner, self.id);
+            inner.layers.borrow_mut().retain(|slot| slot.id != self.id);
+            if inner.focused.get() == Some(self.id) {
+                inner.focused.set(None);
+            }
+            apply_ime(&inner, ime);
+        }
+    }
+}
+
+fn find(layers: &mut [Slot], id: u64) -> Option<&mut Slot> {
+    layers.iter_mut().find(|slot| slot.id == id)
+}
+
+fn set_focus(inner: &Inner, focused: Option<u64>) {
+    let old = focused_ime(inner);
+    let unfocused = inner.focused.replace(focused);
+    if unfocused != focused {
+        // Layers show whether they have the focus
+        unfocused
+            .into_iter()
+            .chain(focused)
+            .for_each(|id| damage_layer(inner, id));
+        apply_ime(inner, old);
+    }
+}
+
+// Repaint the area of a layer with the next frame. Where the layer paints next is found out while drawing
+fn damage_layer(inner: &Inner, id: u64) {
+    let bounds = find(&mut inner.layers.borrow_mut(), id).map(|slot| slot.bounds);
+    match bounds {
+        Some(Some(bounds)) => {
+            inner.surface.damage(bounds);
+            inner.surface.damage_nothing();
+        }
+        Some(None) => inner.surface.invalidate(),
+        None => (),
+    }
+    inner.frame_clock.request_redraw();
+}
+
+fn focused_ime(inner: &Inner) -> Option<ImeRequest> {
+    let focused = inner.focused.get()?;
+    find(&mut inner.layers.borrow_mut(), focused).and_then(|slot| slot.ime)
+}
+
+// Hand the IME request of the focused layer to the window, if it isn't `old` anymore
+fn apply_ime(inner: &Inner, old: Option<ImeRequest>) {
+    let ime = focused_ime(inner);
+    if ime != old
+        && let Some(handler) = &*inner.ime_handler.borrow()
+    {
+        handler(ime);
+    }
+}
+
+fn draw_layers(inner: &Inner, buffer: &mut PixelBuffer) {
+    let background = inner.background.get();
+    let background = background.unwrap_or_else(|| inner.theme.borrow().palette.background);
+    #[cfg(feature = "layout")]
+    {
+        let layout = inner.layout.borrow().clone();
+        if let Some(root) = layout {
+            let scale_factor = buffer.scale_factor as f32;
+            root.compute(
+                buffer.width as f32 / scale_factor,
+                buffer.height as f32 / scale_factor,
+            );
+        }
+    }
+    // Each pass repaints its areas from the background up, and the next one the areas layers moved to
+    let mut damage = take(&mut buffer.damage);
+    let mut pass = damage.clone();
+    for _ in 0..MAX_PASSES {
+        if pass.is_empty() {
+            break;
+        }
+        pass.iter()
+            .for_each(|rect| buffer.fill_rect(*rect, background));
+        buffer.damage = pass;
+        let moved = draw_pass(inner, buffer)
+            .into_iter()
+            .filter(|bounds| !damage.iter().any(|rect| rect.contains(bounds)))
+            .collect::<Vec<_>>();
+        damage.extend(moved.iter().copied());
+        pass = moved;
+    }
+    buffer.damage = damage;
+}
+
+// Draw the layers that cover the damaged areas of `buffer`, returning the areas they newly painted
+fn draw_pass(inner: &Inner, buffer: &mut PixelBuffer) -> Vec<Rect> {
+    // Layers may come and go while drawing, so each one is looked up again
+    let ids = inner
+        .layers
+        .borrow()
+        .iter()
+        .map(|slot| slot.id)
+        .collect::<Vec<_>>();
+    let mut painted = Vec::new();
+    for id in ids {
+        let draw = find(&mut inner.layers.borrow_mut(), id)
+            .filter(|slot| match slot.bounds {
+                // A layer that paints nothing may start anywhere
+                Some(bounds) if !bounds.is_empty() => buffer
+                    .damage
+                    .iter()
+                    .any(|rect| !rect.intersection(&bounds).is_empty()),
+                _ => true,
+            })
+            .and_then(|slot| Some((slot.draw.take()?, slot.bounds)));
+        let Some((mut draw, old)) = draw else {
+            continue;
+        };
+        // A callback that doesn't tell where it paints may write anywhere, over layers above it that aren't
+        // drawn again, so the pixels outside the damage are put back
+        let whole = buffer.bounds();
+        let kept = (old.is_none() && !buffer.damage.iter().any(|rect| rect.contains(&whole)))
+            .then(|| buffer.pixels.to_vec());
+        buffer.painted = None;
+        draw(buffer);
+        let bounds = buffer.painted.take();
+        if let (Some(mut kept), None) = (kept, bounds) {
+            for rect in &buffer.damage {
+                for y in rect.y..rect.y + rect.height {
+                    let row = (y * whole.width + rect.x) as usize
+                        ..(y * whole.width + rect.x + rect.width) as usize;
+                    kept[row.clone()].copy_from_slice(&buffer.pixels[row]);
+                }
+            }
+            buffer.pixels.copy_from_slice(&kept);
+        }
+        if let Some(slot) = find(&mut inner.layers.borrow_mut(), id) {
+            // Where it painted before is repainted already, only new areas need another pass
+            if slot.bounds != bounds {
+                painted.extend(bounds.filter(|bounds| !bounds.is_empty()));
+            }
+            slot.bounds = bounds;
+            // Keep a callback set while drawing
+            if slot.draw.is_none() {
+                slot.draw = Some(draw);
+            }
+        }
+    }
+    painted
+}
@@ -0,0 +1,117 @@
+use std::{cell::Cell, path::PathBuf, rc::Rc};
+
+/// The data a drag carries.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct DragData {
+    pub files: Vec<PathBuf>,
+    pub uris: Vec<String>,
+    pub text: Option<String>,
+    /// Any other formats with their raw bytes, by clipboard format name on Windows.
+    pub formats: Vec<(String, Vec<u8>)>,
+}
+
+impl DragData {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+            && self.uris.is_empty()
+            && self.text.is_none()
+            && self.formats.is_empty()
+    }
+}
+
+/// What a drag offers, known before any of its data is read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct DragFormats {
+    pub files: bool,
+    pub uris: bool,
+    pub text: bool,
+    /// The names of the other formats, as in `DragData::formats`.
+    pub formats: Vec<String>,
+}
+
+impl From<&DragData> for DragFormats {
+    fn from(data: &DragData) -> Self {
+        Self {
+            files: !data.files.is_empty(),
+            uris: !data.uris.is_empty(),
+            text: data.text.is_some(),
+            formats: data.formats.iter().map(|(name, _)| name.clone()).collect(),
+        }
+    }
+}
+
+/// What happens to the data when it is dropped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum DropEffect {
+    /// The drop is rejected.
+    #[default]
+    None,
+    Copy,
+    Move,
+    Link,
+}
+
+/// The window's answer to a drag hovering over it, shared with the platform.
+///
+/// Drags start out rejected. Since the answer is given asynchronously, it takes effect from the next
+/// movement of the drag on.
+#[derive(Clone, Debug, Default)]
+pub struct DropResponse(Rc<Cell<DropEffect>>);
+
+impl DropResponse {
+    pub fn accept(&self, effect: DropEffect) {
+        self.0.set(effect);
+    }
+
+    pub fn reject(&self) {
+        self.0.set(DropEffect::None);
+    }
+
+    pub fn effect(&self) -> DropEffect {
+        self.0.get()
+    }
+}
+
+impl PartialEq for DropResponse {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A drag over a window, delivered by the `on_drag` event of `window`.
+///
+/// Positions are in logical units relative to the client area.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum DragEvent {
+    /// A drag entered the window. Accept it through `response` to allow a drop.
+    ///
+    /// Only the formats on `offered` are known while hovering, sources that render their data on request
+    /// aren't asked for it until the drop.
+    Enter {
+        x: f64,
+        y: f64,
+        offered: DragFormats,
+        #[cfg_attr(feature = "record", serde(skip))]
+        response: DropResponse,
+    },
+    /// The drag moved. `response` is the same as the one of `Enter`, the answer may change at any time.
+    Move {
+        x: f64,
+        y: f64,
+        #[cfg_attr(feature = "record", serde(skip))]
+        response: DropResponse,
+    },
+    /// The drag left the window or was cancelled, or the drop was rejected.
+    Leave,
+    /// The data was dropped with the accepted `effect`.
+    Drop {
+        x: f64,
+        y: f64,
+        data: DragData,
+        effect: DropEffect,
+    },
+}
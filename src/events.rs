@@ -0,0 +1,132 @@
+use {
+    crate::{
+        drag_drop::DragEvent, gesture::GestureEvent, keyboard::KeyEvent, pointer::PointerEvent,
+        scroll::ScrollEvent, text_input::TextInputEvent, theme::ColorScheme,
+    },
+    compo::prelude::Cancellable,
+    std::{cell::RefCell, collections::VecDeque, future::poll_fn, rc::Rc, task::Poll},
+};
+
+/// Everything that happens to a window, as delivered by `WindowEvents`.
+///
+/// The input variants carry the same values as the matching `on_*` event props of `window`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowEvent {
+    /// The user asked to close the window, e.g. with the close button. The window closes right after, and
+    /// the request can't be vetoed; rendering the window again with `visible` set shows a new one.
+    CloseRequested,
+    /// The client area changed its size, in pixels.
+    Resized {
+        width: i32,
+        height: i32,
+    },
+    /// The window gained (`true`) or lost (`false`) the keyboard focus.
+    Focused(bool),
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+    TextInput(TextInputEvent),
+    PointerEnter(PointerEvent),
+    PointerLeave(PointerEvent),
+    PointerMove(PointerEvent),
+    PointerDown(PointerEvent),
+    PointerUp(PointerEvent),
+    Scroll(ScrollEvent),
+    Gesture(GestureEvent),
+    Drag(DragEvent),
+    /// A key binding of the window's keymap or of `Keymap::global()` triggered this action. The key presses
+    /// of the binding don't show up as `KeyDown`.
+    Action(String),
+    /// The system switched between light and dark mode. Windows whose `theme` prop is `None` follow it.
+    ColorSchemeChanged(ColorScheme),
+}
+
+// Events nobody reads are dropped beyond this, oldest first
+const CAPACITY: usize = 4096;
+
+#[derive(Default)]
+struct Inner {
+    queue: VecDeque<WindowEvent>,
+    closed: bool,
+}
+
+/// A buffered stream of the events of a window, to be awaited inside a component's async body.
+///
+/// Pass it to the `events` prop of `window`. Unlike the `on_*` event props, nothing is lost while the
+/// component is busy between two `next()` calls: events queue up until they are read.
+#[derive(Clone, Default)]
+pub struct WindowEvents(Rc<RefCell<Inner>>);
+
+impl WindowEvents {
+    /// Wait for the next event. Returns `None` once the window is gone and all its events are read.
+    pub async fn next(&self) -> Option<WindowEvent> {
+        poll_fn(|_| match self.try_next() {
+            Some(event) => Poll::Ready(Some(event)),
+            None if self.is_closed() => Poll::Ready(None),
+            None => Poll::Pending,
+        })
+        .await
+    }
+
+    /// Like `next`, but gives up with `None` as soon as `cancellable` is cancelled.
+    pub async fn next_or_cancel(&self, cancellable: &Cancellable) -> Option<WindowEvent> {
+        poll_fn(|_| {
+            if cancellable.is_cancelled() {
+                return Poll::Ready(None);
+            }
+            match self.try_next() {
+                Some(event) => Poll::Ready(Some(event)),
+                None if self.is_closed() => Poll::Ready(None),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    // Wait for the next event, also while the window is gone, until `alive` returns `false`
+    #[cfg(feature = "widgets")]
+    pub(crate) async fn next_while(&self, alive: impl Fn() -> bool) -> Option<WindowEvent> {
+        poll_fn(|_| {
+            if !alive() {
+                return Poll::Ready(None);
+            }
+            match self.try_next() {
+                Some(event) => Poll::Ready(Some(event)),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// Take the next event if one is queued, without waiting.
+    pub fn try_next(&self) -> Option<WindowEvent> {
+        self.0.borrow_mut().queue.pop_front()
+    }
+
+    /// Whether the window this stream was attached to is gone.
+    pub fn is_closed(&self) -> bool {
+        self.0.borrow().closed
+    }
+
+    pub(crate) fn push(&self, event: WindowEvent) {
+        let mut inner = self.0.borrow_mut();
+        if inner.queue.len() == CAPACITY {
+            inner.queue.pop_front();
+        }
+        inner.queue.push_back(event);
+    }
+
+    // Nobody but the window holds the stream anymore
+    #[cfg_attr(
+        all(any(target_os = "macos", target_os = "ios"), not(headless)),
+        allow(dead_code)
+    )]
+    pub(crate) fn is_abandoned(&self) -> bool {
+        Rc::strong_count(&self.0) == 1
+    }
+
+    // A window was created for the stream, or the existing one was destroyed
+    pub(crate) fn set_closed(&self, closed: bool) {
+        self.0.borrow_mut().closed = closed;
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    cell::RefCell,
+    future::poll_fn,
+    mem::take,
+    rc::Rc,
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+// Used until the backend knows the display's refresh rate
+const DEFAULT_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// The timing of a frame, as delivered by `on_redraw` and `FrameClock::next_frame`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameTime {
+    /// When the frame is due, aligned to the display's refresh. Animations should be computed for this time.
+    pub time: Instant,
+    /// Since the previous frame, zero for the first one.
+    pub delta: Duration,
+    /// Counts frames from 1.
+    pub number: u64,
+}
+
+struct Inner {
+    interval: Duration,
+    visible: bool,
+    redraw_requested: bool,
+    // Someone awaits `next_frame`
+    waiting: bool,
+    last: Option<FrameTime>,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_INTERVAL,
+            visible: false,
+            redraw_requested: false,
+            waiting: false,
+            last: None,
+        }
+    }
+}
+
+/// Schedules the frames of a `window`.
+///
+/// Pass it to the `frame_clock` prop of `window`. Frames are only produced when wanted, i.e. after
+/// `request_redraw` or while something awaits `next_frame`, and never while the window is hidden. Each frame
+/// is due at the next refresh of the display, so any number of requests before it make a single frame.
+#[derive(Clone, Default)]
+pub struct FrameClock(Rc<RefCell<Inner>>);
+
+impl FrameClock {
+    /// Ask for a redraw with the next frame: the window's `surface` draws again and `on_redraw` fires.
+    pub fn request_redraw(&self) {
+        self.0.borrow_mut().redraw_requested = true;
+    }
+
+    /// Wait for the next frame of the window. Waits while the window is hidden.
+    pub async fn next_frame(&self) -> FrameTime {
+        let number = self.last_number();
+        poll_fn(|_| {
+            let mut inner = self.0.borrow_mut();
+            match inner.last {
+                Some(frame) if frame.number > number => Poll::Ready(frame),
+                _ => {
+                    inner.waiting = true;
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// The time between two frames, from the refresh rate of the window's display.
+    pub fn interval(&self) -> Duration {
+        self.0.borrow().interval
+    }
+
+    /// The last frame, if there was one.
+    pub fn last_frame(&self) -> Option<FrameTime> {
+        self.0.borrow().last
+    }
+
+    fn last_number(&self) -> u64 {
+        self.0.borrow().last.map_or(0, |frame| frame.number)
+    }
+
+    #[cfg_attr(headless, allow(dead_code))]
+    pub(crate) fn set_interval(&self, interval: Duration) {
+        self.0.borrow_mut().interval = interval;
+    }
+
+    pub(crate) fn set_visible(&self, visible: bool) {
+        self.0.borrow_mut().visible = visible;
+    }
+
+    // When the next frame is due, if one is wanted
+    #[cfg_attr(
+        all(any(target_os = "macos", target_os = "ios"), not(headless)),
+        allow(dead_code)
+    )]
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.due(Instant::now())
+    }
+
+    fn due(&self, now: Instant) -> Option<Instant> {
+        let inner = self.0.borrow();
+        if !inner.visible || !(inner.redraw_requested || inner.waiting) {
+            return None;
+        }
+        // A frame wanted after a pause is due right away
+        Some(inner.last.map_or(now, |last| last.time + inner.interval))
+    }
+
+    // Wait for the next frame, for backends without an input loop to drive the clock. `None` once `active` turns
+    // false
+    #[cfg_attr(
+        not(all(any(target_os = "macos", target_os = "ios"), not(headless))),
+        allow(dead_code)
+    )]
+    pub(crate) async fn wait(&self, active: impl Fn() -> bool) -> Option<(FrameTime, bool)> {
+        poll_fn(|_| {
+            if !active() {
+                return Poll::Ready(None);
+            }
+            match self.tick(Instant::now()) {
+                Some(tick) => Poll::Ready(Some(tick)),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    // Produce the frame if it is due, along with whether a redraw was requested for it
+    pub(crate) fn tick(&self, now: Instant) -> Option<(FrameTime, bool)> {
+        let due = self.due(now).filter(|&due| now >= due)?;
+        let mut inner = self.0.borrow_mut();
+        // Frames keep to the refresh grid, which starts over after a pause
+        let time = if now - due < inner.interval { due } else { now };
+        let frame = FrameTime {
+            time,
+            delta: inner.last.map_or(Duration::ZERO, |last| time - last.time),
+            number: inner.last.map_or(1, |last| last.number + 1),
+        };
+        inner.last = Some(frame);
+        inner.waiting = false;
+        Some((frame, take(&mut inner.redraw_requested)))
+    }
+}
@@ -0,0 +1,677 @@
+use {
+    crate::pointer::{PointerEvent, PointerType},
+    std::time::{Duration, Instant},
+};
+
+/// Thresholds of the gesture recognizer, distances are in logical units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureConfig {
+    /// How far a finger may travel before a tap or long press turns into a pan.
+    pub touch_slop: f64,
+    /// How long a finger has to rest before it counts as a long press.
+    pub long_press_timeout: Duration,
+    /// Longest gap between the taps of a double tap.
+    pub double_tap_timeout: Duration,
+    /// Farthest distance between the taps of a double tap.
+    pub double_tap_slop: f64,
+    /// How much the distance between two fingers has to change before a pinch begins.
+    pub pinch_slop: f64,
+    /// How far two fingers have to turn before a rotation begins, in radians.
+    pub rotate_slop: f64,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        // Follows the defaults of Android's `ViewConfiguration`
+        Self {
+            touch_slop: 8.0,
+            long_press_timeout: Duration::from_millis(500),
+            double_tap_timeout: Duration::from_millis(300),
+            double_tap_slop: 100.0,
+            pinch_slop: 16.0,
+            rotate_slop: 5f64.to_radians(),
+        }
+    }
+}
+
+/// The stage of a continuous gesture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum GesturePhase {
+    Began,
+    Changed,
+    Ended,
+}
+
+/// A gesture recognized from touch input, delivered by the `on_gesture` event of `window`.
+///
+/// Positions are in logical units relative to the client area. For multi-finger gestures they are the
+/// centroid of the fingers. iOS doesn't deliver touches yet, so no gestures are recognized there.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum GestureEvent {
+    /// A short touch without movement. The second tap of a double tap is reported as `DoubleTap` instead.
+    Tap {
+        x: f64,
+        y: f64,
+    },
+    DoubleTap {
+        x: f64,
+        y: f64,
+    },
+    /// A touch that rested for `GestureConfig::long_press_timeout`. No tap follows when it lifts.
+    LongPress {
+        x: f64,
+        y: f64,
+    },
+    /// Fingers dragged by `dx` and `dy` since the previous pan event.
+    Pan {
+        phase: GesturePhase,
+        x: f64,
+        y: f64,
+        dx: f64,
+        dy: f64,
+    },
+    /// Two or more fingers spread apart (`scale` above 1) or closed in, relative to the previous pinch event.
+    Pinch {
+        phase: GesturePhase,
+        x: f64,
+        y: f64,
+        scale: f64,
+    },
+    /// Two fingers turned by `angle` radians since the previous rotate event, clockwise on screen.
+    Rotate {
+        phase: GesturePhase,
+        x: f64,
+        y: f64,
+        angle: f64,
+    },
+}
+
+#[derive(Clone, Copy)]
+struct Touch {
+    id: u32,
+    x: f64,
+    y: f64,
+}
+
+#[derive(Clone, Copy)]
+struct TapCandidate {
+    x: f64,
+    y: f64,
+    deadline: Instant,
+}
+
+/// Turns touch pointer events into `GestureEvent`s.
+///
+/// Feed it the pointer events of one window in order. Long presses fire on a timer, so call `tick` once
+/// `deadline` has passed. The recognizer never reads the clock itself, which keeps it deterministic for
+/// synthetic touch sequences.
+#[derive(Default)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    touches: Vec<Touch>,
+    tap: Option<TapCandidate>,
+    last_tap: Option<(Instant, f64, f64)>,
+    // Centroid, spread and angle the running gestures are measured against
+    origin: (f64, f64),
+    span: f64,
+    angle: f64,
+    panning: bool,
+    pinching: bool,
+    rotating: bool,
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: GestureConfig) {
+        self.config = config;
+    }
+
+    /// When the pending long press fires, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.tap.map(|t| t.deadline)
+    }
+
+    /// Forget all touches without reporting anything, e.g. when the window loses its input.
+    pub fn reset(&mut self) {
+        let last_tap = self.last_tap;
+        *self = Self::new(self.config);
+        self.last_tap = last_tap;
+    }
+
+    /// A pointer went down. Pointers other than touch are ignored.
+    pub fn pointer_down(&mut self, event: &PointerEvent, time: Instant) -> Vec<GestureEvent> {
+        if event.pointer_type != PointerType::Touch {
+            return Vec::new();
+        }
+        let mut gestures = Vec::new();
+        self.end_multi_touch(&mut gestures);
+        self.touches.retain(|t| t.id != event.pointer_id);
+        self.touches.push(Touch {
+            id: event.pointer_id,
+            x: event.x,
+            y: event.y,
+        });
+        self.tap = match self.touches.len() {
+            1 => Some(TapCandidate {
+                x: event.x,
+                y: event.y,
+                deadline: time + self.config.long_press_timeout,
+            }),
+            _ => None,
+        };
+        self.rebase();
+        gestures
+    }
+
+    /// A pointer moved. Pointers other than touch, and touches that aren't down, are ignored.
+    pub fn pointer_move(&mut self, event: &PointerEvent, _time: Instant) -> Vec<GestureEvent> {
+        let Some(touch) = self
+            .touches
+            .iter_mut()
+            .find(|t| event.pointer_type == PointerType::Touch && t.id == event.pointer_id)
+        else {
+            return Vec::new();
+        };
+        touch.x = event.x;
+        touch.y = event.y;
+
+        if let Some(tap) = self.tap
+            && (event.x - tap.x).hypot(event.y - tap.y) > self.config.touch_slop
+        {
+            self.tap = None;
+        }
+
+        let mut gestures = Vec::new();
+        let (x, y) = self.centroid();
+        if self.touches.len() >= 2 {
+            let span = self.spread();
+            if self.span > 0.0
+                && (self.pinching || (span - self.span).abs() > self.config.pinch_slop)
+            {
+                let phase = if self.pinching {
+                    GesturePhase::Changed
+                } else {
+                    GesturePhase::Began
+                };
+                self.pinching = true;
+                gestures.push(GestureEvent::Pinch {
+                    phase,
+                    x,
+                    y,
+                    scale: span / self.span,
+                });
+                self.span = span;
+            }
+
+            let angle = self.turn();
+            // Wrap into `-PI..=PI`, so crossing the negative x axis isn't a full turn
+            let delta = (angle - self.angle + std::f64::consts::PI)
+                .rem_euclid(std::f64::consts::TAU)
+                - std::f64::consts::PI;
+            if self.rotating || delta.abs() > self.config.rotate_slop {
+                let phase = if self.rotating {
+                    GesturePhase::Changed
+                } else {
+                    GesturePhase::Began
+                };
+                self.rotating = true;
+                gestures.push(GestureEvent::Rotate {
+                    phase,
+                    x,
+                    y,
+                    angle: delta,
+                });
+                self.angle = angle;
+            }
+        }
+
+        // The centroid also moves while pinching or rotating, which isn't a pan
+        let (dx, dy) = (x - self.origin.0, y - self.origin.1);
+        if self.pinching || self.rotating {
+            self.end_pan(&mut gestures);
+            self.origin = (x, y);
+        } else if self.panning || (self.tap.is_none() && dx.hypot(dy) > self.config.touch_slop) {
+            let phase = if self.panning {
+                GesturePhase::Changed
+            } else {
+                GesturePhase::Began
+            };
+            self.panning = true;
+            self.origin = (x, y);
+            gestures.push(GestureEvent::Pan {
+                phase,
+                x,
+                y,
+                dx,
+                dy,
+            });
+        }
+        gestures
+    }
+
+    /// A pointer went up. Pointers other than touch are ignored.
+    pub fn pointer_up(&mut self, event: &PointerEvent, time: Instant) -> Vec<GestureEvent> {
+        let Some(index) = self
+            .touches
+            .iter()
+            .position(|t| event.pointer_type == PointerType::Touch && t.id == event.pointer_id)
+        else {
+            return Vec::new();
+        };
+
+        let mut gestures = Vec::new();
+        self.end_multi_touch(&mut gestures);
+        self.touches.remove(index);
+
+        if self.touches.is_empty() {
+            self.end_pan(&mut gestures);
+            if self.tap.take().is_some() {
+                gestures.push(self.tap_at(event.x, event.y, time));
+            }
+        }
+        self.rebase();
+        gestures
+    }
+
+    /// The system took a touch away, e.g. for a system gesture. Nothing that touch started is reported
+    /// anymore, and the other touches start over.
+    pub fn cancel(&mut self, event: &PointerEvent) {
+        if event.pointer_type == PointerType::Touch {
+            self.reset();
+        }
+    }
+
+    /// Fire the pending long press once its deadline has passed.
+    pub fn tick(&mut self, time: Instant) -> Option<GestureEvent> {
+        let tap = self.tap.filter(|t| time >= t.deadline)?;
+        self.tap = None;
+        self.last_tap = None;
+        Some(GestureEvent::LongPress { x: tap.x, y: tap.y })
+    }
+
+    fn tap_at(&mut self, x: f64, y: f64, time: Instant) -> GestureEvent {
+        match self.last_tap.take() {
+            Some((last, last_x, last_y))
+                if time.duration_since(last) <= self.config.double_tap_timeout
+                    && (x - last_x).hypot(y - last_y) <= self.config.double_tap_slop =>
+            {
+                GestureEvent::DoubleTap { x, y }
+            }
+            _ => {
+                self.last_tap = Some((time, x, y));
+                GestureEvent::Tap { x, y }
+            }
+        }
+    }
+
+    fn end_pan(&mut self, gestures: &mut Vec<GestureEvent>) {
+        if self.panning {
+            self.panning = false;
+            let (x, y) = self.origin;
+            gestures.push(GestureEvent::Pan {
+                phase: GesturePhase::Ended,
+                x,
+                y,
+                dx: 0.0,
+                dy: 0.0,
+            });
+        }
+    }
+
+    // The number of fingers is about to change, which ends pinches and rotations
+    fn end_multi_touch(&mut self, gestures: &mut Vec<GestureEvent>) {
+        let (x, y) = self.centroid();
+        if self.pinching {
+            self.pinching = false;
+            gestures.push(GestureEvent::Pinch {
+                phase: GesturePhase::Ended,
+                x,
+                y,
+                scale: 1.0,
+            });
+        }
+        if self.rotating {
+            self.rotating = false;
+            gestures.push(GestureEvent::Rotate {
+                phase: GesturePhase::Ended,
+                x,
+                y,
+                angle: 0.0,
+            });
+        }
+    }
+
+    // Measure against the current fingers, so adding or lifting one doesn't make the gestures jump
+    fn rebase(&mut self) {
+        self.origin = self.centroid();
+        self.span = self.spread();
+        self.angle = self.turn();
+    }
+
+    fn centroid(&self) -> (f64, f64) {
+        let count = self.touches.len().max(1) as f64;
+        let (x, y) = self
+            .touches
+            .iter()
+            .fold((0.0, 0.0), |(x, y), t| (x + t.x, y + t.y));
+        (x / count, y / count)
+    }
+
+    // Average distance of the fingers from their centroid
+    fn spread(&self) -> f64 {
+        if self.touches.len() < 2 {
+            return 0.0;
+        }
+        let (x, y) = self.centroid();
+        let total: f64 = self.touches.iter().map(|t| (t.x - x).hypot(t.y - y)).sum();
+        total / self.touches.len() as f64
+    }
+
+    // Angle of the line through the first two fingers
+    fn turn(&self) -> f64 {
+        match self.touches.as_slice() {
+            [a, b, ..] => (b.y - a.y).atan2(b.x - a.x),
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{keyboard::Modifiers, pointer::PointerButton},
+    };
+
+    fn touch(pointer_id: u32, x: f64, y: f64) -> PointerEvent {
+        PointerEvent {
+            pointer_id,
+            pointer_type: PointerType::Touch,
+            x,
+            y,
+            button: Some(PointerButton::Primary),
+            click_count: 1,
+            modifiers: Modifiers::NONE,
+            pressure: 0.5,
+            tilt_x: 0.0,
+            tilt_y: 0.0,
+        }
+    }
+
+    fn ms(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    fn tap(gestures: &mut GestureRecognizer, x: f64, y: f64, time: Instant) -> Vec<GestureEvent> {
+        gestures.pointer_down(&touch(1, x, y), time);
+        gestures.pointer_up(&touch(1, x, y), time + Duration::from_millis(50))
+    }
+
+    fn pans(events: &[GestureEvent]) -> usize {
+        events
+            .iter()
+            .filter(|e| matches!(e, GestureEvent::Pan { .. }))
+            .count()
+    }
+
+    #[test]
+    fn tap_within_slop() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        gestures.pointer_down(&touch(1, 10.0, 10.0), start);
+        assert!(
+            gestures
+                .pointer_move(&touch(1, 15.0, 15.0), ms(start, 20))
+                .is_empty()
+        );
+        assert_eq!(
+            gestures.pointer_up(&touch(1, 15.0, 15.0), ms(start, 40)),
+            [GestureEvent::Tap { x: 15.0, y: 15.0 }]
+        );
+    }
+
+    #[test]
+    fn moving_beyond_slop_pans_instead_of_tapping() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        gestures.pointer_down(&touch(1, 10.0, 10.0), start);
+        assert_eq!(
+            gestures.pointer_move(&touch(1, 19.0, 10.0), ms(start, 20)),
+            [GestureEvent::Pan {
+                phase: GesturePhase::Began,
+                x: 19.0,
+                y: 10.0,
+                dx: 9.0,
+                dy: 0.0,
+            }]
+        );
+        assert_eq!(
+            gestures.pointer_move(&touch(1, 21.0, 13.0), ms(start, 40)),
+            [GestureEvent::Pan {
+                phase: GesturePhase::Changed,
+                x: 21.0,
+                y: 13.0,
+                dx: 2.0,
+                dy: 3.0,
+            }]
+        );
+        assert_eq!(
+            gestures.pointer_up(&touch(1, 21.0, 13.0), ms(start, 60)),
+            [GestureEvent::Pan {
+                phase: GesturePhase::Ended,
+                x: 21.0,
+                y: 13.0,
+                dx: 0.0,
+                dy: 0.0,
+            }]
+        );
+        assert_eq!(gestures.deadline(), None);
+    }
+
+    #[test]
+    fn double_tap_within_timeout_and_slop() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        assert_eq!(
+            tap(&mut gestures, 10.0, 10.0, start),
+            [GestureEvent::Tap { x: 10.0, y: 10.0 }]
+        );
+        assert_eq!(
+            tap(&mut gestures, 60.0, 10.0, ms(start, 200)),
+            [GestureEvent::DoubleTap { x: 60.0, y: 10.0 }]
+        );
+        // A third tap starts over instead of chaining onto the double tap
+        assert_eq!(
+            tap(&mut gestures, 60.0, 10.0, ms(start, 400)),
+            [GestureEvent::Tap { x: 60.0, y: 10.0 }]
+        );
+    }
+
+    #[test]
+    fn second_tap_after_timeout_or_beyond_slop_is_a_tap() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        tap(&mut gestures, 10.0, 10.0, start);
+        // 50ms of the tap plus 301ms of gap
+        assert_eq!(
+            tap(&mut gestures, 10.0, 10.0, ms(start, 351)),
+            [GestureEvent::Tap { x: 10.0, y: 10.0 }]
+        );
+        assert_eq!(
+            tap(&mut gestures, 111.0, 10.0, ms(start, 500)),
+            [GestureEvent::Tap { x: 111.0, y: 10.0 }]
+        );
+    }
+
+    #[test]
+    fn long_press_fires_on_tick_after_timeout() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        gestures.pointer_down(&touch(1, 10.0, 10.0), start);
+        assert_eq!(gestures.deadline(), Some(ms(start, 500)));
+        assert_eq!(gestures.tick(ms(start, 499)), None);
+        assert_eq!(
+            gestures.tick(ms(start, 500)),
+            Some(GestureEvent::LongPress { x: 10.0, y: 10.0 })
+        );
+        assert_eq!(gestures.tick(ms(start, 600)), None);
+        assert!(
+            gestures
+                .pointer_up(&touch(1, 10.0, 10.0), ms(start, 700))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn moving_beyond_slop_cancels_long_press() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        gestures.pointer_down(&touch(1, 10.0, 10.0), start);
+        gestures.pointer_move(&touch(1, 10.0, 19.0), ms(start, 100));
+        assert_eq!(gestures.deadline(), None);
+        assert_eq!(gestures.tick(ms(start, 600)), None);
+    }
+
+    #[test]
+    fn pinch_beyond_slop_without_pan() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        gestures.pointer_down(&touch(1, 100.0, 100.0), start);
+        gestures.pointer_down(&touch(2, 200.0, 100.0), ms(start, 10));
+        // Spread 50 grows to 55, still within the pinch slop of 16 and the touch slop of 8
+        assert!(
+            gestures
+                .pointer_move(&touch(2, 210.0, 100.0), ms(start, 20))
+                .is_empty()
+        );
+        // Spread 50 grows to 75, and the centroid travels far beyond the touch slop
+        let events = gestures.pointer_move(&touch(2, 250.0, 100.0), ms(start, 30));
+        assert_eq!(
+            events,
+            [GestureEvent::Pinch {
+                phase: GesturePhase::Began,
+                x: 175.0,
+                y: 100.0,
+                scale: 1.5,
+            }]
+        );
+        let events = gestures.pointer_move(&touch(1, 25.0, 100.0), ms(start, 40));
+        assert_eq!(pans(&events), 0);
+        assert!(matches!(
+            events[..],
+            [GestureEvent::Pinch {
+                phase: GesturePhase::Changed,
+                scale: 1.5,
+                ..
+            }]
+        ));
+        let events = gestures.pointer_up(&touch(2, 250.0, 100.0), ms(start, 50));
+        assert!(matches!(
+            events[..],
+            [GestureEvent::Pinch {
+                phase: GesturePhase::Ended,
+                ..
+            }]
+        ));
+        assert!(
+            gestures
+                .pointer_up(&touch(1, 25.0, 100.0), ms(start, 60))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn rotate_beyond_slop() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        gestures.pointer_down(&touch(1, -100.0, 0.0), start);
+        gestures.pointer_down(&touch(2, 100.0, 0.0), ms(start, 10));
+        // Both fingers turn 4 degrees about their centroid, within the rotate slop of 5
+        let (sin, cos) = 4f64.to_radians().sin_cos();
+        gestures.pointer_move(&touch(1, -100.0 * cos, -100.0 * sin), ms(start, 20));
+        let events = gestures.pointer_move(&touch(2, 100.0 * cos, 100.0 * sin), ms(start, 30));
+        assert!(events.is_empty());
+        // Finger 1 turns on to 7 degrees, which turns the line through the fingers by 5.5
+        let (sin7, cos7) = 7f64.to_radians().sin_cos();
+        let events = gestures.pointer_move(&touch(1, -100.0 * cos7, -100.0 * sin7), ms(start, 40));
+        assert_eq!(pans(&events), 0);
+        let [GestureEvent::Rotate { phase, angle, .. }] = events[..] else {
+            panic!("expected a rotation, got {events:?}");
+        };
+        assert_eq!(phase, GesturePhase::Began);
+        assert!((angle - (sin + sin7).atan2(cos + cos7)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pan_ends_when_pinch_begins() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        gestures.pointer_down(&touch(1, 100.0, 100.0), start);
+        gestures.pointer_down(&touch(2, 200.0, 100.0), ms(start, 10));
+        // Both fingers drag together
+        gestures.pointer_move(&touch(1, 110.0, 100.0), ms(start, 20));
+        let events = gestures.pointer_move(&touch(2, 210.0, 100.0), ms(start, 30));
+        assert!(matches!(
+            events[..],
+            [GestureEvent::Pan {
+                phase: GesturePhase::Began,
+                ..
+            }]
+        ));
+        let events = gestures.pointer_move(&touch(2, 260.0, 100.0), ms(start, 40));
+        assert!(matches!(
+            events[..],
+            [
+                GestureEvent::Pinch {
+                    phase: GesturePhase::Began,
+                    ..
+                },
+                GestureEvent::Pan {
+                    phase: GesturePhase::Ended,
+                    ..
+                }
+            ]
+        ));
+        let events = gestures.pointer_move(&touch(1, 60.0, 100.0), ms(start, 50));
+        assert_eq!(pans(&events), 0);
+    }
+
+    #[test]
+    fn cancelled_touch_does_not_tap() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        gestures.pointer_down(&touch(1, 10.0, 10.0), start);
+        gestures.cancel(&touch(1, 10.0, 10.0));
+        assert_eq!(gestures.deadline(), None);
+        assert!(
+            gestures
+                .pointer_up(&touch(1, 10.0, 10.0), ms(start, 40))
+                .is_empty()
+        );
+        assert_eq!(gestures.tick(ms(start, 600)), None);
+    }
+
+    #[test]
+    fn mouse_is_ignored() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        let mouse = PointerEvent {
+            pointer_type: PointerType::Mouse,
+            ..touch(1, 10.0, 10.0)
+        };
+        assert!(gestures.pointer_down(&mouse, start).is_empty());
+        assert!(gestures.pointer_up(&mouse, ms(start, 40)).is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawDisplayHandle,
+    RawWindowHandle, WindowHandle,
+};
+use {
+    crate::capture::{Capture, CaptureError},
+    std::{cell::RefCell, future::poll_fn, rc::Rc, task::Poll},
+};
+
+// Reads back the native window
+type Reader = Rc<dyn Fn() -> Result<Capture, CaptureError>>;
+
+#[derive(Default)]
+struct Inner {
+    available: bool,
+    // Only where the platform can read back the window
+    reader: Option<Reader>,
+    #[cfg(feature = "raw-window-handle")]
+    handles: Option<(RawWindowHandle, RawDisplayHandle)>,
+    // The client area in physical pixels
+    size: (u32, u32),
+    // The backend has no native window to hand out
+    unsupported: bool,
+}
+
+/// The native window behind a `window`, for graphics libraries that draw into it, e.g. wgpu, glutin,
+/// softbuffer or skia.
+///
+/// Pass it to the `native_handle` prop of `window`. With the `raw-window-handle` feature, it implements
+/// `HasWindowHandle` and `HasDisplayHandle` of raw-window-handle 0.6, and hands out the handles while the
+/// window exists. Before the window is shown and after it is hidden or closed, both return
+/// `HandleError::Unavailable`, and a surface created earlier must not be used anymore. The headless backend
+/// has no native window and returns `HandleError::NotSupported`.
+#[derive(Clone, Default)]
+pub struct NativeHandle(Rc<RefCell<Inner>>);
+
+impl NativeHandle {
+    /// Whether the window currently exists, so the handles can be taken.
+    pub fn is_available(&self) -> bool {
+        self.0.borrow().available
+    }
+
+    /// The client area in physical pixels as of the last render or resize of the window, e.g. to size a
+    /// surface created from the handles. `(0, 0)` before the window is shown. Also known on the headless
+    /// backend, from the `width` and `height` props.
+    pub fn size(&self) -> (u32, u32) {
+        self.0.borrow().size
+    }
+
+    /// Wait until the window exists. Never returns on the headless backend.
+    pub async fn available(&self) {
+        poll_fn(|_| {
+            if self.is_available() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Read back the client area from the native window, including what graphics libraries drew into it
+    /// through the handles, unlike `WindowContext::capture`, which only reads the software surface.
+    ///
+    /// Only Windows lets an app read back its own window, with `PrintWindow`. Elsewhere it returns
+    /// `CaptureError::NotSupported`, and `CaptureError::Unavailable` while the window doesn't exist.
+    pub fn capture(&self) -> Result<Capture, CaptureError> {
+        // The platform may call back into the window while reading it back
+        let reader = {
+            let inner = self.0.borrow();
+            match &inner.reader {
+                Some(reader) => reader.clone(),
+                None if inner.unsupported || inner.available => {
+                    return Err(CaptureError::NotSupported);
+                }
+                None => return Err(CaptureError::Unavailable),
+            }
+        };
+        reader()
+    }
+
+    // The window was created, or its native window replaced
+    #[cfg_attr(headless, allow(dead_code))]
+    pub(crate) fn set_available(&self) {
+        self.0.borrow_mut().available = true;
+    }
+
+    // The handles of the window that was created, after `set_available`
+    #[cfg(feature = "raw-window-handle")]
+    #[cfg_attr(headless, allow(dead_code))]
+    pub(crate) fn set(&self, window: RawWindowHandle, display: RawDisplayHandle) {
+        self.0.borrow_mut().handles = Some((window, display));
+    }
+
+    // How to read back the window that was created, after `set_available`
+    #[cfg(all(windows, not(headless)))]
+    pub(crate) fn set_reader(&self, reader: impl Fn() -> Result<Capture, CaptureError> + 'static) {
+        self.0.borrow_mut().reader = Some(Rc::new(reader));
+    }
+
+    pub(crate) fn set_size(&self, width: u32, height: u32) {
+        self.0.borrow_mut().size = (width, height);
+    }
+
+    #[cfg_attr(headless, allow(dead_code))]
+    pub(crate) fn clear(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.available = false;
+        inner.reader = None;
+        #[cfg(feature = "raw-window-handle")]
+        {
+            inner.handles = None;
+        }
+    }
+
+    #[cfg_attr(not(headless), allow(dead_code))]
+    pub(crate) fn set_unsupported(&self) {
+        self.0.borrow_mut().unsupported = true;
+    }
+
+    #[cfg(feature = "raw-window-handle")]
+    fn handles(&self) -> Result<(RawWindowHandle, RawDisplayHandle), HandleError> {
+        let inner = self.0.borrow();
+        match inner.handles {
+            Some(handles) => Ok(handles),
+            None if inner.unsupported => Err(HandleError::NotSupported),
+            None => Err(HandleError::Unavailable),
+        }
+    }
+}
+
+#[cfg(feature = "raw-window-handle")]
+impl HasWindowHandle for NativeHandle {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        // The backend clears the handle before the native window goes away
+        self.handles()
+            .map(|(window, _)| unsafe { WindowHandle::borrow_raw(window) })
+    }
+}
+
+#[cfg(feature = "raw-window-handle")]
+impl HasDisplayHandle for NativeHandle {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        self.handles()
+            .map(|(_, display)| unsafe { DisplayHandle::borrow_raw(display) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_before_the_window_exists() {
+        let handle = NativeHandle::default();
+        assert!(matches!(handle.capture(), Err(CaptureError::Unavailable)));
+    }
+
+    #[test]
+    fn capture_without_a_reader() {
+        let handle = NativeHandle::default();
+        handle.set_available();
+        assert!(matches!(handle.capture(), Err(CaptureError::NotSupported)));
+        handle.clear();
+        assert!(matches!(handle.capture(), Err(CaptureError::Unavailable)));
+        handle.set_unsupported();
+        assert!(matches!(handle.capture(), Err(CaptureError::NotSupported)));
+    }
+}
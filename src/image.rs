@@ -0,0 +1,530 @@
+use {
+    crate::{
+        canvas::paint_region,
+        context::{Layer, WindowContext},
+        layout::LayoutNode,
+        surface::PixelBuffer,
+    },
+    compo::prelude::*,
+    std::{
+        collections::HashMap,
+        error::Error,
+        fmt::{Display, Formatter, Result as FmtResult},
+        future::poll_fn,
+        io,
+        path::PathBuf,
+        sync::{
+            Arc,
+            mpsc::{Receiver, TryRecvError, channel},
+        },
+        task::Poll,
+        thread,
+    },
+    taffy::Size,
+    tiny_skia::{FilterQuality, IntSize, Pixmap, Rect},
+};
+
+thread_local! {
+    static GLOBAL: ImageCache = ImageCache::default();
+}
+
+/// Where the pixels of an image come from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageData {
+    /// A PNG, JPEG or WebP file, read when the image is decoded.
+    File(PathBuf),
+    /// The contents of a PNG, JPEG or WebP file, e.g. from `include_bytes!`.
+    Bytes(Arc<[u8]>),
+}
+
+/// What an `image` shows: an image, and optionally versions of it for high-DPI displays.
+///
+/// Each version is drawn at its scale, e.g. a version for scale 2 has twice the pixels per logical pixel of
+/// the one for scale 1. The `image` picks the smallest version at least as large as the scale factor of the
+/// window, or the largest version if none is.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImageSource(Vec<(f64, ImageData)>);
+
+impl ImageSource {
+    /// The image in a file, for scale 1.
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::default().with_variant(1.0, ImageData::File(path.into()))
+    }
+
+    /// The image in an encoded file in memory, for scale 1.
+    pub fn bytes(bytes: impl Into<Arc<[u8]>>) -> Self {
+        Self::default().with_variant(1.0, ImageData::Bytes(bytes.into()))
+    }
+
+    /// Add the version of the image for `scale` physical pixels per logical pixel, e.g. `logo@2x.png` for 2.
+    pub fn with_variant(mut self, scale: f64, data: ImageData) -> Self {
+        self.0.retain(|(existing, _)| *existing != scale);
+        self.0.push((scale, data));
+        self.0.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        self
+    }
+
+    // The version to show at `scale_factor`, with its scale
+    fn pick(&self, scale_factor: f64) -> Option<(f64, &ImageData)> {
+        self.0
+            .iter()
+            .find(|(scale, _)| *scale >= scale_factor)
+            .or(self.0.last())
+            .map(|(scale, data)| (*scale, data))
+    }
+}
+
+/// Why an image couldn't be shown.
+#[derive(Clone, Debug)]
+pub enum ImageError {
+    /// The file couldn't be read.
+    Io(Arc<io::Error>),
+    /// The data isn't a PNG, JPEG or WebP image, or it is damaged.
+    Decode(Arc<dyn Error + Send + Sync>),
+}
+
+impl Display for ImageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Io(e) => write!(f, "can't read image: {}", e),
+            Self::Decode(e) => write!(f, "can't decode image: {}", e),
+        }
+    }
+}
+
+impl Error for ImageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(&**e),
+            Self::Decode(e) => Some(&**e),
+        }
+    }
+}
+
+impl From<::image::ImageError> for ImageError {
+    fn from(e: ::image::ImageError) -> Self {
+        match e {
+            ::image::ImageError::IoError(e) => Self::Io(Arc::new(e)),
+            e => Self::Decode(Arc::new(e)),
+        }
+    }
+}
+
+/// Decoded pixels, premultiplied RGBA as tiny-skia draws them. Cheap to clone.
+#[derive(Clone, Debug)]
+pub struct Bitmap(Rc<Pixmap>);
+
+impl Bitmap {
+    /// Decode a PNG, JPEG or WebP image on the current thread.
+    pub fn decode(data: &ImageData) -> Result<Self, ImageError> {
+        decode(data).map(|pixmap| Self(Rc::new(pixmap)))
+    }
+
+    /// In physical pixels.
+    pub fn width(&self) -> u32 {
+        self.0.width()
+    }
+
+    /// In physical pixels.
+    pub fn height(&self) -> u32 {
+        self.0.height()
+    }
+
+    /// To draw with `Painter::draw_image`.
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.0
+    }
+}
+
+fn decode(data: &ImageData) -> Result<Pixmap, ImageError> {
+    let image = match data {
+        ImageData::File(path) => ::image::ImageReader::open(path)
+            .map_err(|e| ImageError::Io(Arc::new(e)))?
+            .with_guessed_format()
+            .map_err(|e| ImageError::Io(Arc::new(e)))?
+            .decode()?,
+        ImageData::Bytes(bytes) => ::image::load_from_memory(bytes)?,
+    };
+    let image = image.into_rgba8();
+    let size = IntSize::from_wh(image.width(), image.height())
+        .ok_or_else(|| decode_error("the image is empty"))?;
+    let mut pixels = image.into_raw();
+    for pixel in pixels.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        for channel in &mut pixel[..3] {
+            *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+    Pixmap::from_vec(pixels, size).ok_or_else(|| decode_error("the image is too large"))
+}
+
+fn decode_error(message: &str) -> ImageError {
+    ImageError::Decode(Arc::from(Box::<dyn Error + Send + Sync>::from(message)))
+}
+
+enum Entry {
+    // Decoding on another thread
+    Loading(Receiver<Result<Pixmap, ImageError>>),
+    Ready(Bitmap),
+    Failed(ImageError),
+}
+
+#[derive(Default)]
+struct Inner {
+    // With the time of the last use, to let go of the least recently used bitmaps first
+    entries: HashMap<ImageData, (Entry, u64)>,
+    clock: u64,
+    // In bytes, `None` for the default
+    capacity: Option<usize>,
+}
+
+impl Inner {
+    fn poll(&mut self, data: &ImageData) -> Poll<Result<Bitmap, ImageError>> {
+        self.clock += 1;
+        let clock = self.clock;
+        let Some((entry, used)) = self.entries.get_mut(data) else {
+            let (sender, receiver) = channel();
+            let source = data.clone();
+            thread::spawn(move || sender.send(decode(&source)));
+            self.entries
+                .insert(data.clone(), (Entry::Loading(receiver), clock));
+            return Poll::Pending;
+        };
+        *used = clock;
+        let result = match entry {
+            Entry::Ready(bitmap) => return Poll::Ready(Ok(bitmap.clone())),
+            Entry::Failed(e) => return Poll::Ready(Err(e.clone())),
+            Entry::Loading(receiver) => match receiver.try_recv() {
+                Ok(result) => result.map(|pixmap| Bitmap(Rc::new(pixmap))),
+                Err(TryRecvError::Empty) => return Poll::Pending,
+                Err(TryRecvError::Disconnected) => Err(decode_error("the decoder stopped")),
+            },
+        };
+        *entry = match &result {
+            Ok(bitmap) => Entry::Ready(bitmap.clone()),
+            Err(e) => Entry::Failed(e.clone()),
+        };
+        self.evict(Some(data));
+        Poll::Ready(result)
+    }
+
+    // Let go of bitmaps until the rest fit the capacity, except the one of `keep`
+    fn evict(&mut self, keep: Option<&ImageData>) {
+        let capacity = self.capacity.unwrap_or(ImageCache::DEFAULT_CAPACITY);
+        let size = |entry: &Entry| match entry {
+            Entry::Ready(bitmap) => bitmap.0.data().len(),
+            _ => 0,
+        };
+        let mut total = self
+            .entries
+            .values()
+            .map(|(entry, _)| size(entry))
+            .sum::<usize>();
+        while total > capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(data, (entry, _))| Some(*data) != keep && size(entry) > 0)
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(data, _)| data.clone());
+            let Some((entry, _)) = oldest.and_then(|data| self.entries.remove(&data)) else {
+                break;
+            };
+            total -= size(&entry);
+        }
+    }
+}
+
+/// Decoded images, shared by every `image` so each one is decoded once.
+///
+/// Images are decoded on a thread of their own, so large ones don't hold up the windows. Once the decoded
+/// bitmaps take up more than the capacity, the least recently used ones are let go, to be decoded again
+/// when shown. Images that failed to decode are remembered as well, until `remove` or `clear`.
+#[derive(Clone, Default)]
+pub struct ImageCache(Rc<RefCell<Inner>>);
+
+impl ImageCache {
+    /// 64 MiB.
+    pub const DEFAULT_CAPACITY: usize = 64 << 20;
+
+    /// The cache of the application.
+    pub fn global() -> Self {
+        GLOBAL.with(Clone::clone)
+    }
+
+    /// The bitmap of `data`, decoded on another thread unless cached.
+    pub async fn load(&self, data: &ImageData) -> Result<Bitmap, ImageError> {
+        poll_fn(|_| self.0.borrow_mut().poll(data)).await
+    }
+
+    /// The bytes the decoded bitmaps may take up before the least recently used ones are let go.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.0.borrow_mut();
+        inner.capacity = Some(capacity);
+        inner.evict(None);
+    }
+
+    /// Forget `data`, e.g. after the file changed.
+    pub fn remove(&self, data: &ImageData) {
+        self.0.borrow_mut().entries.remove(data);
+    }
+
+    /// Forget every image.
+    pub fn clear(&self) {
+        self.0.borrow_mut().entries.clear();
+    }
+}
+
+/// How an image is sized to the area of its `image`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ImageFit {
+    /// As large as fits, keeping the aspect ratio. Centered.
+    #[default]
+    Contain,
+    /// As small as covers the area, keeping the aspect ratio. Centered and cut off.
+    Cover,
+    /// Stretched over the area.
+    Fill,
+    /// At its own size. Centered and cut off.
+    None,
+}
+
+/// How an image is sampled when drawn at another size than its own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ImageFilter {
+    /// Sharp pixels, e.g. for pixel art.
+    Nearest,
+    #[default]
+    Bilinear,
+    /// Smoother than `Bilinear`, and slower.
+    Bicubic,
+}
+
+// Where an image of `width` x `height` goes in an area of `area_width` x `area_height`
+fn fit_rect(
+    fit: ImageFit,
+    width: f32,
+    height: f32,
+    area_width: f32,
+    area_height: f32,
+) -> Option<Rect> {
+    let (width, height) = match fit {
+        ImageFit::Fill => (area_width, area_height),
+        ImageFit::None => (width, height),
+        ImageFit::Contain | ImageFit::Cover => {
+            let (x_scale, y_scale) = (area_width / width, area_height / height);
+            let scale = match fit {
+                ImageFit::Contain => x_scale.min(y_scale),
+                _ => x_scale.max(y_scale),
+            };
+            (width * scale, height * scale)
+        }
+    };
+    Rect::from_xywh(
+        (area_width - width) / 2.0,
+        (area_height - height) / 2.0,
+        width,
+        height,
+    )
+}
+
+// A PNG, JPEG or WebP image in a window. Placed at `x`, `y` in logical pixels, a `width` or `height` of 0
+// extends it to the right or bottom edge of the window. Placed by `layout` instead, if given, which is sized
+// to the image unless its style gives it a size
+#[component]
+pub async fn image(
+    context: Option<&WindowContext>,
+    source: Option<&ImageSource>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    fit: ImageFit,
+    filter: ImageFilter,
+    #[event] on_error: ImageError,
+    layout: Option<&LayoutNode>,
+) {
+    #[field]
+    let layer: Option<Layer> = None;
+    #[field]
+    // Bumped on every render, so an image still loading for an earlier render isn't shown
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let layer = layer.get_or_insert_with(|| context.add_layer());
+    let Some(source) = source.cloned() else {
+        layer.set_draw(|_: &mut PixelBuffer| {});
+        return;
+    };
+    let layout = layout.cloned();
+    let (x, y, width, height, fit, filter) = (*x, *y, *width, *height, *fit, *filter);
+    let quality = match filter {
+        ImageFilter::Nearest => FilterQuality::Nearest,
+        ImageFilter::Bilinear => FilterQuality::Bilinear,
+        ImageFilter::Bicubic => FilterQuality::Bicubic,
+    };
+
+    // The version shown depends on the scale factor, which may change while the window exists
+    loop {
+        let scale_factor = context.scale_factor();
+        let Some((scale, data)) = source.pick(scale_factor) else {
+            return;
+        };
+        let result = ImageCache::global().load(data).await;
+        if *render_id != current_render {
+            return;
+        }
+        match result {
+            Ok(bitmap) => {
+                let (image_width, image_height) = (
+                    (bitmap.width() as f64 / scale) as f32,
+                    (bitmap.height() as f64 / scale) as f32,
+                );
+                if let Some(layout) = &layout {
+                    // A size given in one direction keeps the aspect ratio in the other
+                    let content = (image_width, image_height);
+                    layout.set_measure(content, move |known, _| {
+                        match (known.width, known.height) {
+                            (Some(width), Some(height)) => Size { width, height },
+                            (Some(width), None) => Size {
+                                width,
+                                height: width * image_height / image_width,
+                            },
+                            (None, Some(height)) => Size {
+                                width: height * image_width / image_height,
+                                height,
+                            },
+                            (None, None) => Size {
+                                width: image_width,
+                                height: image_height,
+                            },
+                        }
+                    });
+                }
+                let layout = layout.clone();
+                layer.set_draw(move |buffer: &mut PixelBuffer| {
+                    let region = (x, y, width, height);
+                    paint_region(buffer, layout.as_ref(), region, |painter| {
+                        let (area_width, area_height) = (painter.width(), painter.height());
+                        let rect =
+                            fit_rect(fit, image_width, image_height, area_width, area_height);
+                        if let Some(rect) = rect {
+                            painter.draw_image_filtered(bitmap.pixmap(), rect, quality);
+                        }
+                    });
+                });
+            }
+            Err(e) => {
+                layer.set_draw(|_: &mut PixelBuffer| {});
+                let _ = on_error.emit(e);
+            }
+        }
+        if source.0.len() < 2 {
+            return;
+        }
+        poll_fn(|_| {
+            if *render_id != current_render || context.scale_factor() != scale_factor {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+        if *render_id != current_render {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        ::image::{DynamicImage, ImageFormat, RgbaImage},
+        std::io::Cursor,
+    };
+
+    // 2 x 2 pixels: red, green, blue, and half transparent white
+    fn encode(format: ImageFormat) -> ImageData {
+        let pixels = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [255, 255, 255, 128],
+        ];
+        let image = RgbaImage::from_raw(2, 2, pixels.concat()).unwrap();
+        let image = match format {
+            // JPEG has no alpha
+            ImageFormat::Jpeg => {
+                DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(image).into_rgb8())
+            }
+            _ => DynamicImage::ImageRgba8(image),
+        };
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        ImageData::Bytes(bytes.into_inner().into())
+    }
+
+    fn pixels(bitmap: &Bitmap) -> Vec<[u8; 4]> {
+        bitmap
+            .pixmap()
+            .pixels()
+            .iter()
+            .map(|p| [p.red(), p.green(), p.blue(), p.alpha()])
+            .collect()
+    }
+
+    #[test]
+    fn decodes_png() {
+        let bitmap = Bitmap::decode(&encode(ImageFormat::Png)).unwrap();
+        assert_eq!((bitmap.width(), bitmap.height()), (2, 2));
+        // Premultiplied by alpha
+        assert_eq!(
+            pixels(&bitmap),
+            [
+                [255, 0, 0, 255],
+                [0, 255, 0, 255],
+                [0, 0, 255, 255],
+                [128, 128, 128, 128]
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_webp() {
+        let bitmap = Bitmap::decode(&encode(ImageFormat::WebP)).unwrap();
+        assert_eq!((bitmap.width(), bitmap.height()), (2, 2));
+        // Lossless, so the same as the PNG
+        assert_eq!(
+            pixels(&bitmap),
+            [
+                [255, 0, 0, 255],
+                [0, 255, 0, 255],
+                [0, 0, 255, 255],
+                [128, 128, 128, 128]
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_jpeg() {
+        let bitmap = Bitmap::decode(&encode(ImageFormat::Jpeg)).unwrap();
+        assert_eq!((bitmap.width(), bitmap.height()), (2, 2));
+        // Lossy, and the colors bleed into each other at this size, but every pixel is opaque
+        assert!(pixels(&bitmap).iter().all(|p| p[3] == 255));
+        let [r, g, b, _] = pixels(&bitmap)[0];
+        assert!(r > g && r > b);
+    }
+
+    #[test]
+    fn reports_damaged_data_and_missing_files() {
+        let data = ImageData::Bytes(b"not an image".as_slice().into());
+        assert!(matches!(Bitmap::decode(&data), Err(ImageError::Decode(_))));
+        let data = ImageData::File("does/not/exist.png".into());
+        assert!(matches!(Bitmap::decode(&data), Err(ImageError::Io(_))));
+    }
+}
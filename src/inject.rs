@@ -0,0 +1,176 @@
+use {
+    crate::{
+        events::WindowEvent,
+        keyboard::{KeyEvent, Modifiers},
+        pointer::{PointerButton, PointerEvent, PointerType},
+        scroll::{ScrollDelta, ScrollEvent, ScrollPhase},
+        text_input::TextInputEvent,
+    },
+    std::{cell::RefCell, rc::Rc},
+};
+
+// Hands an event to the window's backend, where the platform's own input arrives. With `false`, the event
+// was recognized before and skips the gesture recognizer
+type Sink = Rc<dyn Fn(WindowEvent, bool)>;
+
+#[derive(Default)]
+struct Inner {
+    sink: Option<Sink>,
+    // Events injected before a window was attached
+    pending: Vec<(WindowEvent, bool)>,
+}
+
+/// Delivers synthetic input to a `window`, for tests that drive an app without a human.
+///
+/// Pass it to the `injector` prop of `window`. Injected events take the same path as the platform's own input:
+/// pointer events feed the gesture recognizer, key presses go through the keymaps, and everything arrives at
+/// the `on_*` event props and `WindowEvents` like a real event would. Events injected before the window
+/// exists are held back until it is shown.
+///
+/// On the native backends close requests go through the platform, so the window really closes, while
+/// resizes and focus changes are only reported and leave the native window as it is.
+///
+/// A window whose `enabled` prop is `false` drops injected input like the platform's own, and only gets
+/// close requests, resizes and focus changes.
+#[derive(Clone, Default)]
+pub struct InputInjector(Rc<RefCell<Inner>>);
+
+impl InputInjector {
+    /// Deliver any event, exactly as given.
+    pub fn inject(&self, event: WindowEvent) {
+        self.deliver(event, true);
+    }
+
+    // Deliver an event of a recording, whose gestures were recognized while recording
+    pub(crate) fn replay(&self, event: WindowEvent) {
+        self.deliver(event, false);
+    }
+
+    fn deliver(&self, event: WindowEvent, recognize: bool) {
+        let sink = {
+            let mut inner = self.0.borrow_mut();
+            match &inner.sink {
+                Some(sink) => sink.clone(),
+                None => {
+                    inner.pending.push((event, recognize));
+                    return;
+                }
+            }
+        };
+        sink(event, recognize);
+    }
+
+    /// Whether a window is currently attached. Events injected while this is `false` are held back.
+    pub fn is_attached(&self) -> bool {
+        self.0.borrow().sink.is_some()
+    }
+
+    pub fn key_down(&self, event: KeyEvent) {
+        self.inject(WindowEvent::KeyDown(event));
+    }
+
+    pub fn key_up(&self, event: KeyEvent) {
+        self.inject(WindowEvent::KeyUp(event));
+    }
+
+    /// Press and release a key, committing its `text` in between like a keyboard does.
+    pub fn press_key(&self, event: KeyEvent) {
+        self.key_down(event.clone());
+        if let Some(text) = &event.text {
+            self.type_text(text);
+        }
+        self.key_up(KeyEvent {
+            text: None,
+            repeat: false,
+            ..event
+        });
+    }
+
+    /// Commit `text` as if typed, without any key events.
+    pub fn type_text(&self, text: &str) {
+        self.inject(WindowEvent::TextInput(TextInputEvent::Commit(
+            text.to_owned(),
+        )));
+    }
+
+    /// Move the mouse to (`x`, `y`) in logical units, with no button held.
+    pub fn mouse_move(&self, x: f64, y: f64) {
+        self.inject(WindowEvent::PointerMove(mouse_event(x, y, None, 0)));
+    }
+
+    /// Press and release `button` at (`x`, `y`), moving the mouse there first.
+    pub fn click(&self, x: f64, y: f64, button: PointerButton) {
+        self.mouse_move(x, y);
+        self.inject(WindowEvent::PointerDown(PointerEvent {
+            pressure: 0.5,
+            ..mouse_event(x, y, Some(button), 1)
+        }));
+        self.inject(WindowEvent::PointerUp(mouse_event(x, y, Some(button), 1)));
+    }
+
+    /// Scroll by `delta` with the mouse at (`x`, `y`).
+    pub fn scroll(&self, x: f64, y: f64, delta: ScrollDelta) {
+        self.inject(WindowEvent::Scroll(ScrollEvent {
+            x,
+            y,
+            delta,
+            phase: ScrollPhase::Changed,
+            modifiers: Modifiers::NONE,
+        }));
+    }
+
+    /// Report a new client area size, in pixels.
+    pub fn resize(&self, width: i32, height: i32) {
+        self.inject(WindowEvent::Resized { width, height });
+    }
+
+    /// Report that the window gained or lost the keyboard focus.
+    pub fn focus(&self, focused: bool) {
+        self.inject(WindowEvent::Focused(focused));
+    }
+
+    /// Ask the window to close, like its close button does.
+    pub fn request_close(&self) {
+        self.inject(WindowEvent::CloseRequested);
+    }
+
+    // A window was shown with this injector, held-back events are delivered now
+    #[cfg_attr(
+        all(any(target_os = "macos", target_os = "ios"), not(headless)),
+        allow(dead_code)
+    )]
+    pub(crate) fn attach(&self, sink: impl Fn(WindowEvent, bool) + 'static) {
+        let pending = {
+            let mut inner = self.0.borrow_mut();
+            inner.sink = Some(Rc::new(sink));
+            std::mem::take(&mut inner.pending)
+        };
+        pending
+            .into_iter()
+            .for_each(|(event, recognize)| self.deliver(event, recognize));
+    }
+
+    // The window is gone, events are held back until the next one is shown
+    #[cfg_attr(
+        all(any(target_os = "macos", target_os = "ios"), not(headless)),
+        allow(dead_code)
+    )]
+    pub(crate) fn detach(&self) {
+        self.0.borrow_mut().sink = None;
+    }
+}
+
+fn mouse_event(x: f64, y: f64, button: Option<PointerButton>, click_count: u32) -> PointerEvent {
+    PointerEvent {
+        pointer_id: 0,
+        pointer_type: PointerType::Mouse,
+        x,
+        y,
+        button,
+        click_count,
+        modifiers: Modifiers::NONE,
+        pressure: 0.0,
+        tilt_x: 0.0,
+        tilt_y: 0.0,
+    }
+}
@@ -0,0 +1,663 @@
+use {
+    crate::keyboard::{Key, KeyCode, KeyEvent, Modifiers, NamedKey},
+    std::{
+        cell::RefCell,
+        collections::HashMap,
+        error::Error,
+        fmt::{Display, Formatter, Result as FmtResult},
+        fs, io,
+        path::Path,
+        rc::Rc,
+        str::FromStr,
+    },
+    tracing::warn,
+};
+
+// Names of the named keys in shortcut strings, the first one of a key is the one it displays as
+const NAMED_KEYS: [(&str, NamedKey); 47] = [
+    ("Enter", NamedKey::Enter),
+    ("Return", NamedKey::Enter),
+    ("Tab", NamedKey::Tab),
+    ("Down", NamedKey::ArrowDown),
+    ("ArrowDown", NamedKey::ArrowDown),
+    ("Left", NamedKey::ArrowLeft),
+    ("ArrowLeft", NamedKey::ArrowLeft),
+    ("Right", NamedKey::ArrowRight),
+    ("ArrowRight", NamedKey::ArrowRight),
+    ("Up", NamedKey::ArrowUp),
+    ("ArrowUp", NamedKey::ArrowUp),
+    ("End", NamedKey::End),
+    ("Home", NamedKey::Home),
+    ("PageDown", NamedKey::PageDown),
+    ("PageUp", NamedKey::PageUp),
+    ("Backspace", NamedKey::Backspace),
+    ("Delete", NamedKey::Delete),
+    ("Insert", NamedKey::Insert),
+    ("Escape", NamedKey::Escape),
+    ("Esc", NamedKey::Escape),
+    ("ContextMenu", NamedKey::ContextMenu),
+    ("Pause", NamedKey::Pause),
+    ("PrintScreen", NamedKey::PrintScreen),
+    ("F1", NamedKey::F1),
+    ("F2", NamedKey::F2),
+    ("F3", NamedKey::F3),
+    ("F4", NamedKey::F4),
+    ("F5", NamedKey::F5),
+    ("F6", NamedKey::F6),
+    ("F7", NamedKey::F7),
+    ("F8", NamedKey::F8),
+    ("F9", NamedKey::F9),
+    ("F10", NamedKey::F10),
+    ("F11", NamedKey::F11),
+    ("F12", NamedKey::F12),
+    ("F13", NamedKey::F13),
+    ("F14", NamedKey::F14),
+    ("F15", NamedKey::F15),
+    ("F16", NamedKey::F16),
+    ("F17", NamedKey::F17),
+    ("F18", NamedKey::F18),
+    ("F19", NamedKey::F19),
+    ("F20", NamedKey::F20),
+    ("F21", NamedKey::F21),
+    ("F22", NamedKey::F22),
+    ("F23", NamedKey::F23),
+    ("F24", NamedKey::F24),
+];
+
+// Characters that would clash with the shortcut syntax, or be invisible in it
+const NAMED_CHARACTERS: [(&str, char); 3] = [("Space", ' '), ("Plus", '+'), ("Comma", ',')];
+
+const LETTER_KEYS: [KeyCode; 26] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+];
+
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+// The physical key that produces an ASCII letter or digit on a US layout
+fn us_layout_key(c: char) -> Option<KeyCode> {
+    match c {
+        'a'..='z' => Some(LETTER_KEYS[c as usize - 'a' as usize]),
+        '0'..='9' => Some(DIGIT_KEYS[c as usize - '0' as usize]),
+        _ => None,
+    }
+}
+
+/// A single key press with modifiers, such as `Ctrl+Shift+K`.
+///
+/// Parsed from and displayed as modifier names joined to the key with `+`. The modifiers are `Ctrl`, `Alt`
+/// (or `Option`), `Shift`, `Super` (or `Cmd`, `Win`, `Meta`) and `Primary`, which is `Cmd` on Apple
+/// platforms and `Ctrl` elsewhere. Names are case-insensitive.
+///
+/// The key is a single character, such as `K`, `1` or `/`, or one of these names:
+///
+/// - `Enter` (or `Return`), `Tab`, `Backspace`, `Delete`, `Insert`, `Escape` (or `Esc`), `ContextMenu`,
+///   `Pause` and `PrintScreen`
+/// - `Up`, `Down`, `Left` and `Right` (or `ArrowUp`, `ArrowDown`, `ArrowLeft` and `ArrowRight`), `Home`,
+///   `End`, `PageUp` and `PageDown`
+/// - `F1` to `F24`
+/// - `Space`, `Plus` and `Comma` for those characters; `+` also works as the last key, as in `Ctrl++`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    pub modifiers: Modifiers,
+    /// The key, characters are always lowercase.
+    pub key: Key,
+}
+
+impl Shortcut {
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        let key = match key {
+            Key::Character(c) => Key::Character(c.to_lowercase().next().unwrap_or(c)),
+            key => key,
+        };
+        Self { modifiers, key }
+    }
+
+    /// Whether the key press `event` triggers this shortcut.
+    ///
+    /// Letters match regardless of Shift's effect on their case. On layouts that don't produce ASCII
+    /// letters, `Ctrl+C` still matches the key that is C on a US layout, so shortcuts keep working.
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        let key = match (self.key, event.logical_key) {
+            (Key::Character(c), Key::Character(pressed)) => {
+                pressed.to_lowercase().eq(std::iter::once(c))
+                    || (!pressed.is_ascii() && us_layout_key(c) == Some(event.physical_key))
+            }
+            (Key::Character(c), _) => us_layout_key(c) == Some(event.physical_key),
+            (key, pressed) => key == pressed,
+        };
+        // Shift picks a symbol like `?` itself, so it only counts for letters and named keys unless asked for
+        if matches!(self.key, Key::Character(c) if !c.is_alphabetic()) && !self.modifiers.shift() {
+            key && event.modifiers | Modifiers::SHIFT == self.modifiers | Modifiers::SHIFT
+        } else {
+            key && event.modifiers == self.modifiers
+        }
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || KeymapError::InvalidShortcut(s.to_owned());
+        let trimmed = s.trim();
+        // A trailing `+` right after a separator, as in `Ctrl++`, is the key itself
+        let (names, key) = match trimmed.strip_suffix('+') {
+            Some(names) if names.is_empty() || names.ends_with('+') => (names, "+"),
+            _ => trimmed.rsplit_once('+').unwrap_or(("", trimmed)),
+        };
+        let mut modifiers = Modifiers::NONE;
+        for name in names.split('+').filter(|n| !n.is_empty()) {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CONTROL,
+                "alt" | "option" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                "super" | "cmd" | "command" | "win" | "meta" => Modifiers::SUPER,
+                "primary" => Modifiers::PRIMARY,
+                _ => return Err(invalid()),
+            };
+        }
+        let mut chars = key.chars();
+        let key = match (chars.next(), chars.next()) {
+            (Some(c), None) => Key::Character(c),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, key)| Key::Named(*key))
+                .or_else(|| {
+                    NAMED_CHARACTERS
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                        .map(|(_, c)| Key::Character(*c))
+                })
+                .ok_or_else(invalid)?,
+        };
+        Ok(Self::new(modifiers, key))
+    }
+}
+
+impl Display for Shortcut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let super_name = if cfg!(any(target_os = "macos", target_os = "ios")) {
+            "Cmd"
+        } else {
+            "Super"
+        };
+        for (modifier, name) in [
+            (Modifiers::CONTROL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::SUPER, super_name),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.key {
+            Key::Character(c) => match NAMED_CHARACTERS.iter().find(|(_, n)| *n == c) {
+                Some((name, _)) => f.write_str(name),
+                None => write!(f, "{}", c.to_uppercase()),
+            },
+            Key::Named(key) => match NAMED_KEYS.iter().find(|(_, n)| *n == key) {
+                Some((name, _)) => f.write_str(name),
+                None => write!(f, "{:?}", key),
+            },
+            Key::Dead(_) | Key::Unidentified => f.write_str("Unidentified"),
+        }
+    }
+}
+
+/// The key presses that trigger an action: a single shortcut, or a chord of several pressed one after another,
+/// such as `Ctrl+K Ctrl+C`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBinding(pub Vec<Shortcut>);
+
+impl KeyBinding {
+    /// Whether `other` starts with all the key presses of this binding.
+    pub fn is_prefix_of(&self, other: &KeyBinding) -> bool {
+        other.0.starts_with(&self.0)
+    }
+}
+
+impl From<Shortcut> for KeyBinding {
+    fn from(shortcut: Shortcut) -> Self {
+        Self(vec![shortcut])
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let shortcuts = s
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<Shortcut>, _>>()?;
+        if shortcuts.is_empty() {
+            return Err(KeymapError::InvalidShortcut(s.to_owned()));
+        }
+        Ok(Self(shortcuts))
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (i, shortcut) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", shortcut)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a key binding or an overrides file was rejected.
+#[derive(Debug)]
+pub enum KeymapError {
+    /// The text isn't a valid shortcut or key binding.
+    InvalidShortcut(String),
+    /// A line of an overrides file isn't of the form `action = bindings`, with its 1-based number.
+    InvalidLine(usize),
+    /// The overrides file couldn't be read.
+    Io(io::Error),
+}
+
+impl Display for KeymapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::InvalidShortcut(s) => write!(f, "invalid shortcut `{}`", s),
+            Self::InvalidLine(line) => write!(f, "line {} isn't `action = bindings`", line),
+            Self::Io(e) => write!(f, "can't read key bindings: {}", e),
+        }
+    }
+}
+
+impl Error for KeymapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for KeymapError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Two actions of a keymap competing for the same key presses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The binding of `first`. Either `second` has the very same binding, or a chord starting with it.
+    pub binding: KeyBinding,
+    pub first: String,
+    /// The action that can't be triggered while `first` keeps its binding.
+    pub second: String,
+}
+
+/// What `Keymap::dispatch` made of a key press.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Dispatch {
+    /// The key press completed the binding of this action.
+    Action(String),
+    /// The key press started or continued a chord, more keys are needed.
+    Pending,
+    /// The key press broke off a chord. It belongs to neither the chord nor the normal key handlers.
+    Aborted,
+    /// The key press isn't bound, it goes to the normal key handlers.
+    Unhandled,
+}
+
+#[derive(Default)]
+struct Inner {
+    // Registered actions with their default bindings, in registration order
+    actions: Vec<(String, Vec<KeyBinding>)>,
+    // Bindings replacing the defaults, also for actions that aren't registered yet
+    overrides: HashMap<String, Vec<KeyBinding>>,
+    // The key presses of the chord being typed
+    pending: Vec<KeyEvent>,
+}
+
+impl Inner {
+    fn bindings(&self, action: &str) -> Vec<KeyBinding> {
+        self.overrides.get(action).cloned().unwrap_or_else(|| {
+            self.actions
+                .iter()
+                .find(|(name, _)| name == action)
+                .map(|(_, defaults)| defaults.clone())
+                .unwrap_or_default()
+        })
+    }
+}
+
+thread_local! {
+    static GLOBAL: Keymap = Keymap::with_builtins();
+}
+
+/// A registry of named actions and the key bindings that trigger them.
+///
+/// Each `window` consults the keymap passed to its `keymap` prop first, then the application-wide
+/// `Keymap::global()`, before a key press reaches `on_key_down`. Window keymaps may rebind shortcuts of the
+/// global one for that window only.
+///
+/// Key presses are currently dispatched on Windows and the headless backend. macOS and iOS don't deliver key
+/// presses to windows yet, so only the application menu's `QUIT` and `CLOSE_WINDOW` work on macOS.
+#[derive(Clone, Default)]
+pub struct Keymap(Rc<RefCell<Inner>>);
+
+impl Keymap {
+    /// Quits the application. Bound to `Cmd+Q` on macOS.
+    pub const QUIT: &str = "app.quit";
+    /// Closes the window the key was pressed in. Bound to `Cmd+W` on macOS.
+    pub const CLOSE_WINDOW: &str = "window.close";
+
+    /// An empty keymap, for actions scoped to a single window.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_builtins() -> Self {
+        let keymap = Self::new();
+        let (quit, close): (&[&str], &[&str]) = if cfg!(target_os = "macos") {
+            (&["Cmd+Q"], &["Cmd+W"])
+        } else {
+            // Other platforms close and quit through the window manager, e.g. with Alt+F4
+            (&[], &[])
+        };
+        let _ = keymap.add_action(Self::QUIT, quit);
+        let _ = keymap.add_action(Self::CLOSE_WINDOW, close);
+        keymap
+    }
+
+    /// The application-wide keymap of the current thread, holding `QUIT` and `CLOSE_WINDOW`.
+    pub fn global() -> Self {
+        GLOBAL.with(Clone::clone)
+    }
+
+    /// Register `action` with its default bindings, e.g. `&["Primary+S"]` or `&["Ctrl+K Ctrl+C"]`.
+    /// Registering an action again replaces its defaults. Overrides applied before still take precedence.
+    pub fn add_action(&self, action: &str, defaults: &[&str]) -> Result<(), KeymapError> {
+        let defaults = defaults
+            .iter()
+            .map(|b| b.parse())
+            .collect::<Result<Vec<KeyBinding>, _>>()?;
+        let mut inner = self.0.borrow_mut();
+        match inner.actions.iter_mut().find(|(name, _)| name == action) {
+            Some((_, bindings)) => *bindings = defaults,
+            None => inner.actions.push((action.to_owned(), defaults)),
+        }
+        Ok(())
+    }
+
+    /// The names of the registered actions, in registration order.
+    pub fn actions(&self) -> Vec<String> {
+        let inner = self.0.borrow();
+        inner.actions.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// The bindings currently triggering `action`.
+    pub fn bindings(&self, action: &str) -> Vec<KeyBinding> {
+        self.0.borrow().bindings(action)
+    }
+
+    /// Add `binding` to the bindings of `action`, as a user override.
+    pub fn bind(&self, action: &str, binding: &str) -> Result<(), KeymapError> {
+        let binding = binding.parse()?;
+        let mut inner = self.0.borrow_mut();
+        let mut bindings = inner.bindings(action);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        inner.overrides.insert(action.to_owned(), bindings);
+        Ok(())
+    }
+
+    /// Remove every binding of `action`, as a user override.
+    pub fn unbind(&self, action: &str) {
+        self.0
+            .borrow_mut()
+            .overrides
+            .insert(action.to_owned(), Vec::new());
+    }
+
+    /// Drop the user overrides of `action`, so its default bindings apply again.
+    pub fn restore_defaults(&self, action: &str) {
+        self.0.borrow_mut().overrides.remove(action);
+    }
+
+    /// Apply user overrides in the format of a key bindings file.
+    ///
+    /// Each line is `action = bindings`, with the bindings separated by commas and optionally quoted.
+    /// An empty right-hand side unbinds the action, and lines starting with `#` are comments:
+    ///
+    /// ```text
+    /// # Comment with Ctrl+K Ctrl+C instead of the default
+    /// editor.comment = "Ctrl+K Ctrl+C, Primary+/"
+    /// app.quit =
+    /// ```
+    ///
+    /// Nothing is applied if any line is invalid. Conflicts the overrides introduce are logged.
+    pub fn apply_overrides(&self, text: &str) -> Result<(), KeymapError> {
+        let mut overrides = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((action, value)) = line.split_once('=') else {
+                return Err(KeymapError::InvalidLine(i + 1));
+            };
+            let action = action.trim().trim_matches('"');
+            if action.is_empty() {
+                return Err(KeymapError::InvalidLine(i + 1));
+            }
+            let bindings = split_bindings(value.trim().trim_matches('"'))
+                .map(str::parse)
+                .collect::<Result<Vec<KeyBinding>, _>>()?;
+            overrides.push((action.to_owned(), bindings));
+        }
+        self.0.borrow_mut().overrides.extend(overrides);
+        for conflict in self.conflicts() {
+            warn!(
+                "Key binding `{}` of `{}` shadows `{}`.",
+                conflict.binding, conflict.first, conflict.second
+            );
+        }
+        Ok(())
+    }
+
+    /// Read `path` and apply it with `apply_overrides`.
+    pub fn load_overrides(&self, path: impl AsRef<Path>) -> Result<(), KeymapError> {
+        self.apply_overrides(&fs::read_to_string(path)?)
+    }
+
+    /// Pairs of registered actions whose bindings collide: the same binding twice, or a binding that is the
+    /// start of a chord, which makes the chord unreachable.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let inner = self.0.borrow();
+        let bound = inner
+            .actions
+            .iter()
+            .flat_map(|(name, _)| {
+                inner
+                    .bindings(name)
+                    .into_iter()
+                    .map(move |binding| (name, binding))
+            })
+            .collect::<Vec<_>>();
+        let mut conflicts = Vec::new();
+        for (i, (first, a)) in bound.iter().enumerate() {
+            for (second, b) in &bound[i + 1..] {
+                if first == second {
+                    continue;
+                }
+                let (binding, first, second) = if a.is_prefix_of(b) {
+                    (a, first, second)
+                } else if b.is_prefix_of(a) {
+                    (b, second, first)
+                } else {
+                    continue;
+                };
+                conflicts.push(Conflict {
+                    binding: binding.clone(),
+                    first: (*first).clone(),
+                    second: (*second).clone(),
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// Feed a key press through the keymap. Presses of modifier keys alone are `Unhandled` and leave a chord
+    /// in progress untouched.
+    pub fn dispatch(&self, event: &KeyEvent) -> Dispatch {
+        if matches!(
+            event.logical_key,
+            Key::Named(
+                NamedKey::Alt
+                    | NamedKey::Control
+                    | NamedKey::Shift
+                    | NamedKey::Super
+                    | NamedKey::CapsLock
+                    | NamedKey::NumLock
+                    | NamedKey::ScrollLock
+            )
+        ) {
+            return Dispatch::Unhandled;
+        }
+        let mut inner = self.0.borrow_mut();
+        let mut typed = std::mem::take(&mut inner.pending);
+        let chord = !typed.is_empty();
+        typed.push(event.clone());
+
+        let mut prefix = false;
+        for (name, _) in &inner.actions {
+            for binding in inner.bindings(name) {
+                if binding.0.len() < typed.len()
+                    || !binding.0.iter().zip(&typed).all(|(s, e)| s.matches(e))
+                {
+                    continue;
+                }
+                if binding.0.len() == typed.len() {
+                    return Dispatch::Action(name.clone());
+                }
+                prefix = true;
+            }
+        }
+        if prefix {
+            inner.pending = typed;
+            Dispatch::Pending
+        } else if chord {
+            Dispatch::Aborted
+        } else {
+            Dispatch::Unhandled
+        }
+    }
+
+    /// Forget a chord in progress, e.g. when the window loses the focus.
+    pub fn clear_pending(&self) {
+        self.0.borrow_mut().pending.clear();
+    }
+}
+
+// Split a comma-separated list of bindings, where a comma right after `+` is the key itself
+fn split_bindings(value: &str) -> impl Iterator<Item = &str> {
+    let mut start = 0;
+    let mut parts = Vec::new();
+    let mut previous = None;
+    for (i, c) in value.char_indices() {
+        if c == ',' && previous != Some('+') {
+            parts.push(&value[start..i]);
+            start = i + 1;
+        }
+        previous = Some(c);
+    }
+    parts.push(&value[start..]);
+    parts.into_iter().map(str::trim).filter(|p| !p.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Shortcut {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn plus_as_key() {
+        let ctrl_plus = Shortcut::new(Modifiers::CONTROL, Key::Character('+'));
+        assert_eq!(parse("Ctrl++"), ctrl_plus);
+        assert_eq!(parse("Ctrl+Plus"), ctrl_plus);
+        assert_eq!(
+            parse("+"),
+            Shortcut::new(Modifiers::NONE, Key::Character('+'))
+        );
+        assert_eq!(
+            parse("Ctrl+Shift++"),
+            Shortcut::new(Modifiers::CONTROL | Modifiers::SHIFT, Key::Character('+'))
+        );
+        assert_eq!(parse(&ctrl_plus.to_string()), ctrl_plus);
+    }
+
+    #[test]
+    fn missing_key() {
+        assert!("Ctrl+".parse::<Shortcut>().is_err());
+        assert!("".parse::<Shortcut>().is_err());
+    }
+
+    #[test]
+    fn named_keys() {
+        assert_eq!(
+            parse("esc"),
+            Shortcut::new(Modifiers::NONE, Key::Named(NamedKey::Escape))
+        );
+        assert_eq!(
+            parse("Alt+F24"),
+            Shortcut::new(Modifiers::ALT, Key::Named(NamedKey::F24))
+        );
+        assert_eq!(
+            parse("Primary+Comma"),
+            Shortcut::new(Modifiers::PRIMARY, Key::Character(','))
+        );
+        assert!("Ctrl+Banana".parse::<Shortcut>().is_err());
+    }
+}
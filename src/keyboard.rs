@@ -0,0 +1,470 @@
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+/// Physical key on the keyboard, identified by its position rather than by the character it produces.
+///
+/// Names follow the W3C UI Events `code` values, so `KeyCode::KeyQ` is the key to the right of `Tab`
+/// regardless of whether the active layout is QWERTY, AZERTY or Dvorak.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyCode {
+    Backquote,
+    Backslash,
+    BracketLeft,
+    BracketRight,
+    Comma,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Equal,
+    IntlBackslash,
+    IntlRo,
+    IntlYen,
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Minus,
+    Period,
+    Quote,
+    Semicolon,
+    Slash,
+    AltLeft,
+    AltRight,
+    Backspace,
+    CapsLock,
+    ContextMenu,
+    ControlLeft,
+    ControlRight,
+    Enter,
+    SuperLeft,
+    SuperRight,
+    ShiftLeft,
+    ShiftRight,
+    Space,
+    Tab,
+    Convert,
+    KanaMode,
+    NonConvert,
+    Delete,
+    End,
+    Home,
+    Insert,
+    PageDown,
+    PageUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    NumLock,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadComma,
+    NumpadDecimal,
+    NumpadDivide,
+    NumpadEnter,
+    NumpadEqual,
+    NumpadMultiply,
+    NumpadSubtract,
+    Escape,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    BrowserBack,
+    BrowserFavorites,
+    BrowserForward,
+    BrowserHome,
+    BrowserRefresh,
+    BrowserSearch,
+    BrowserStop,
+    LaunchApp1,
+    LaunchApp2,
+    LaunchMail,
+    MediaPlayPause,
+    MediaSelect,
+    MediaStop,
+    MediaTrackNext,
+    MediaTrackPrevious,
+    Power,
+    Sleep,
+    AudioVolumeDown,
+    AudioVolumeMute,
+    AudioVolumeUp,
+    WakeUp,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    /// A key without a known mapping, carrying the platform's raw scancode.
+    Unidentified(u32),
+}
+
+impl KeyCode {
+    /// Maps a PC/AT set 1 scancode to a physical key.
+    ///
+    /// Extended keys carry the `0xE0` prefix in the high byte (e.g. `0xE04B` is the dedicated left arrow),
+    /// which is how Windows reports them in `WM_KEYDOWN`/`WM_KEYUP`.
+    pub fn from_scancode(scancode: u32) -> Self {
+        match scancode {
+            0x0001 => Self::Escape,
+            0x0002 => Self::Digit1,
+            0x0003 => Self::Digit2,
+            0x0004 => Self::Digit3,
+            0x0005 => Self::Digit4,
+            0x0006 => Self::Digit5,
+            0x0007 => Self::Digit6,
+            0x0008 => Self::Digit7,
+            0x0009 => Self::Digit8,
+            0x000A => Self::Digit9,
+            0x000B => Self::Digit0,
+            0x000C => Self::Minus,
+            0x000D => Self::Equal,
+            0x000E => Self::Backspace,
+            0x000F => Self::Tab,
+            0x0010 => Self::KeyQ,
+            0x0011 => Self::KeyW,
+            0x0012 => Self::KeyE,
+            0x0013 => Self::KeyR,
+            0x0014 => Self::KeyT,
+            0x0015 => Self::KeyY,
+            0x0016 => Self::KeyU,
+            0x0017 => Self::KeyI,
+            0x0018 => Self::KeyO,
+            0x0019 => Self::KeyP,
+            0x001A => Self::BracketLeft,
+            0x001B => Self::BracketRight,
+            0x001C => Self::Enter,
+            0x001D => Self::ControlLeft,
+            0x001E => Self::KeyA,
+            0x001F => Self::KeyS,
+            0x0020 => Self::KeyD,
+            0x0021 => Self::KeyF,
+            0x0022 => Self::KeyG,
+            0x0023 => Self::KeyH,
+            0x0024 => Self::KeyJ,
+            0x0025 => Self::KeyK,
+            0x0026 => Self::KeyL,
+            0x0027 => Self::Semicolon,
+            0x0028 => Self::Quote,
+            0x0029 => Self::Backquote,
+            0x002A => Self::ShiftLeft,
+            0x002B => Self::Backslash,
+            0x002C => Self::KeyZ,
+            0x002D => Self::KeyX,
+            0x002E => Self::KeyC,
+            0x002F => Self::KeyV,
+            0x0030 => Self::KeyB,
+            0x0031 => Self::KeyN,
+            0x0032 => Self::KeyM,
+            0x0033 => Self::Comma,
+            0x0034 => Self::Period,
+            0x0035 => Self::Slash,
+            0x0036 => Self::ShiftRight,
+            0x0037 => Self::NumpadMultiply,
+            0x0038 => Self::AltLeft,
+            0x0039 => Self::Space,
+            0x003A => Self::CapsLock,
+            0x003B => Self::F1,
+            0x003C => Self::F2,
+            0x003D => Self::F3,
+            0x003E => Self::F4,
+            0x003F => Self::F5,
+            0x0040 => Self::F6,
+            0x0041 => Self::F7,
+            0x0042 => Self::F8,
+            0x0043 => Self::F9,
+            0x0044 => Self::F10,
+            0x0045 => Self::Pause,
+            0x0046 => Self::ScrollLock,
+            0x0047 => Self::Numpad7,
+            0x0048 => Self::Numpad8,
+            0x0049 => Self::Numpad9,
+            0x004A => Self::NumpadSubtract,
+            0x004B => Self::Numpad4,
+            0x004C => Self::Numpad5,
+            0x004D => Self::Numpad6,
+            0x004E => Self::NumpadAdd,
+            0x004F => Self::Numpad1,
+            0x0050 => Self::Numpad2,
+            0x0051 => Self::Numpad3,
+            0x0052 => Self::Numpad0,
+            0x0053 => Self::NumpadDecimal,
+            0x0054 => Self::PrintScreen,
+            0x0056 => Self::IntlBackslash,
+            0x0057 => Self::F11,
+            0x0058 => Self::F12,
+            0x0059 => Self::NumpadEqual,
+            0x0064 => Self::F13,
+            0x0065 => Self::F14,
+            0x0066 => Self::F15,
+            0x0067 => Self::F16,
+            0x0068 => Self::F17,
+            0x0069 => Self::F18,
+            0x006A => Self::F19,
+            0x006B => Self::F20,
+            0x006C => Self::F21,
+            0x006D => Self::F22,
+            0x006E => Self::F23,
+            0x0070 => Self::KanaMode,
+            0x0073 => Self::IntlRo,
+            0x0076 => Self::F24,
+            0x0079 => Self::Convert,
+            0x007B => Self::NonConvert,
+            0x007D => Self::IntlYen,
+            0x007E => Self::NumpadComma,
+            0xE010 => Self::MediaTrackPrevious,
+            0xE019 => Self::MediaTrackNext,
+            0xE01C => Self::NumpadEnter,
+            0xE01D => Self::ControlRight,
+            0xE020 => Self::AudioVolumeMute,
+            0xE021 => Self::LaunchApp2,
+            0xE022 => Self::MediaPlayPause,
+            0xE024 => Self::MediaStop,
+            0xE02E => Self::AudioVolumeDown,
+            0xE030 => Self::AudioVolumeUp,
+            0xE032 => Self::BrowserHome,
+            0xE035 => Self::NumpadDivide,
+            0xE037 => Self::PrintScreen,
+            0xE038 => Self::AltRight,
+            0xE045 => Self::NumLock,
+            0xE046 => Self::Pause,
+            0xE047 => Self::Home,
+            0xE048 => Self::ArrowUp,
+            0xE049 => Self::PageUp,
+            0xE04B => Self::ArrowLeft,
+            0xE04D => Self::ArrowRight,
+            0xE04F => Self::End,
+            0xE050 => Self::ArrowDown,
+            0xE051 => Self::PageDown,
+            0xE052 => Self::Insert,
+            0xE053 => Self::Delete,
+            0xE05B => Self::SuperLeft,
+            0xE05C => Self::SuperRight,
+            0xE05D => Self::ContextMenu,
+            0xE05E => Self::Power,
+            0xE05F => Self::Sleep,
+            0xE063 => Self::WakeUp,
+            0xE065 => Self::BrowserSearch,
+            0xE066 => Self::BrowserFavorites,
+            0xE067 => Self::BrowserRefresh,
+            0xE068 => Self::BrowserStop,
+            0xE069 => Self::BrowserForward,
+            0xE06A => Self::BrowserBack,
+            0xE06B => Self::LaunchApp1,
+            0xE06C => Self::LaunchMail,
+            0xE06D => Self::MediaSelect,
+            _ => Self::Unidentified(scancode),
+        }
+    }
+}
+
+/// Keys that don't produce a character, or whose meaning doesn't depend on the character they produce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum NamedKey {
+    Alt,
+    CapsLock,
+    Control,
+    NumLock,
+    ScrollLock,
+    Shift,
+    Super,
+    Enter,
+    Tab,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    End,
+    Home,
+    PageDown,
+    PageUp,
+    Backspace,
+    Delete,
+    Insert,
+    Escape,
+    ContextMenu,
+    Pause,
+    PrintScreen,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+}
+
+/// The meaning of a key press under the active keyboard layout.
+///
+/// On an AZERTY layout the physical `KeyCode::KeyQ` reports `Key::Character('a')`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum Key {
+    /// A key that produces a character, with the current modifiers (e.g. Shift) applied.
+    Character(char),
+    /// A key with a fixed, layout-independent meaning.
+    Named(NamedKey),
+    /// A dead key that combines with the next key press, with its own character if known.
+    Dead(Option<char>),
+    /// The platform couldn't tell what the key means.
+    Unidentified,
+}
+
+/// The set of modifier keys held down while an input event happened.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const NONE: Self = Self(0);
+    pub const SHIFT: Self = Self(1 << 0);
+    pub const CONTROL: Self = Self(1 << 1);
+    pub const ALT: Self = Self(1 << 2);
+    /// The Windows key, or Command on Apple platforms.
+    pub const SUPER: Self = Self(1 << 3);
+    /// The modifier of most application shortcuts: Command on Apple platforms, Control elsewhere.
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub const PRIMARY: Self = Self::SUPER;
+    /// The modifier of most application shortcuts: Command on Apple platforms, Control elsewhere.
+    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+    pub const PRIMARY: Self = Self::CONTROL;
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn shift(self) -> bool {
+        self.contains(Self::SHIFT)
+    }
+
+    pub fn control(self) -> bool {
+        self.contains(Self::CONTROL)
+    }
+
+    pub fn alt(self) -> bool {
+        self.contains(Self::ALT)
+    }
+
+    pub fn super_key(self) -> bool {
+        self.contains(Self::SUPER)
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Modifiers {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// A key press or release delivered by the `on_key_down`/`on_key_up` events of `window`.
+///
+/// Only Windows delivers them so far: the macOS, iOS and Android windows have no key events yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyEvent {
+    /// Which key was pressed, independent of the keyboard layout.
+    pub physical_key: KeyCode,
+    /// What the key means under the active keyboard layout.
+    pub logical_key: Key,
+    /// The text the key press produces, if any. Always `None` for key releases.
+    pub text: Option<String>,
+    /// Whether this press was generated by holding the key down.
+    pub repeat: bool,
+    /// Modifier keys held at the time of the event.
+    pub modifiers: Modifiers,
+}
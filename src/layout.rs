@@ -0,0 +1,381 @@
+use {
+    crate::{frame::FrameClock, surface::SoftwareSurface},
+    compo::prelude::*,
+    std::any::Any,
+    taffy::{
+        AvailableSpace, Display, NodeId, Size, Style, TaffyTree,
+        prelude::{TaffyMaxContent, flex, length},
+    },
+};
+
+type MeasureFn = Box<dyn Fn(Size<Option<f32>>, Size<AvailableSpace>) -> Size<f32>>;
+
+struct Measure {
+    // What the size depends on, so measuring the same content again changes nothing
+    content: Box<dyn Any>,
+    measure: MeasureFn,
+}
+
+struct Tree {
+    taffy: TaffyTree<Measure>,
+    // Of the windows showing a layout, redrawn entirely when any node changes
+    windows: Vec<(NodeId, FrameClock, SoftwareSurface)>,
+}
+
+impl Tree {
+    fn new() -> Self {
+        Self {
+            taffy: TaffyTree::new(),
+            windows: Vec::new(),
+        }
+    }
+
+    fn changed(&self) {
+        self.windows.iter().for_each(|(_, frame_clock, surface)| {
+            surface.invalidate();
+            frame_clock.request_redraw();
+        });
+    }
+}
+
+thread_local! {
+    // Every node lives in one tree, so nodes can be created on their own and put together later
+    static TREE: RefCell<Tree> = RefCell::new(Tree::new());
+}
+
+fn with_tree<R>(f: impl FnOnce(&mut Tree) -> R) -> R {
+    TREE.with(|tree| f(&mut tree.borrow_mut()))
+}
+
+/// Where a `LayoutNode` ended up, in logical pixels from the top left corner of the root.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LayoutRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+struct Handle(NodeId);
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        let _ = TREE.try_with(|tree| {
+            let mut tree = tree.borrow_mut();
+            let _ = tree.taffy.remove(self.0);
+            tree.windows.retain(|(root, _, _)| *root != self.0);
+            tree.changed();
+        });
+    }
+}
+
+/// A box of a flexbox, grid or block layout, computed with taffy, which the crate re-exports.
+///
+/// Nodes are created on their own and put together with `set_children`. The root of a layout is handed to
+/// `WindowContext::set_layout`, fills the window unless its style gives it a size, and is laid out again on
+/// every frame after the window was resized or any node changed. Components with a `layout` prop, e.g.
+/// `canvas`, `label` and `image`, take their place and size from their node instead of `x`, `y`, `width`
+/// and `height`.
+///
+/// A node is removed from the layout when its last clone is dropped.
+#[derive(Clone)]
+pub struct LayoutNode(Rc<Handle>);
+
+impl Default for LayoutNode {
+    fn default() -> Self {
+        Self::new(Style::default())
+    }
+}
+
+impl LayoutNode {
+    pub fn new(style: Style) -> Self {
+        let id = with_tree(|tree| tree.taffy.new_leaf(style)).expect("a new node can't fail");
+        Self(Rc::new(Handle(id)))
+    }
+
+    pub fn style(&self) -> Style {
+        with_tree(|tree| tree.taffy.style(self.0.0).cloned().unwrap_or_default())
+    }
+
+    /// Replace the style. Does nothing if it didn't change.
+    pub fn set_style(&self, style: Style) {
+        with_tree(|tree| {
+            if tree.taffy.style(self.0.0).is_ok_and(|old| *old != style) {
+                let _ = tree.taffy.set_style(self.0.0, style);
+                tree.changed();
+            }
+        });
+    }
+
+    /// Replace the children, taking them from their current parents. Does nothing if they didn't change.
+    pub fn set_children(&self, children: &[&LayoutNode]) {
+        let children = children.iter().map(|child| child.0.0).collect::<Vec<_>>();
+        with_tree(|tree| {
+            if tree
+                .taffy
+                .children(self.0.0)
+                .is_ok_and(|old| old != children)
+            {
+                let _ = tree.taffy.set_children(self.0.0, &children);
+                tree.changed();
+            }
+        });
+    }
+
+    /// Size the node by its content, e.g. text, when its style leaves the size open. `measure` receives the
+    /// sizes already known and the space available, and returns the size of the content in logical pixels.
+    ///
+    /// `content` is what the size depends on, e.g. the text and its style. The layout is only computed again
+    /// when it differs from the `content` of the last call, so a component may call this on every render.
+    pub fn set_measure<C: PartialEq + 'static>(
+        &self,
+        content: C,
+        measure: impl Fn(Size<Option<f32>>, Size<AvailableSpace>) -> Size<f32> + 'static,
+    ) {
+        with_tree(|tree| {
+            let unchanged = tree
+                .taffy
+                .get_node_context(self.0.0)
+                .and_then(|old| old.content.downcast_ref::<C>())
+                .is_some_and(|old| *old == content);
+            let measure = Measure {
+                content: Box::new(content),
+                measure: Box::new(measure),
+            };
+            // The callback is replaced either way, it may hold on to other state
+            let _ = tree.taffy.set_node_context(self.0.0, Some(measure));
+            if !unchanged {
+                tree.changed();
+            }
+        });
+    }
+
+    /// Lay out this node and everything below it in an area of `width` x `height` logical pixels, as
+    /// `WindowContext` does with the size of the window.
+    pub fn compute(&self, width: f32, height: f32) {
+        with_tree(|tree| {
+            // A grid cell of the size of the area stretches the root, unless it has a size of its own
+            let viewport = Style {
+                display: Display::Grid,
+                size: Size {
+                    width: length(width),
+                    height: length(height),
+                },
+                grid_template_columns: vec![flex(1.0)],
+                grid_template_rows: vec![flex(1.0)],
+                ..Default::default()
+            };
+            let Ok(viewport) = tree.taffy.new_with_children(viewport, &[self.0.0]) else {
+                return;
+            };
+            let _ = tree.taffy.compute_layout_with_measure(
+                viewport,
+                Size::MAX_CONTENT,
+                |known, available, _, measure, _| match measure {
+                    Some(measure) => (measure.measure)(known, available),
+                    None => Size::ZERO,
+                },
+            );
+            let _ = tree.taffy.remove(viewport);
+        });
+    }
+
+    /// Where the node was placed by the last `compute`, relative to the root of its layout.
+    pub fn layout(&self) -> LayoutRect {
+        with_tree(|tree| {
+            let Ok(layout) = tree.taffy.layout(self.0.0) else {
+                return LayoutRect::default();
+            };
+            let mut rect = LayoutRect {
+                x: layout.location.x,
+                y: layout.location.y,
+                width: layout.size.width,
+                height: layout.size.height,
+            };
+            let mut node = self.0.0;
+            while let Some(parent) = tree.taffy.parent(node) {
+                if let Ok(layout) = tree.taffy.layout(parent) {
+                    rect.x += layout.location.x;
+                    rect.y += layout.location.y;
+                }
+                node = parent;
+            }
+            rect
+        })
+    }
+
+    // The window of `frame_clock` and `surface` shows the layout of this node
+    pub(crate) fn set_window(&self, window: Option<(FrameClock, SoftwareSurface)>) {
+        with_tree(|tree| {
+            tree.windows.retain(|(root, _, _)| *root != self.0.0);
+            if let Some((frame_clock, surface)) = window {
+                surface.invalidate();
+                frame_clock.request_redraw();
+                tree.windows.push((self.0.0, frame_clock, surface));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::geometry::Rect,
+        taffy::{
+            AlignItems, FlexDirection,
+            prelude::{auto, fr, percent},
+        },
+    };
+
+    fn sized(width: f32, height: f32) -> LayoutNode {
+        LayoutNode::new(Style {
+            size: Size {
+                width: length(width),
+                height: length(height),
+            },
+            ..Default::default()
+        })
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> LayoutRect {
+        LayoutRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn flex_row() {
+        let root = LayoutNode::default();
+        let (a, b) = (sized(30.0, 20.0), sized(50.0, 10.0));
+        root.set_children(&[&a, &b]);
+        root.compute(200.0, 100.0);
+        assert_eq!(root.layout(), rect(0.0, 0.0, 200.0, 100.0));
+        assert_eq!(a.layout(), rect(0.0, 0.0, 30.0, 20.0));
+        assert_eq!(b.layout(), rect(30.0, 0.0, 50.0, 10.0));
+    }
+
+    #[test]
+    fn flex_column_with_grow_and_gap() {
+        let root = LayoutNode::new(Style {
+            flex_direction: FlexDirection::Column,
+            gap: Size {
+                width: length(0.0),
+                height: length(10.0),
+            },
+            padding: taffy::Rect {
+                left: length(5.0),
+                right: length(5.0),
+                top: length(5.0),
+                bottom: length(5.0),
+            },
+            ..Default::default()
+        });
+        let header = sized(40.0, 20.0);
+        let body = LayoutNode::new(Style {
+            flex_grow: 1.0,
+            ..Default::default()
+        });
+        root.set_children(&[&header, &body]);
+        root.compute(100.0, 200.0);
+        assert_eq!(header.layout(), rect(5.0, 5.0, 40.0, 20.0));
+        // Stretched across, and down to the padding
+        assert_eq!(body.layout(), rect(5.0, 35.0, 90.0, 160.0));
+    }
+
+    #[test]
+    fn grid() {
+        let root = LayoutNode::new(Style {
+            display: Display::Grid,
+            grid_template_columns: vec![length(50.0), fr(1.0)],
+            grid_template_rows: vec![length(20.0), auto()],
+            ..Default::default()
+        });
+        let cells = (0..4).map(|_| LayoutNode::default()).collect::<Vec<_>>();
+        root.set_children(&cells.iter().collect::<Vec<_>>());
+        root.compute(200.0, 100.0);
+        assert_eq!(cells[0].layout(), rect(0.0, 0.0, 50.0, 20.0));
+        assert_eq!(cells[1].layout(), rect(50.0, 0.0, 150.0, 20.0));
+        assert_eq!(cells[2].layout(), rect(0.0, 20.0, 50.0, 80.0));
+        assert_eq!(cells[3].layout(), rect(50.0, 20.0, 150.0, 80.0));
+    }
+
+    #[test]
+    fn nested_nodes_are_relative_to_the_root() {
+        let root = LayoutNode::new(Style {
+            padding: taffy::Rect {
+                left: length(10.0),
+                right: length(0.0),
+                top: length(20.0),
+                bottom: length(0.0),
+            },
+            ..Default::default()
+        });
+        let inner = LayoutNode::new(Style {
+            size: Size {
+                width: percent(0.5),
+                height: length(40.0),
+            },
+            padding: taffy::Rect {
+                left: length(4.0),
+                right: length(0.0),
+                top: length(4.0),
+                bottom: length(0.0),
+            },
+            ..Default::default()
+        });
+        let leaf = sized(10.0, 10.0);
+        inner.set_children(&[&leaf]);
+        root.set_children(&[&inner]);
+        root.compute(210.0, 100.0);
+        assert_eq!(inner.layout(), rect(10.0, 20.0, 100.0, 40.0));
+        assert_eq!(leaf.layout(), rect(14.0, 24.0, 10.0, 10.0));
+    }
+
+    #[test]
+    fn measured_leaf() {
+        let root = LayoutNode::new(Style {
+            align_items: Some(AlignItems::Start),
+            ..Default::default()
+        });
+        let (text, fixed) = (LayoutNode::default(), sized(30.0, 30.0));
+        // Like a text of 12 x 8 units per character
+        text.set_measure("hello", |known, _| Size {
+            width: known.width.unwrap_or(5.0 * 12.0),
+            height: known.height.unwrap_or(8.0),
+        });
+        root.set_children(&[&text, &fixed]);
+        root.compute(200.0, 100.0);
+        assert_eq!(text.layout(), rect(0.0, 0.0, 60.0, 8.0));
+        assert_eq!(fixed.layout(), rect(60.0, 0.0, 30.0, 30.0));
+    }
+
+    #[test]
+    fn measuring_the_same_content_changes_nothing() {
+        let (root, leaf) = (LayoutNode::default(), LayoutNode::default());
+        root.set_children(&[&leaf]);
+        let (frame_clock, surface) = (FrameClock::default(), SoftwareSurface::default());
+        root.set_window(Some((frame_clock, surface.clone())));
+        let frame_damage = || {
+            let mut damage = Vec::new();
+            surface.present(100, 100, |_, _, _, rects| damage = rects.to_vec());
+            damage
+        };
+        let whole = vec![Rect::new(0, 0, 100, 100)];
+        let small = vec![Rect::new(0, 0, 10, 10)];
+        assert_eq!(frame_damage(), whole);
+
+        leaf.set_measure("a", |_, _| Size::ZERO);
+        surface.damage(small[0]);
+        assert_eq!(frame_damage(), whole);
+        leaf.set_measure("a", |_, _| Size::ZERO);
+        surface.damage(small[0]);
+        assert_eq!(frame_damage(), small);
+        leaf.set_measure("b", |_, _| Size::ZERO);
+        surface.damage(small[0]);
+        assert_eq!(frame_damage(), whole);
+    }
+}
@@ -1,2 +1,41 @@
+mod animation;
+#[cfg(feature = "canvas")]
+mod canvas;
+mod capture;
+mod context;
+mod drag_drop;
+mod events;
+mod frame;
+mod geometry;
+mod gesture;
+mod handle;
+#[cfg(feature = "image")]
+mod image;
+mod inject;
+mod keybinding;
+mod keyboard;
+#[cfg(feature = "layout")]
+mod layout;
+#[cfg(feature = "opengl")]
+mod opengl;
+mod pointer;
 pub mod prelude;
+mod record;
+mod scroll;
+mod surface;
+#[cfg(feature = "text")]
+mod text;
+#[cfg(feature = "widgets")]
+mod text_field;
+mod text_input;
+mod theme;
+#[cfg(feature = "widgets")]
+mod widget;
 mod window;
+
+// Styles of `LayoutNode`
+#[cfg(feature = "layout")]
+pub use taffy;
+// Paths, paints and transforms of `Painter`
+#[cfg(feature = "canvas")]
+pub use tiny_skia;
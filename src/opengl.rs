@@ -0,0 +1,481 @@
+use {
+    crate::handle::NativeHandle,
+    std::{
+        cell::RefCell,
+        error::Error,
+        ffi::{CStr, c_void},
+        fmt::{Display as FmtDisplay, Formatter, Result as FmtResult},
+        ptr::null,
+        rc::Rc,
+        sync::Arc,
+    },
+};
+#[cfg(not(target_os = "ios"))]
+use {
+    glutin::{
+        config::{Api, Config, ConfigTemplateBuilder},
+        context::{
+            ContextApi, ContextAttributes, ContextAttributesBuilder, PossiblyCurrentContext,
+            Version,
+        },
+        display::{Display, DisplayApiPreference},
+        error::ErrorKind,
+        prelude::{GlDisplay, GlSurface, NotCurrentGlContext, PossiblyCurrentGlContext},
+        surface::{PbufferSurface, Surface, SurfaceAttributesBuilder, SwapInterval, WindowSurface},
+    },
+    raw_window_handle::{HandleError, HasDisplayHandle, HasWindowHandle, RawWindowHandle},
+    std::num::NonZeroU32,
+    tracing::warn,
+};
+
+/// The flavor of OpenGL a context implements.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OpenGlApi {
+    /// Desktop OpenGL.
+    #[default]
+    OpenGl,
+    /// OpenGL ES, e.g. on Android.
+    Gles,
+}
+
+/// What an `OpenGlSurface` asks the platform for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OpenGlConfig {
+    pub api: OpenGlApi,
+    /// The lowest version to accept as `(major, minor)`, `None` for any.
+    pub version: Option<(u8, u8)>,
+    pub depth_bits: u8,
+    pub stencil_bits: u8,
+    /// Samples per pixel for multisampling, 0 for none.
+    pub samples: u8,
+    /// Whether `swap_buffers` waits for the display's refresh, see `OpenGlSurface::set_vsync`.
+    pub vsync: bool,
+}
+
+impl Default for OpenGlConfig {
+    fn default() -> Self {
+        Self {
+            api: OpenGlApi::OpenGl,
+            version: None,
+            depth_bits: 24,
+            stencil_bits: 8,
+            samples: 0,
+            vsync: true,
+        }
+    }
+}
+
+/// Why an `OpenGlSurface` can't be drawn into.
+#[derive(Clone, Debug)]
+pub enum OpenGlError {
+    /// The window doesn't exist, before it is shown and after it is hidden or closed.
+    Unavailable,
+    /// There is no OpenGL for windows of this platform.
+    NotSupported,
+    /// The platform failed to create or use the context, e.g. without a matching configuration.
+    Platform(Arc<dyn Error + Send + Sync>),
+}
+
+impl FmtDisplay for OpenGlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Unavailable => write!(f, "the window doesn't exist"),
+            Self::NotSupported => write!(f, "OpenGL isn't supported for this window"),
+            Self::Platform(e) => write!(f, "OpenGL failed: {}", e),
+        }
+    }
+}
+
+impl Error for OpenGlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Platform(e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(target_os = "ios"))]
+impl From<glutin::error::Error> for OpenGlError {
+    fn from(e: glutin::error::Error) -> Self {
+        Self::Platform(Arc::new(e))
+    }
+}
+
+#[cfg_attr(target_os = "ios", allow(dead_code))]
+struct Inner {
+    handle: NativeHandle,
+    config: OpenGlConfig,
+    vsync: bool,
+    current: Option<Current>,
+}
+
+/// An OpenGL or OpenGL ES context drawing into a `window`, e.g. for visualizations with glow or the gl
+/// crate, enabled with the `opengl` feature.
+///
+/// It is created from the `NativeHandle` passed to the `native_handle` prop of the window, and follows it:
+/// `make_current` creates the context with the first call after the window is shown, and creates a new one
+/// after the native window was replaced, e.g. when an Android app comes back from the background. When the
+/// window's size changes, e.g. through its `width` and `height` props, the surface takes the new size with
+/// the next `make_current`. Each frame is drawn between `make_current` and `swap_buffers`, usually driven by
+/// a `FrameClock`.
+///
+/// The context comes from WGL on Windows, falling back to EGL, from CGL on macOS, and from EGL on Android.
+/// The headless backend draws into an offscreen EGL pbuffer of the window's size, e.g. with Mesa's
+/// llvmpipe on machines without a GPU, and `swap_buffers` does nothing there. There is no OpenGL on iOS, so
+/// `make_current` returns `OpenGlError::NotSupported` there.
+#[derive(Clone)]
+pub struct OpenGlSurface(Rc<RefCell<Inner>>);
+
+impl OpenGlSurface {
+    /// Draws into the window of `native_handle`, with a context matching `config`.
+    pub fn new(native_handle: &NativeHandle, config: OpenGlConfig) -> Self {
+        Self(Rc::new(RefCell::new(Inner {
+            handle: native_handle.clone(),
+            config,
+            vsync: config.vsync,
+            current: None,
+        })))
+    }
+
+    /// Make the context current on this thread, so OpenGL calls draw into the window. Returns `true` when
+    /// the context is new, so the textures, buffers and shaders of an earlier one are gone and have to be
+    /// created again. Creates the context, or adapts the surface to the window's size, on the way.
+    pub fn make_current(&self) -> Result<bool, OpenGlError> {
+        let mut inner = self.0.borrow_mut();
+        let created = inner.sync()?;
+        let vsync = inner.vsync;
+        let Some(current) = &mut inner.current else {
+            return Err(OpenGlError::Unavailable);
+        };
+        current.make_current()?;
+        current.apply_vsync(vsync);
+        Ok(created)
+    }
+
+    /// Show what was drawn since the last call. Waits for the display's refresh with vsync on.
+    pub fn swap_buffers(&self) -> Result<(), OpenGlError> {
+        match &self.0.borrow().current {
+            Some(current) => current.swap_buffers(),
+            None => Err(OpenGlError::Unavailable),
+        }
+    }
+
+    /// Turn waiting for the display's refresh in `swap_buffers` on or off. Takes effect with the next
+    /// `make_current`. Some platforms and drivers ignore it.
+    pub fn set_vsync(&self, vsync: bool) {
+        self.0.borrow_mut().vsync = vsync;
+    }
+
+    /// The address of the OpenGL function `name`, e.g. to load functions with glow or the gl crate, or null
+    /// if it doesn't exist. Call it while the context is current, some platforms only find a few functions
+    /// otherwise.
+    pub fn get_proc_address(&self, name: &CStr) -> *const c_void {
+        match &self.0.borrow().current {
+            Some(current) => current.get_proc_address(name),
+            None => null(),
+        }
+    }
+
+    /// The size of the surface in physical pixels, `(0, 0)` without a context.
+    pub fn size(&self) -> (u32, u32) {
+        self.0
+            .borrow()
+            .current
+            .as_ref()
+            .map_or((0, 0), Current::size)
+    }
+}
+
+// A context with the surface it draws into
+#[cfg(not(target_os = "ios"))]
+struct Current {
+    // The native window of the surface, `None` for a pbuffer
+    window: Option<RawWindowHandle>,
+    size: (u32, u32),
+    // The swap interval set on the surface
+    vsync: Option<bool>,
+    surface: Target,
+    context: PossiblyCurrentContext,
+    config: Config,
+    display: Display,
+}
+
+#[cfg(not(target_os = "ios"))]
+enum Target {
+    Window(Surface<WindowSurface>),
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    Pbuffer(Surface<PbufferSurface>),
+}
+
+#[cfg(not(target_os = "ios"))]
+impl Inner {
+    // Create the context, or a new one for a replaced window, and follow the window's size. Returns whether
+    // the context is new
+    fn sync(&mut self) -> Result<bool, OpenGlError> {
+        let size = self.handle.size();
+        match self.handle.window_handle().map(|handle| handle.as_raw()) {
+            Ok(window) => {
+                if let Some(current) = &mut self.current
+                    && current.window == Some(window)
+                {
+                    current.resize(size)?;
+                    return Ok(false);
+                }
+                self.current = None;
+                let display = self
+                    .handle
+                    .display_handle()
+                    .map_err(|_| OpenGlError::Unavailable)?
+                    .as_raw();
+                // The handles stay valid while the window exists, which it does until the next render
+                self.current =
+                    Some(unsafe { Current::for_window(display, window, size, &self.config) }?);
+                Ok(true)
+            }
+            Err(HandleError::NotSupported) => {
+                if let Some(current) = &mut self.current {
+                    current.resize(size)?;
+                    return Ok(false);
+                }
+                self.current = Some(Current::offscreen(size, &self.config)?);
+                Ok(true)
+            }
+            Err(_) => {
+                // The surface must not outlive its window
+                self.current = None;
+                Err(OpenGlError::Unavailable)
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "ios"))]
+impl Current {
+    unsafe fn for_window(
+        display: raw_window_handle::RawDisplayHandle,
+        window: RawWindowHandle,
+        size: (u32, u32),
+        config: &OpenGlConfig,
+    ) -> Result<Self, OpenGlError> {
+        #[cfg(windows)]
+        let preference = DisplayApiPreference::WglThenEgl(Some(window));
+        #[cfg(target_os = "macos")]
+        let preference = DisplayApiPreference::Cgl;
+        #[cfg(not(any(windows, target_os = "macos")))]
+        let preference = DisplayApiPreference::Egl;
+        let display = unsafe { Display::new(display, preference) }?;
+        let template = template(config).compatible_with_native_window(window);
+        let gl_config = unsafe { first_config(&display, template) }?;
+        let context = unsafe {
+            display.create_context(&gl_config, &context_attributes(config, Some(window)))
+        }?;
+        let (width, height) = non_zero(size);
+        let attributes =
+            SurfaceAttributesBuilder::<WindowSurface>::new().build(window, width, height);
+        let surface = unsafe { display.create_window_surface(&gl_config, &attributes) }?;
+        let context = context.make_current(&surface)?;
+        Ok(Self {
+            window: Some(window),
+            size,
+            vsync: None,
+            surface: Target::Window(surface),
+            context,
+            config: gl_config,
+            display,
+        })
+    }
+
+    // A pbuffer on the first EGL device, for the headless backend
+    #[cfg(not(target_os = "macos"))]
+    fn offscreen(size: (u32, u32), config: &OpenGlConfig) -> Result<Self, OpenGlError> {
+        use glutin::{
+            api::egl::{device::Device, display::Display as EglDisplay},
+            config::ConfigSurfaceTypes,
+        };
+
+        let device = Device::query_devices()?
+            .next()
+            .ok_or(OpenGlError::NotSupported)?;
+        let display = Display::Egl(unsafe { EglDisplay::with_device(&device, None) }?);
+        let template = template(config).with_surface_type(ConfigSurfaceTypes::PBUFFER);
+        let gl_config = unsafe { first_config(&display, template) }?;
+        let context =
+            unsafe { display.create_context(&gl_config, &context_attributes(config, None)) }?;
+        let surface = pbuffer(&display, &gl_config, size)?;
+        let context = context.make_current(&surface)?;
+        Ok(Self {
+            window: None,
+            size,
+            vsync: None,
+            surface: Target::Pbuffer(surface),
+            context,
+            config: gl_config,
+            display,
+        })
+    }
+
+    // There is no EGL on macOS
+    #[cfg(target_os = "macos")]
+    fn offscreen(_size: (u32, u32), _config: &OpenGlConfig) -> Result<Self, OpenGlError> {
+        Err(OpenGlError::NotSupported)
+    }
+
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), OpenGlError> {
+        // A minimized window keeps its surface
+        if self.size == size || size.0 == 0 || size.1 == 0 {
+            return Ok(());
+        }
+        match &self.surface {
+            Target::Window(surface) => {
+                let (width, height) = non_zero(size);
+                surface.resize(&self.context, width, height);
+            }
+            // Pbuffers keep their size, so a new one replaces it
+            Target::Pbuffer(_) => {
+                self.surface = Target::Pbuffer(pbuffer(&self.display, &self.config, size)?);
+            }
+        }
+        self.size = size;
+        Ok(())
+    }
+
+    fn make_current(&self) -> Result<(), OpenGlError> {
+        match &self.surface {
+            Target::Window(surface) => self.context.make_current(surface),
+            Target::Pbuffer(surface) => self.context.make_current(surface),
+        }?;
+        Ok(())
+    }
+
+    fn apply_vsync(&mut self, vsync: bool) {
+        let Target::Window(surface) = &self.surface else {
+            return;
+        };
+        if self.vsync == Some(vsync) {
+            return;
+        }
+        let interval = if vsync {
+            SwapInterval::Wait(NonZeroU32::MIN)
+        } else {
+            SwapInterval::DontWait
+        };
+        if let Err(e) = surface.set_swap_interval(&self.context, interval) {
+            warn!(?e, "Can't set the swap interval.");
+        }
+        self.vsync = Some(vsync);
+    }
+
+    fn swap_buffers(&self) -> Result<(), OpenGlError> {
+        match &self.surface {
+            Target::Window(surface) => surface.swap_buffers(&self.context)?,
+            // Nothing shows a pbuffer
+            Target::Pbuffer(_) => {}
+        }
+        Ok(())
+    }
+
+    fn get_proc_address(&self, name: &CStr) -> *const c_void {
+        self.display.get_proc_address(name)
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+#[cfg(not(target_os = "ios"))]
+fn template(config: &OpenGlConfig) -> ConfigTemplateBuilder {
+    let api = match config.api {
+        OpenGlApi::OpenGl => Api::OPENGL,
+        OpenGlApi::Gles if config.version.is_some_and(|(major, _)| major >= 3) => Api::GLES3,
+        OpenGlApi::Gles => Api::GLES2,
+    };
+    let template = ConfigTemplateBuilder::new()
+        .with_api(api)
+        .with_depth_size(config.depth_bits)
+        .with_stencil_size(config.stencil_bits);
+    if config.samples > 0 {
+        template.with_multisampling(config.samples)
+    } else {
+        template
+    }
+}
+
+// The window of the template must exist
+#[cfg(not(target_os = "ios"))]
+unsafe fn first_config(
+    display: &Display,
+    template: ConfigTemplateBuilder,
+) -> Result<Config, OpenGlError> {
+    let config = unsafe { display.find_configs(template.build()) }?.next();
+    config.ok_or_else(|| glutin::error::Error::from(ErrorKind::BadConfig).into())
+}
+
+#[cfg(not(target_os = "ios"))]
+fn context_attributes(config: &OpenGlConfig, window: Option<RawWindowHandle>) -> ContextAttributes {
+    let version = config
+        .version
+        .map(|(major, minor)| Version::new(major, minor));
+    let api = match config.api {
+        OpenGlApi::OpenGl => ContextApi::OpenGl(version),
+        OpenGlApi::Gles => ContextApi::Gles(version),
+    };
+    ContextAttributesBuilder::new()
+        .with_context_api(api)
+        .build(window)
+}
+
+#[cfg(not(target_os = "ios"))]
+fn pbuffer(
+    display: &Display,
+    config: &Config,
+    size: (u32, u32),
+) -> Result<Surface<PbufferSurface>, OpenGlError> {
+    let (width, height) = non_zero(size);
+    let attributes = SurfaceAttributesBuilder::<PbufferSurface>::new().build(width, height);
+    Ok(unsafe { display.create_pbuffer_surface(config, &attributes) }?)
+}
+
+// Surfaces have at least one pixel, e.g. before the window was laid out
+#[cfg(not(target_os = "ios"))]
+fn non_zero((width, height): (u32, u32)) -> (NonZeroU32, NonZeroU32) {
+    (
+        NonZeroU32::new(width).unwrap_or(NonZeroU32::MIN),
+        NonZeroU32::new(height).unwrap_or(NonZeroU32::MIN),
+    )
+}
+
+// There is no OpenGL platform for UIKit, so there is never a context
+#[cfg(target_os = "ios")]
+enum Current {}
+
+#[cfg(target_os = "ios")]
+impl Inner {
+    fn sync(&mut self) -> Result<bool, OpenGlError> {
+        Err(OpenGlError::NotSupported)
+    }
+}
+
+#[cfg(target_os = "ios")]
+impl Current {
+    fn make_current(&self) -> Result<(), OpenGlError> {
+        match *self {}
+    }
+
+    fn apply_vsync(&mut self, _vsync: bool) {
+        match *self {}
+    }
+
+    fn swap_buffers(&self) -> Result<(), OpenGlError> {
+        match *self {}
+    }
+
+    fn get_proc_address(&self, _name: &CStr) -> *const c_void {
+        match *self {}
+    }
+
+    fn size(&self) -> (u32, u32) {
+        match *self {}
+    }
+}
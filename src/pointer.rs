@@ -0,0 +1,51 @@
+use crate::keyboard::Modifiers;
+
+/// The kind of device behind a pointer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum PointerType {
+    Mouse,
+    Touch,
+    Pen,
+}
+
+/// The button that changed in a pointer down or up event.
+///
+/// Touch contacts and the pen tip are reported as `Primary`, so handlers written for a mouse also work
+/// for fingers and styluses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum PointerButton {
+    Primary,
+    Secondary,
+    Middle,
+    Back,
+    Forward,
+    /// The eraser end of a pen.
+    Eraser,
+}
+
+/// A pointer event delivered by the `on_pointer_*` events of `window`, on Windows and Android. Mouse events
+/// of macOS and touches of iOS aren't forwarded yet.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointerEvent {
+    /// Identifies a pointer across its events, e.g. each finger of a multi-touch gesture has its own id.
+    pub pointer_id: u32,
+    pub pointer_type: PointerType,
+    /// Horizontal position in logical units, relative to the client area.
+    pub x: f64,
+    /// Vertical position in logical units, relative to the client area.
+    pub y: f64,
+    /// The button that was pressed or released, `None` for enter, leave and move events.
+    pub button: Option<PointerButton>,
+    /// Number of consecutive presses of the same button in the same place, e.g. 2 for a double click.
+    pub click_count: u32,
+    pub modifiers: Modifiers,
+    /// Normalized pressure in `0.0..=1.0`. Devices without pressure report 0.5 while pressed and 0 otherwise.
+    pub pressure: f32,
+    /// Pen tilt towards the right edge in degrees, in `-90.0..=90.0`.
+    pub tilt_x: f32,
+    /// Pen tilt towards the bottom edge in degrees, in `-90.0..=90.0`.
+    pub tilt_y: f32,
+}
@@ -1,7 +1,25 @@
+#[cfg(feature = "canvas")]
+pub use crate::canvas::*;
+#[cfg(feature = "image")]
+pub use crate::image::*;
+#[cfg(feature = "layout")]
+pub use crate::layout::*;
+#[cfg(feature = "opengl")]
+pub use crate::opengl::*;
+#[cfg(feature = "text")]
+pub use crate::text::*;
+#[cfg(feature = "widgets")]
+pub use crate::{text_field::*, widget::*};
+#[cfg(not(headless))]
+pub use compo_platform_loop::prelude::run;
 pub use {
-    crate::window::*,
+    crate::{
+        animation::*, capture::*, context::*, drag_drop::*, events::*, frame::*, geometry::*,
+        gesture::*, handle::*, inject::*, keybinding::*, keyboard::*, pointer::*, record::*,
+        scroll::*, surface::*, text_input::*, theme::*, window::*,
+    },
     compo::prelude::{
-        Cancellable, Component, Duration, Rc, RefCell, Runtime, Weak, component, sleep,
+        Cancellable, Component, Duration, Rc, RefCell, Runtime, UnsafeCell, Weak, component, sleep,
+        transmute,
     },
-    compo_platform_loop::prelude::run,
 };
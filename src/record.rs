@@ -0,0 +1,209 @@
+use {
+    crate::{events::WindowEvent, frame::FrameClock, inject::InputInjector},
+    std::{
+        cell::RefCell,
+        rc::Rc,
+        time::{Duration, Instant},
+    },
+};
+#[cfg(feature = "record")]
+use {
+    std::{
+        fs::File,
+        io::{self, BufRead, BufReader, BufWriter, Write},
+        path::Path,
+    },
+    tracing::error,
+};
+
+/// The props of a `window` at some point of a recording.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowProps {
+    pub title: String,
+    pub width: i32,
+    pub height: i32,
+    pub left: i32,
+    pub top: i32,
+    pub visible: bool,
+    pub enabled: bool,
+    pub opacity: f32,
+}
+
+/// What a `Record` holds.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecordEntry {
+    /// The window was rendered with new props.
+    Props(WindowProps),
+    /// The window delivered an event to the app.
+    Event(WindowEvent),
+}
+
+/// One entry of a recording, with the time since the recorder was created.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct Record {
+    pub time: Duration,
+    pub entry: RecordEntry,
+}
+
+struct Inner {
+    start: Instant,
+    sink: Box<dyn FnMut(&Record)>,
+    // Props are only recorded when they change
+    props: Option<WindowProps>,
+}
+
+/// Records the events a `window` delivers and the props it is rendered with, with timestamps.
+///
+/// Pass it to the `recorder` prop of `window`. Events are recorded after the keymaps and the gesture
+/// recognizer had their say, exactly as the `on_*` event props and `WindowEvents` get them.
+#[derive(Clone)]
+pub struct Recorder(Rc<RefCell<Inner>>);
+
+impl Recorder {
+    /// Hand every record to `sink` as it happens.
+    pub fn new(sink: impl FnMut(&Record) + 'static) -> Self {
+        Self(Rc::new(RefCell::new(Inner {
+            start: Instant::now(),
+            sink: Box::new(sink),
+            props: None,
+        })))
+    }
+
+    /// Write the records to the file at `path` as JSON lines, one record per line.
+    ///
+    /// Every line is flushed right away, so the recording survives a crash of the app.
+    #[cfg(feature = "record")]
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        Ok(Self::new(move |record| {
+            let result = serde_json::to_writer(&mut file, record)
+                .map_err(io::Error::from)
+                .and_then(|()| writeln!(file))
+                .and_then(|()| file.flush());
+            if let Err(e) = result {
+                error!("Failed to write a record: {}", e);
+            }
+        }))
+    }
+
+    // Record the props of a render, unless they didn't change
+    pub(crate) fn record_props(&self, props: WindowProps) {
+        if self.0.borrow().props.as_ref() == Some(&props) {
+            return;
+        }
+        self.0.borrow_mut().props = Some(props.clone());
+        self.record(RecordEntry::Props(props));
+    }
+
+    #[cfg_attr(
+        all(any(target_os = "macos", target_os = "ios"), not(headless)),
+        allow(dead_code)
+    )]
+    pub(crate) fn record_event(&self, event: &WindowEvent) {
+        self.record(RecordEntry::Event(event.clone()));
+    }
+
+    fn record(&self, entry: RecordEntry) {
+        let mut inner = self.0.borrow_mut();
+        let record = Record {
+            time: inner.start.elapsed(),
+            entry,
+        };
+        (inner.sink)(&record);
+    }
+}
+
+/// A recording made with a `Recorder`, to be played back with `replay`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    pub records: Vec<Record>,
+}
+
+impl From<Vec<Record>> for Recording {
+    fn from(records: Vec<Record>) -> Self {
+        Self { records }
+    }
+}
+
+#[cfg(feature = "record")]
+impl Recording {
+    /// Load a recording written by `Recorder::create`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut records = Vec::new();
+        for (number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            if let Some(record) = parse_line(&line?, number + 1)? {
+                records.push(record);
+            }
+        }
+        Ok(Self { records })
+    }
+
+    /// Parse JSON lines as written by `Recorder::create`. Blank lines are skipped.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut records = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            if let Some(record) = parse_line(line, number + 1)? {
+                records.push(record);
+            }
+        }
+        Ok(Self { records })
+    }
+}
+
+#[cfg(feature = "record")]
+fn parse_line(line: &str, number: usize) -> io::Result<Option<Record>> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(line).map(Some).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid record on line {}: {}", number, e),
+        )
+    })
+}
+
+/// Feed the events of `recording` to the window `injector` is attached to, keeping their timing.
+///
+/// The replay runs on a virtual clock driven by `frame_clock`, which should be the clock of the same window:
+/// every frame advances it by one frame interval, and the events due by then are delivered right after the
+/// frame. How fast the machine is doesn't matter, each event lands after the same frame on every run, and a
+/// slow app only plays back slower. `speed` scales the timing: `2.0` plays twice as fast, and `f64::INFINITY`
+/// delivers all events after the first frame. Props are not replayed, the app renders its own. Gestures were
+/// recorded as they were recognized, so replayed touches don't feed the gesture recognizer again.
+///
+/// # Panics
+/// If `speed` is not positive.
+pub async fn replay(
+    recording: &Recording,
+    injector: &InputInjector,
+    frame_clock: &FrameClock,
+    speed: f64,
+) {
+    assert!(speed > 0.0, "replay speed must be positive, got {}", speed);
+    let Some(first) = recording.records.first() else {
+        return;
+    };
+    let mut events = recording
+        .records
+        .iter()
+        .filter_map(|record| match &record.entry {
+            RecordEntry::Event(event) => Some((record.time.saturating_sub(first.time), event)),
+            RecordEntry::Props(_) => None,
+        })
+        .peekable();
+    // Taken once, so a change of display doesn't move the events to other frames
+    let interval = frame_clock.interval().as_secs_f64();
+    let mut frames = 0u64;
+    while events.peek().is_some() {
+        frame_clock.next_frame().await;
+        let now = frames as f64 * interval;
+        while let Some((_, event)) = events.next_if(|(time, _)| time.as_secs_f64() / speed <= now) {
+            injector.replay(event.clone());
+        }
+        frames += 1;
+    }
+}
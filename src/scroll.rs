@@ -0,0 +1,42 @@
+use crate::keyboard::Modifiers;
+
+/// How far a scroll event scrolls.
+///
+/// Positive values scroll towards the right and the bottom of the content, i.e. the content moves left and up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScrollDelta {
+    /// Wheel steps, one per notch. High-resolution wheels report fractions of a notch.
+    Lines { x: f64, y: f64 },
+    /// Precise distance in logical units, as reported by touchpads and touch screens.
+    Pixels { x: f64, y: f64 },
+}
+
+/// Where a scroll event falls within a scroll gesture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScrollPhase {
+    /// The fingers touched down and started scrolling. Wheels begin a gesture with their first event.
+    Began,
+    /// The gesture continues.
+    Changed,
+    /// The fingers lifted. Momentum events may follow. Wheels end a gesture once they pause, with an event
+    /// that scrolls no further.
+    Ended,
+    /// Inertial scrolling after the fingers lifted.
+    Momentum,
+}
+
+/// A scroll event delivered by the `on_scroll` event of `window`, on Windows and Android but not yet on macOS
+/// and iOS.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScrollEvent {
+    /// Horizontal pointer position in logical units, relative to the client area.
+    pub x: f64,
+    /// Vertical pointer position in logical units, relative to the client area.
+    pub y: f64,
+    pub delta: ScrollDelta,
+    pub phase: ScrollPhase,
+    pub modifiers: Modifiers,
+}
@@ -0,0 +1,262 @@
+use {
+    crate::{capture::Capture, geometry::Rect},
+    std::{cell::RefCell, mem::take, rc::Rc},
+};
+
+// Damaged areas beyond this many are merged into one
+const MAX_DAMAGE: usize = 16;
+
+/// The pixels of a `SoftwareSurface` handed to its draw callback.
+///
+/// Each pixel is a `u32` in `0x00RRGGBB` form, rows top to bottom without padding, so the pixel at (`x`, `y`)
+/// is `pixels[y * width + x]`. The upper byte is ignored.
+pub struct PixelBuffer<'a> {
+    pub pixels: &'a mut [u32],
+    /// In physical pixels.
+    pub width: u32,
+    /// In physical pixels.
+    pub height: u32,
+    /// Physical pixels per logical pixel, as reported through a `WindowContext`. `1.0` for a surface
+    /// passed to `window` directly.
+    pub scale_factor: f64,
+    /// The areas to repaint, in physical pixels within the buffer. Outside them the pixels still hold the
+    /// last frame and aren't presented again, so the callback may leave them alone. The whole buffer for the
+    /// first frame, after a resize, and unless areas were marked with `SoftwareSurface::damage`. A callback
+    /// that changes pixels elsewhere adds their area, or they only show with a later frame.
+    pub damage: Vec<Rect>,
+    // What `paint_region` painted, so the context knows which damaged areas a layer covers
+    pub(crate) painted: Option<Rect>,
+}
+
+impl PixelBuffer<'_> {
+    /// Set every pixel to `color`.
+    pub fn fill(&mut self, color: u32) {
+        self.pixels.fill(color);
+    }
+
+    /// Set the pixels of `rect`, in physical pixels, to `color`.
+    pub fn fill_rect(&mut self, rect: Rect, color: u32) {
+        let rect = rect.intersection(&self.bounds());
+        if rect.is_empty() {
+            return;
+        }
+        for y in rect.y..rect.bottom() {
+            let start = (y * self.width as i32 + rect.x) as usize;
+            self.pixels[start..start + rect.width as usize].fill(color);
+        }
+    }
+
+    /// The whole buffer.
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width as i32, self.height as i32)
+    }
+
+    /// Whether the pixel at (`x`, `y`) lies in a damaged area.
+    pub fn is_damaged(&self, x: u32, y: u32) -> bool {
+        let pixel = Rect::new(x as i32, y as i32, 1, 1);
+        self.damage.iter().any(|rect| rect.contains(&pixel))
+    }
+}
+
+pub(crate) type Draw = Box<dyn FnMut(&mut PixelBuffer)>;
+
+struct Inner {
+    draw: Option<Draw>,
+    // The last frame, kept at the window's physical size
+    pixels: Vec<u32>,
+    width: u32,
+    height: u32,
+    scale_factor: f64,
+    // Areas to repaint with the next frame, `None` while none were marked
+    damage: Option<Vec<Rect>>,
+    // The next frame repaints everything anyway
+    full: bool,
+    // What the last frame repainted
+    last_damage: Vec<Rect>,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            draw: None,
+            pixels: Vec::new(),
+            width: 0,
+            height: 0,
+            scale_factor: 1.0,
+            damage: None,
+            full: false,
+            last_damage: Vec::new(),
+        }
+    }
+}
+
+/// A CPU framebuffer for a `window`, to draw without any GPU.
+///
+/// Pass it to the `surface` prop of `window`. The draw callback receives a `PixelBuffer` sized to the
+/// window's client area in physical pixels whenever the window needs its contents: when it is shown, when
+/// it is resized, and when the platform asks for a repaint. The buffer is reallocated on resize, and its
+/// contents survive from one frame to the next as long as the size stays the same.
+///
+/// A frame repaints the whole buffer, unless only parts of it were marked with `damage` since the last
+/// frame. Then the callback only has to repaint these, see `PixelBuffer::damage`, and the backend only
+/// presents them where the platform allows.
+///
+/// The frame is presented with GDI on Windows, as the contents of the view's layer on macOS and iOS, and
+/// through the activity's `ANativeWindow` on Android. The headless backend keeps the frame in memory, where
+/// `pixels` reads it back.
+#[derive(Clone, Default)]
+pub struct SoftwareSurface(Rc<RefCell<Inner>>);
+
+impl SoftwareSurface {
+    pub fn new(draw: impl FnMut(&mut PixelBuffer) + 'static) -> Self {
+        let surface = Self::default();
+        surface.set_draw(draw);
+        surface
+    }
+
+    /// Replace the draw callback. Takes effect with the next frame, which repaints everything.
+    pub fn set_draw(&self, draw: impl FnMut(&mut PixelBuffer) + 'static) {
+        let mut inner = self.0.borrow_mut();
+        inner.draw = Some(Box::new(draw));
+        inner.full = true;
+    }
+
+    /// Repaint `rect`, in physical pixels, with the next frame. Areas marked before the next frame add up,
+    /// and a frame with none marked repaints everything. Doesn't ask for a frame, see
+    /// `FrameClock::request_redraw`.
+    pub fn damage(&self, rect: Rect) {
+        if rect.is_empty() {
+            return;
+        }
+        let mut inner = self.0.borrow_mut();
+        let damage = inner.damage.get_or_insert_with(Vec::new);
+        if damage.iter().any(|existing| existing.contains(&rect)) {
+            return;
+        }
+        damage.retain(|existing| !rect.contains(existing));
+        damage.push(rect);
+        if damage.len() > MAX_DAMAGE {
+            let bounds = damage.iter().fold(rect, |bounds, rect| bounds.union(rect));
+            *damage = vec![bounds];
+        }
+    }
+
+    /// Repaint everything with the next frame.
+    pub fn invalidate(&self) {
+        self.0.borrow_mut().full = true;
+    }
+
+    // Let the next frame repaint only the damaged areas, even if none are marked, for callbacks that find out
+    // what to repaint while drawing
+    pub(crate) fn damage_nothing(&self) {
+        self.0.borrow_mut().damage.get_or_insert_with(Vec::new);
+    }
+
+    /// The areas the last frame repainted, in physical pixels. Empty before the first frame.
+    pub fn last_damage(&self) -> Vec<Rect> {
+        self.0.borrow().last_damage.clone()
+    }
+
+    /// The size of the last frame, in physical pixels. `(0, 0)` before the first frame.
+    pub fn size(&self) -> (u32, u32) {
+        let inner = self.0.borrow();
+        (inner.width, inner.height)
+    }
+
+    /// Physical pixels per logical pixel, handed to the draw callback.
+    pub fn scale_factor(&self) -> f64 {
+        self.0.borrow().scale_factor
+    }
+
+    /// A copy of the last frame, in the layout of `PixelBuffer`.
+    pub fn pixels(&self) -> Vec<u32> {
+        self.0.borrow().pixels.clone()
+    }
+
+    /// The last frame as RGBA, `None` before the first frame and while the window has no area.
+    pub fn capture(&self) -> Option<Capture> {
+        let inner = self.0.borrow();
+        if inner.pixels.is_empty() {
+            return None;
+        }
+        Some(Capture::from_frame(
+            &inner.pixels,
+            inner.width,
+            inner.height,
+        ))
+    }
+
+    pub(crate) fn set_scale_factor(&self, scale_factor: f64) {
+        let mut inner = self.0.borrow_mut();
+        if inner.scale_factor != scale_factor {
+            inner.scale_factor = scale_factor;
+            inner.full = true;
+        }
+    }
+
+    // Draw a frame of `width` x `height` physical pixels and hand it to `present` along with the areas that
+    // changed
+    pub(crate) fn present(
+        &self,
+        width: u32,
+        height: u32,
+        present: impl FnOnce(&[u32], u32, u32, &[Rect]),
+    ) {
+        let bounds = Rect::new(0, 0, width as i32, height as i32);
+        // Neither the buffer nor the callback stay borrowed while drawing, so the callback may use the surface
+        let (mut pixels, draw, scale_factor, damage) = {
+            let mut inner = self.0.borrow_mut();
+            let full = take(&mut inner.full);
+            let mut damage = inner.damage.take().filter(|_| !full);
+            if (inner.width, inner.height) != (width, height) {
+                inner.pixels = Vec::new();
+                inner.width = width;
+                inner.height = height;
+                damage = None;
+            }
+            let damage = match damage {
+                Some(damage) => damage
+                    .iter()
+                    .map(|rect| rect.intersection(&bounds))
+                    .filter(|rect| !rect.is_empty())
+                    .collect(),
+                None => vec![bounds],
+            };
+            (
+                take(&mut inner.pixels),
+                inner.draw.take(),
+                inner.scale_factor,
+                damage,
+            )
+        };
+        pixels.resize(width as usize * height as usize, 0);
+        let mut buffer = PixelBuffer {
+            pixels: &mut pixels,
+            width,
+            height,
+            scale_factor,
+            damage,
+            painted: None,
+        };
+        let draw = draw.map(|mut draw| {
+            draw(&mut buffer);
+            draw
+        });
+        let damage = take(&mut buffer.damage)
+            .into_iter()
+            .map(|rect| rect.intersection(&bounds))
+            .filter(|rect| !rect.is_empty())
+            .collect::<Vec<_>>();
+        present(&pixels, width, height, &damage);
+
+        let mut inner = self.0.borrow_mut();
+        if (inner.width, inner.height) == (width, height) {
+            inner.pixels = pixels;
+        }
+        inner.last_damage = damage;
+        // Keep a callback set while drawing
+        if inner.draw.is_none() {
+            inner.draw = draw;
+        }
+    }
+}
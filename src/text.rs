@@ -0,0 +1,294 @@
+#[cfg(feature = "widgets")]
+use cosmic_text::{Cursor, Motion};
+use {
+    crate::{
+        canvas::paint_region,
+        context::{Layer, WindowContext},
+        layout::LayoutNode,
+        surface::PixelBuffer,
+    },
+    compo::prelude::*,
+    cosmic_text::{
+        Align, Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, Style, SwashCache,
+        Weight, Wrap,
+    },
+    std::{io, path::Path},
+    taffy::{AvailableSpace, Size},
+};
+
+thread_local! {
+    // Loading the system fonts takes a moment, so it waits for the first text
+    static GLOBAL: Fonts = Fonts::new();
+}
+
+struct Inner {
+    system: FontSystem,
+    cache: SwashCache,
+}
+
+/// The fonts text is shaped and drawn with: the fonts of the system and the ones the app loads.
+///
+/// System fonts are found through the fontconfig configuration on Linux and the BSDs, in the font
+/// directories of Windows and macOS, in `/system/fonts` on Android and in `/System/Library/Fonts` on iOS.
+/// Characters a font lacks fall back to other fonts, e.g. for CJK, Arabic or emoji.
+#[derive(Clone)]
+pub struct Fonts(Rc<RefCell<Inner>>);
+
+impl Fonts {
+    fn new() -> Self {
+        #[cfg_attr(not(any(target_os = "android", target_os = "ios")), allow(unused_mut))]
+        let mut system = FontSystem::new();
+        // fontdb doesn't look for the fonts of these systems
+        #[cfg(target_os = "android")]
+        system.db_mut().load_fonts_dir("/system/fonts");
+        #[cfg(target_os = "ios")]
+        system.db_mut().load_fonts_dir("/System/Library/Fonts");
+        Self(Rc::new(RefCell::new(Inner {
+            system,
+            cache: SwashCache::new(),
+        })))
+    }
+
+    /// The fonts of the application, shared by all its windows.
+    pub fn global() -> Self {
+        GLOBAL.with(Clone::clone)
+    }
+
+    /// Load the fonts of a TrueType or OpenType file, or of a font collection.
+    pub fn load_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.0.borrow_mut().system.db_mut().load_font_file(path)
+    }
+
+    /// Load the fonts of a TrueType or OpenType file in memory, e.g. from `include_bytes!`.
+    pub fn load_data(&self, data: Vec<u8>) {
+        self.0.borrow_mut().system.db_mut().load_font_data(data);
+    }
+
+    /// The names of the font families available, sorted and without duplicates.
+    pub fn families(&self) -> Vec<String> {
+        let inner = self.0.borrow();
+        let mut families = inner
+            .system
+            .db()
+            .faces()
+            .flat_map(|face| face.families.iter().map(|(name, _)| name.clone()))
+            .collect::<Vec<_>>();
+        families.sort();
+        families.dedup();
+        families
+    }
+
+    // Lay out `text` with `style` at `scale` physical pixels per logical pixel, breaking lines at `width`
+    // physical pixels if wrapping
+    pub(crate) fn layout(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        scale: f32,
+        width: Option<f32>,
+    ) -> Buffer {
+        let inner = &mut *self.0.borrow_mut();
+        let size = style.size * scale;
+        let mut buffer = Buffer::new(
+            &mut inner.system,
+            Metrics::relative(size, style.line_height),
+        );
+        buffer.set_wrap(if style.wrap {
+            Wrap::WordOrGlyph
+        } else {
+            Wrap::None
+        });
+        buffer.set_size(width, None);
+        let family = match style.family.as_str() {
+            "" | "sans-serif" => Family::SansSerif,
+            "serif" => Family::Serif,
+            "monospace" => Family::Monospace,
+            name => Family::Name(name),
+        };
+        let attrs = Attrs::new()
+            .family(family)
+            .weight(if style.bold {
+                Weight::BOLD
+            } else {
+                Weight::NORMAL
+            })
+            .style(if style.italic {
+                Style::Italic
+            } else {
+                Style::Normal
+            });
+        let align = match style.align {
+            TextAlign::Start => None,
+            TextAlign::Center => Some(Align::Center),
+            TextAlign::End => Some(Align::End),
+            TextAlign::Justify => Some(Align::Justified),
+        };
+        buffer.set_text(text, &attrs, Shaping::Advanced, align);
+        buffer.shape_until_scroll(&mut inner.system, false);
+        buffer
+    }
+
+    // Move `cursor` through `buffer` by `motion`, keeping to the column `x` when moving up and down
+    #[cfg(feature = "widgets")]
+    pub(crate) fn motion(
+        &self,
+        buffer: &mut Buffer,
+        cursor: Cursor,
+        x: Option<i32>,
+        motion: Motion,
+    ) -> Option<(Cursor, Option<i32>)> {
+        buffer.cursor_motion(&mut self.0.borrow_mut().system, cursor, x, motion)
+    }
+
+    // Rasterize the glyphs of `buffer`, handing each covered span of pixels to `span` with its color
+    pub(crate) fn draw(
+        &self,
+        buffer: &mut Buffer,
+        color: u32,
+        span: impl FnMut(i32, i32, u32, u32, Color),
+    ) {
+        let inner = &mut *self.0.borrow_mut();
+        let [b, g, r, _] = color.to_le_bytes();
+        buffer.draw(
+            &mut inner.system,
+            &mut inner.cache,
+            Color::rgb(r, g, b),
+            span,
+        );
+    }
+}
+
+/// How the lines of a text line up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextAlign {
+    /// Left for left-to-right text, right for right-to-left text.
+    #[default]
+    Start,
+    Center,
+    /// Right for left-to-right text, left for right-to-left text.
+    End,
+    /// Stretched to both edges, except for the last line of a paragraph.
+    Justify,
+}
+
+/// How text looks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    /// A family name, or `serif`, `sans-serif` or `monospace`. Empty for the default sans-serif font.
+    pub family: String,
+    /// In logical pixels.
+    pub size: f32,
+    /// Relative to `size`.
+    pub line_height: f32,
+    /// In `0x00RRGGBB` form.
+    pub color: u32,
+    pub bold: bool,
+    pub italic: bool,
+    pub align: TextAlign,
+    /// Break lines that don't fit the width, between words where possible.
+    pub wrap: bool,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            family: String::new(),
+            size: 14.0,
+            line_height: 1.2,
+            color: 0x000000,
+            bold: false,
+            italic: false,
+            align: TextAlign::Start,
+            wrap: true,
+        }
+    }
+}
+
+impl TextStyle {
+    /// The size of `text` in logical pixels, with lines broken at `max_width` if wrapping.
+    pub fn measure(&self, text: &str, max_width: Option<f32>) -> (f32, f32) {
+        let buffer = Fonts::global().layout(text, self, 1.0, max_width);
+        buffer
+            .layout_runs()
+            .fold((0.0, 0.0), |(width, height), run| {
+                (width.max(run.line_w), height + run.line_height)
+            })
+    }
+}
+
+// Text in a window. Placed at `x`, `y` in logical pixels, a `width` or `height` of 0 extends it to the right
+// or bottom edge of the window. Placed by `layout` instead, if given, which is sized to the text unless its
+// style gives it a size. The font and color the props leave out come from the theme of the context
+#[component]
+pub async fn label(
+    context: Option<&WindowContext>,
+    text: &str,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    family: &str,
+    size: Option<f32>,
+    #[default = 1.2] line_height: f32,
+    color: Option<u32>,
+    bold: bool,
+    italic: bool,
+    align: TextAlign,
+    #[default = true] wrap: bool,
+    layout: Option<&LayoutNode>,
+) {
+    #[field]
+    let layer: Option<Layer> = None;
+
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let text = text.to_string();
+    let theme = context.theme();
+    let style = TextStyle {
+        family: match *family {
+            "" => theme.typography.family.clone(),
+            family => family.to_string(),
+        },
+        size: size.unwrap_or(theme.typography.size),
+        line_height: *line_height,
+        color: color.unwrap_or(theme.palette.text),
+        bold: *bold,
+        italic: *italic,
+        align: *align,
+        wrap: *wrap,
+    };
+    let layout = layout.cloned();
+    if let Some(layout) = &layout {
+        let (text, style) = (text.clone(), style.clone());
+        let content = (text.clone(), style.clone());
+        layout.set_measure(content, move |known, available| {
+            let max_width = known.width.or(match available.width {
+                AvailableSpace::Definite(width) => Some(width),
+                AvailableSpace::MinContent => Some(0.0),
+                AvailableSpace::MaxContent => None,
+            });
+            let (width, height) = style.measure(&text, max_width);
+            Size {
+                width: known.width.unwrap_or(width),
+                height: known.height.unwrap_or(height),
+            }
+        });
+    }
+    let (x, y, width, height) = (*x, *y, *width, *height);
+    let layer = layer.get_or_insert_with(|| context.add_layer());
+    let color = *color;
+    layer.set_draw(move |buffer: &mut PixelBuffer| {
+        // Without a color of its own, the text follows the theme, which may change between frames
+        let style = TextStyle {
+            color: color.unwrap_or_else(|| context.theme().palette.text),
+            ..style.clone()
+        };
+        paint_region(buffer, layout.as_ref(), (x, y, width, height), |painter| {
+            let rect = tiny_skia::Rect::from_xywh(0.0, 0.0, painter.width(), painter.height());
+            if let Some(rect) = rect {
+                painter.draw_text(&text, &style, rect);
+            }
+        });
+    });
+}
@@ -0,0 +1,957 @@
+use {
+    crate::{
+        canvas::Painter,
+        context::WindowContext,
+        geometry::Rect,
+        keyboard::{Key, KeyEvent, Modifiers, NamedKey},
+        layout::{LayoutNode, LayoutRect},
+        scroll::ScrollDelta,
+        text::{Fonts, TextStyle},
+        text_input::{ImeRequest, TextInputEvent},
+        theme::Theme,
+        widget::{Input, Look, Widget, fill, paint, rounded_rect, stroke},
+        window::{clipboard_text, set_clipboard_text},
+    },
+    compo::prelude::*,
+    cosmic_text::{Buffer, Cursor, Motion},
+    std::ops::Range,
+};
+
+// Shown for each character of a password
+const MASK: char = '\u{2022}';
+// Pixels scrolled per wheel step
+const LINE_SCROLL: f32 = 40.0;
+
+// Moves the caret by words: Alt on Apple platforms, Control elsewhere
+fn word_modifier(modifiers: Modifiers) -> bool {
+    if cfg!(any(target_os = "macos", target_os = "ios")) {
+        modifiers.alt()
+    } else {
+        modifiers.control()
+    }
+}
+
+// What an edit did, typing and deleting in a row are undone together
+#[derive(Clone, Copy, PartialEq)]
+enum Edit {
+    Typing,
+    Deleting,
+    Other,
+}
+
+struct Snapshot {
+    text: String,
+    cursor: usize,
+    anchor: usize,
+}
+
+// What the user did to the text
+enum Outcome {
+    Changed,
+    Submitted,
+}
+
+// The text as drawn: masked for passwords, and with the composition of the input method at the caret
+struct Shown {
+    text: String,
+    // The offsets of each character boundary in the edited text and in the shown text
+    offsets: Vec<(usize, usize)>,
+    preedit: Range<usize>,
+}
+
+impl Shown {
+    fn shown(&self, offset: usize) -> usize {
+        let i = self.offsets.partition_point(|(source, _)| *source < offset);
+        self.offsets
+            .get(i)
+            .map_or(self.text.len(), |(_, shown)| *shown)
+    }
+
+    // Offsets within the composition go to the caret
+    fn source(&self, shown: usize) -> usize {
+        let i = self.offsets.partition_point(|(_, s)| *s <= shown);
+        self.offsets[i.max(1) - 1].0
+    }
+
+    fn cursor(&self, offset: usize) -> Cursor {
+        let before = &self.text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Cursor::new(before.matches('\n').count(), offset - line_start)
+    }
+
+    fn offset(&self, cursor: Cursor) -> usize {
+        let line_start = match cursor.line {
+            0 => 0,
+            line => self
+                .text
+                .match_indices('\n')
+                .nth(line - 1)
+                .map_or(self.text.len(), |(i, _)| i + 1),
+        };
+        (line_start + cursor.index).min(self.text.len())
+    }
+}
+
+// The state of a text field, in byte offsets into its text
+struct Editor {
+    text: String,
+    // The selection goes from `anchor` to `cursor`, it is empty when they are equal
+    cursor: usize,
+    anchor: usize,
+    preedit: String,
+    preedit_cursor: Option<(usize, usize)>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last_edit: Option<Edit>,
+    // The column kept while moving up and down
+    goal_x: Option<i32>,
+    scroll: (f32, f32),
+    // The text prop, which the text follows whenever it changes
+    prop: Option<String>,
+    multiline: bool,
+    password: bool,
+    max_length: usize,
+    style: TextStyle,
+    // Between the frame and the text
+    padding: f32,
+    placeholder: String,
+}
+
+impl Editor {
+    fn new(multiline: bool) -> Self {
+        Self {
+            text: String::new(),
+            cursor: 0,
+            anchor: 0,
+            preedit: String::new(),
+            preedit_cursor: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_edit: None,
+            goal_x: None,
+            scroll: (0.0, 0.0),
+            prop: None,
+            multiline,
+            password: false,
+            max_length: 0,
+            style: TextStyle::default(),
+            padding: 0.0,
+            placeholder: String::new(),
+        }
+    }
+
+    fn set_prop(&mut self, text: &str) {
+        if self.prop.as_deref() == Some(text) {
+            return;
+        }
+        self.prop = Some(text.to_string());
+        let text = self.clean(text);
+        if text != self.text {
+            self.text = text;
+            self.cursor = self.text.len();
+            self.anchor = self.cursor;
+            self.undo.clear();
+            self.redo.clear();
+            self.last_edit = None;
+        }
+    }
+
+    // Line breaks as `\n`, none in a single line, and no other control characters
+    fn clean(&self, text: &str) -> String {
+        text.replace("\r\n", "\n")
+            .chars()
+            .map(|c| if c == '\r' { '\n' } else { c })
+            .filter(|c| (*c == '\n' && self.multiline) || !c.is_control())
+            .collect()
+    }
+
+    fn selection(&self) -> Range<usize> {
+        self.cursor.min(self.anchor)..self.cursor.max(self.anchor)
+    }
+
+    fn shown(&self) -> Shown {
+        let mut text = String::with_capacity(self.text.len() + self.preedit.len());
+        let mut offsets = Vec::new();
+        let mut preedit = 0..0;
+        let boundaries = self
+            .text
+            .char_indices()
+            .map(|(i, c)| (i, Some(c)))
+            .chain([(self.text.len(), None)]);
+        for (offset, c) in boundaries {
+            offsets.push((offset, text.len()));
+            if offset == self.cursor && !self.preedit.is_empty() {
+                let start = text.len();
+                text.push_str(&self.preedit);
+                preedit = start..text.len();
+            }
+            match c {
+                Some(_) if self.password => text.push(MASK),
+                Some(c) => text.push(c),
+                None => (),
+            }
+        }
+        Shown {
+            text,
+            offsets,
+            preedit,
+        }
+    }
+
+    // The area text is laid out in, within the frame of the field
+    fn text_area(&self, width: f32, height: f32) -> LayoutRect {
+        let line_height = self.style.size * self.style.line_height;
+        let padding = self.padding;
+        let inner_width = (width - 2.0 * padding).max(0.0);
+        if self.multiline {
+            LayoutRect {
+                x: padding,
+                y: padding,
+                width: inner_width,
+                height: (height - 2.0 * padding).max(0.0),
+            }
+        } else {
+            LayoutRect {
+                x: padding,
+                y: ((height - line_height) / 2.0).max(0.0),
+                width: inner_width,
+                height: line_height,
+            }
+        }
+    }
+
+    // Lay out the shown text in logical pixels, wrapped at `width` in multiple lines
+    fn layout(&self, shown: &Shown, width: f32) -> Buffer {
+        let width = self.multiline.then_some(width);
+        Fonts::global().layout(&shown.text, &self.style, 1.0, width)
+    }
+
+    // Where the caret is in the shown text, within the composition while composing
+    fn caret(&self, shown: &Shown) -> usize {
+        match self.preedit_cursor {
+            _ if shown.preedit.is_empty() => shown.shown(self.cursor),
+            Some((start, _)) => shown.preedit.start + start.min(shown.preedit.len()),
+            None => shown.preedit.end,
+        }
+    }
+
+    // The caret as `(x, top)` in the laid out text
+    fn caret_position(&self, shown: &Shown, buffer: &Buffer) -> (f32, f32) {
+        let cursor = shown.cursor(self.caret(shown));
+        buffer.cursor_position(&cursor).unwrap_or_else(|| {
+            buffer
+                .layout_runs()
+                .filter(|run| run.line_i == cursor.line)
+                .last()
+                .map_or((0.0, 0.0), |run| (run.line_w, run.line_top))
+        })
+    }
+
+    // Scroll the caret into `area`
+    fn reveal(&mut self, area: LayoutRect) {
+        let shown = self.shown();
+        let buffer = self.layout(&shown, area.width);
+        let (x, top) = self.caret_position(&shown, &buffer);
+        let line_height = self.style.size * self.style.line_height;
+        let (content_width, content_height) =
+            buffer
+                .layout_runs()
+                .fold((0.0f32, 0.0f32), |(width, height), run| {
+                    (
+                        width.max(run.line_w),
+                        height.max(run.line_top + run.line_height),
+                    )
+                });
+        let (scroll_x, scroll_y) = &mut self.scroll;
+        if self.multiline {
+            *scroll_y = scroll_y
+                .min(top)
+                .max(top + line_height - area.height)
+                .min((content_height - area.height).max(0.0))
+                .max(0.0);
+        } else {
+            // One pixel more for the caret at the end
+            *scroll_x = scroll_x
+                .min(x)
+                .max(x + 1.0 - area.width)
+                .min((content_width + 1.0 - area.width).max(0.0))
+                .max(0.0);
+        }
+    }
+
+    fn scroll_by(&mut self, delta: ScrollDelta, area: LayoutRect) {
+        let (dx, dy) = match delta {
+            ScrollDelta::Lines { x, y } => (x as f32 * LINE_SCROLL, y as f32 * LINE_SCROLL),
+            ScrollDelta::Pixels { x, y } => (x as f32, y as f32),
+        };
+        let shown = self.shown();
+        let buffer = self.layout(&shown, area.width);
+        let (content_width, content_height) =
+            buffer
+                .layout_runs()
+                .fold((0.0f32, 0.0f32), |(width, height), run| {
+                    (
+                        width.max(run.line_w),
+                        height.max(run.line_top + run.line_height),
+                    )
+                });
+        // Scrolling down moves the content up
+        self.scroll = if self.multiline {
+            (
+                0.0,
+                (self.scroll.1 - dy)
+                    .min(content_height - area.height)
+                    .max(0.0),
+            )
+        } else {
+            (
+                (self.scroll.0 - dx - dy)
+                    .min(content_width + 1.0 - area.width)
+                    .max(0.0),
+                0.0,
+            )
+        };
+    }
+
+    // The offset under `x`, `y` in the frame of the field
+    fn hit(&self, x: f32, y: f32, area: LayoutRect) -> usize {
+        let shown = self.shown();
+        let buffer = self.layout(&shown, area.width);
+        let (x, y) = (x - area.x + self.scroll.0, y - area.y + self.scroll.1);
+        match buffer.hit(x, y) {
+            Some(cursor) => shown.source(shown.offset(cursor)),
+            None if y < 0.0 => 0,
+            None => self.text.len(),
+        }
+    }
+
+    // Where `motion` takes the caret
+    fn motion(&mut self, motion: Motion, area: LayoutRect) -> usize {
+        let shown = self.shown();
+        let mut buffer = self.layout(&shown, area.width);
+        let cursor = shown.cursor(shown.shown(self.cursor));
+        let keep_column = matches!(motion, Motion::Up | Motion::Down | Motion::Vertical(_));
+        let goal_x = if keep_column { self.goal_x } else { None };
+        match Fonts::global().motion(&mut buffer, cursor, goal_x, motion) {
+            Some((cursor, goal_x)) => {
+                self.goal_x = if keep_column { goal_x } else { None };
+                shown.source(shown.offset(cursor))
+            }
+            None => self.cursor,
+        }
+    }
+
+    fn move_to(&mut self, offset: usize, extend: bool) {
+        self.cursor = offset;
+        if !extend {
+            self.anchor = offset;
+        }
+        self.last_edit = None;
+    }
+
+    // The word, or run of spaces or punctuation, around `offset`
+    fn word_at(&self, offset: usize) -> Range<usize> {
+        if self.password {
+            return 0..self.text.len();
+        }
+        let class = |c: char| {
+            if c.is_alphanumeric() || c == '_' {
+                0
+            } else if c.is_whitespace() {
+                1
+            } else {
+                2
+            }
+        };
+        let after = self.text[offset..].chars().next();
+        let before = self.text[..offset].chars().next_back();
+        let Some(class_of) = after.or(before).map(class) else {
+            return offset..offset;
+        };
+        let start = self.text[..offset]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| class(*c) == class_of)
+            .last()
+            .map_or(offset, |(i, _)| i);
+        let end = self.text[offset..]
+            .char_indices()
+            .find(|(_, c)| class(*c) != class_of)
+            .map_or(self.text.len(), |(i, _)| offset + i);
+        start..end
+    }
+
+    fn line_at(&self, offset: usize) -> Range<usize> {
+        let start = self.text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = self.text[offset..]
+            .find('\n')
+            .map_or(self.text.len(), |i| offset + i);
+        start..end
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            text: self.text.clone(),
+            cursor: self.cursor,
+            anchor: self.anchor,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.text = snapshot.text;
+        self.cursor = snapshot.cursor;
+        self.anchor = snapshot.anchor;
+        self.last_edit = None;
+    }
+
+    // Replace the selection with `text`, as much of it as `max_length` leaves room for. Whether the text
+    // changed
+    fn replace(&mut self, text: &str, edit: Edit) -> bool {
+        let selection = self.selection();
+        let mut text = self.clean(text);
+        if self.max_length > 0 {
+            let kept = self.text.chars().count() - self.text[selection.clone()].chars().count();
+            let room = self.max_length.saturating_sub(kept);
+            if let Some((end, _)) = text.char_indices().nth(room) {
+                text.truncate(end);
+            }
+        }
+        if text.is_empty() && selection.is_empty() {
+            return false;
+        }
+        if self.last_edit != Some(edit) || edit == Edit::Other {
+            self.undo.push(self.snapshot());
+        }
+        self.redo.clear();
+        self.text.replace_range(selection.clone(), &text);
+        self.cursor = selection.start + text.len();
+        self.anchor = self.cursor;
+        self.last_edit = Some(edit);
+        true
+    }
+
+    // Delete the selection, or from the caret to where `motion` takes it
+    fn delete(&mut self, motion: Motion, area: LayoutRect) -> bool {
+        if self.cursor == self.anchor {
+            self.anchor = self.motion(motion, area);
+        }
+        self.replace("", Edit::Deleting)
+    }
+
+    fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(self.snapshot());
+        self.restore(snapshot);
+        true
+    }
+
+    fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(self.snapshot());
+        self.restore(snapshot);
+        true
+    }
+
+    // Copy the selection, but never a password
+    fn copy(&self) {
+        let selection = self.selection();
+        if !self.password && !selection.is_empty() {
+            set_clipboard_text(&self.text[selection]);
+        }
+    }
+
+    fn input(&mut self, input: Input, area: LayoutRect) -> Option<Outcome> {
+        match input {
+            Input::Press {
+                x,
+                y,
+                click_count,
+                modifiers,
+            } => {
+                let offset = self.hit(x, y, area);
+                match click_count {
+                    1 => self.move_to(offset, modifiers.shift()),
+                    2 => {
+                        let word = self.word_at(offset);
+                        self.move_to(word.start, false);
+                        self.move_to(word.end, true);
+                    }
+                    _ => {
+                        let line = self.line_at(offset);
+                        self.move_to(line.start, false);
+                        self.move_to(line.end, true);
+                    }
+                }
+                None
+            }
+            Input::Drag { x, y } => {
+                let offset = self.hit(x, y, area);
+                self.move_to(offset, true);
+                None
+            }
+            Input::Text(TextInputEvent::Preedit { text, cursor }) => {
+                // The composition replaces the selection once committed
+                if !text.is_empty() && self.cursor != self.anchor && self.preedit.is_empty() {
+                    self.replace("", Edit::Other);
+                }
+                self.preedit = text;
+                self.preedit_cursor = cursor;
+                None
+            }
+            Input::Text(TextInputEvent::Commit(text)) => {
+                self.preedit.clear();
+                self.preedit_cursor = None;
+                self.replace(&text, Edit::Typing)
+                    .then_some(Outcome::Changed)
+            }
+            Input::Key(key) if self.preedit.is_empty() => self.key(key, area),
+            Input::Scroll(delta) => {
+                self.scroll_by(delta, area);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn key(&mut self, key: KeyEvent, area: LayoutRect) -> Option<Outcome> {
+        let modifiers = key.modifiers;
+        let (shift, word) = (modifiers.shift(), word_modifier(modifiers));
+        let changed = |changed: bool| changed.then_some(Outcome::Changed);
+        if modifiers.contains(Modifiers::PRIMARY)
+            && let Key::Character(c) = key.logical_key
+        {
+            return match c.to_ascii_lowercase() {
+                'a' => {
+                    self.move_to(0, false);
+                    self.move_to(self.text.len(), true);
+                    None
+                }
+                'c' => {
+                    self.copy();
+                    None
+                }
+                'x' if !self.password => {
+                    self.copy();
+                    changed(self.replace("", Edit::Other))
+                }
+                'v' => {
+                    let text = clipboard_text().unwrap_or_default();
+                    changed(self.replace(&text, Edit::Other))
+                }
+                'z' if shift => changed(self.redo()),
+                'z' => changed(self.undo()),
+                'y' => changed(self.redo()),
+                _ => None,
+            };
+        }
+        let Key::Named(named) = key.logical_key else {
+            return None;
+        };
+        let motion = match named {
+            NamedKey::Enter if self.multiline => return changed(self.replace("\n", Edit::Typing)),
+            NamedKey::Enter => return Some(Outcome::Submitted),
+            NamedKey::Backspace => {
+                let motion = if word {
+                    Motion::LeftWord
+                } else {
+                    Motion::Previous
+                };
+                return changed(self.delete(motion, area));
+            }
+            NamedKey::Delete => {
+                let motion = if word {
+                    Motion::RightWord
+                } else {
+                    Motion::Next
+                };
+                return changed(self.delete(motion, area));
+            }
+            // Without Shift, a selection collapses to its side
+            NamedKey::ArrowLeft | NamedKey::ArrowRight
+                if !shift && !word && self.cursor != self.anchor =>
+            {
+                let selection = self.selection();
+                let start = (named == NamedKey::ArrowLeft) != self.is_rtl(area);
+                let offset = if start {
+                    selection.start
+                } else {
+                    selection.end
+                };
+                self.move_to(offset, false);
+                return None;
+            }
+            NamedKey::ArrowLeft if word => Motion::LeftWord,
+            NamedKey::ArrowLeft => Motion::Left,
+            NamedKey::ArrowRight if word => Motion::RightWord,
+            NamedKey::ArrowRight => Motion::Right,
+            NamedKey::ArrowUp if self.multiline => Motion::Up,
+            NamedKey::ArrowDown if self.multiline => Motion::Down,
+            NamedKey::ArrowUp => Motion::BufferStart,
+            NamedKey::ArrowDown => Motion::BufferEnd,
+            NamedKey::Home if modifiers.control() => Motion::BufferStart,
+            NamedKey::Home => Motion::Home,
+            NamedKey::End if modifiers.control() => Motion::BufferEnd,
+            NamedKey::End => Motion::End,
+            NamedKey::PageUp if self.multiline => Motion::Vertical(-area.height as i32),
+            NamedKey::PageDown if self.multiline => Motion::Vertical(area.height as i32),
+            _ => return None,
+        };
+        let offset = self.motion(motion, area);
+        let goal_x = self.goal_x;
+        self.move_to(offset, shift);
+        self.goal_x = goal_x;
+        None
+    }
+
+    // Whether the line of the caret runs from right to left
+    fn is_rtl(&self, area: LayoutRect) -> bool {
+        let shown = self.shown();
+        let buffer = self.layout(&shown, area.width);
+        let line = shown.cursor(shown.shown(self.cursor)).line;
+        buffer
+            .layout_runs()
+            .find(|run| run.line_i == line)
+            .is_some_and(|run| run.rtl)
+    }
+
+    fn draw(&self, painter: &mut Painter, look: Look, theme: &Theme) {
+        let (palette, radius) = (&theme.palette, theme.radii.medium);
+        let (width, height) = (painter.width(), painter.height());
+        let background = if look.enabled {
+            palette.surface
+        } else {
+            palette.disabled
+        };
+        fill(
+            painter,
+            rounded_rect(0.0, 0.0, width, height, radius),
+            background,
+        );
+        if look.focused {
+            stroke(
+                painter,
+                rounded_rect(1.0, 1.0, width - 2.0, height - 2.0, radius - 1.0),
+                palette.accent,
+                2.0,
+            );
+        } else {
+            let border = match look {
+                Look { enabled: false, .. } => palette.text_disabled,
+                Look { hovered: true, .. } => palette.text,
+                _ => palette.border,
+            };
+            stroke(
+                painter,
+                rounded_rect(0.5, 0.5, width - 1.0, height - 1.0, radius - 0.5),
+                border,
+                1.0,
+            );
+        }
+
+        let area = self.text_area(width, height);
+        let Some(clip) = tiny_skia::Rect::from_xywh(
+            area.x,
+            self.padding.min(area.y),
+            area.width,
+            height - 2.0 * self.padding.min(area.y),
+        ) else {
+            return;
+        };
+        painter.save();
+        painter.clip_rect(clip);
+        painter.translate(area.x - self.scroll.0, area.y - self.scroll.1);
+
+        let shown = self.shown();
+        let buffer = self.layout(&shown, area.width);
+        let line_height = self.style.size * self.style.line_height;
+        let span = |painter: &mut Painter, range: Range<usize>, color: u32, underline: bool| {
+            let (start, end) = (shown.cursor(range.start), shown.cursor(range.end));
+            for run in buffer.layout_runs() {
+                for (x, width) in run.highlight(start, end) {
+                    let (y, height) = if underline {
+                        (run.line_top + run.line_height - 2.0, 1.0)
+                    } else {
+                        (run.line_top, run.line_height)
+                    };
+                    if let Some(rect) = tiny_skia::Rect::from_xywh(x, y, width, height) {
+                        painter.fill_rect(rect, &paint(color));
+                    }
+                }
+            }
+        };
+
+        let selection = self.selection();
+        if !selection.is_empty() {
+            let color = if look.focused {
+                palette.selection
+            } else {
+                palette.selection_unfocused
+            };
+            span(
+                painter,
+                shown.shown(selection.start)..shown.shown(selection.end),
+                color,
+                false,
+            );
+        }
+
+        let (content_width, content_height) =
+            buffer
+                .layout_runs()
+                .fold((0.0f32, 0.0f32), |(width, height), run| {
+                    (
+                        width.max(run.line_w),
+                        height.max(run.line_top + run.line_height),
+                    )
+                });
+        let (text, color) = match shown.text.is_empty() {
+            true => (self.placeholder.as_str(), palette.text_disabled),
+            false if look.enabled => (shown.text.as_str(), palette.text),
+            false => (shown.text.as_str(), palette.text_disabled),
+        };
+        let style = TextStyle {
+            color,
+            ..self.style.clone()
+        };
+        let text_width = if self.multiline {
+            area.width
+        } else {
+            // Wide enough for all of the line, however far it is scrolled
+            content_width.max(area.width) + 1.0
+        };
+        let text_height = content_height.max(line_height);
+        if let Some(rect) = tiny_skia::Rect::from_xywh(0.0, 0.0, text_width, text_height) {
+            painter.draw_text(text, &style, rect);
+        }
+
+        if !shown.preedit.is_empty() {
+            span(painter, shown.preedit.clone(), palette.text, true);
+        }
+        if look.focused && look.enabled {
+            let (x, top) = self.caret_position(&shown, &buffer);
+            let caret = tiny_skia::Rect::from_xywh(x.round(), top, 1.0, line_height);
+            if let Some(caret) = caret {
+                painter.fill_rect(caret, &paint(palette.text));
+            }
+        }
+        painter.restore();
+    }
+
+    // The caret in the window, in physical pixels
+    fn caret_area(&self, bounds: LayoutRect, scale_factor: f32) -> Rect {
+        let area = self.text_area(bounds.width, bounds.height);
+        let shown = self.shown();
+        let buffer = self.layout(&shown, area.width);
+        let (x, top) = self.caret_position(&shown, &buffer);
+        let x = bounds.x + area.x + x - self.scroll.0;
+        let y = bounds.y + area.y + top - self.scroll.1;
+        let line_height = self.style.size * self.style.line_height;
+        Rect::new(
+            (x * scale_factor).round() as i32,
+            (y * scale_factor).round() as i32,
+            scale_factor.ceil() as i32,
+            (line_height * scale_factor).round() as i32,
+        )
+    }
+}
+
+// What both text fields share
+struct Field {
+    widget: Widget<()>,
+    editor: Rc<RefCell<Editor>>,
+}
+
+impl Field {
+    fn new(context: &WindowContext, multiline: bool) -> Self {
+        let field = Self {
+            widget: Widget::new(context, (), true),
+            editor: Rc::new(RefCell::new(Editor::new(multiline))),
+        };
+        let editor = field.editor.clone();
+        field
+            .widget
+            .set_draw(move |painter, look, (), theme| editor.borrow().draw(painter, look, theme));
+        field
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn update(
+        &self,
+        context: &WindowContext,
+        region: (f32, f32, f32, f32),
+        layout: Option<&LayoutNode>,
+        enabled: bool,
+        text: &str,
+        placeholder: &str,
+        size: Option<f32>,
+        password: bool,
+        max_length: usize,
+    ) {
+        self.widget.update(context, region, layout, enabled, ());
+        let theme = context.theme();
+        let mut editor = self.editor.borrow_mut();
+        editor.password = password;
+        editor.max_length = max_length;
+        editor.placeholder = placeholder.to_string();
+        editor.padding = theme.spacing.medium;
+        editor.style = TextStyle {
+            size: size.unwrap_or(theme.typography.size),
+            wrap: editor.multiline,
+            ..theme.text_style()
+        };
+        editor.set_prop(text);
+        let (multiline, style, padding) = (editor.multiline, editor.style.clone(), editor.padding);
+        drop(editor);
+
+        let content = (multiline, style.clone(), padding);
+        self.widget.measure(content, move |width| {
+            let line_height = style.size * style.line_height;
+            let lines = if multiline { 4.0 } else { 1.0 };
+            (width.unwrap_or(200.0), lines * line_height + 2.0 * padding)
+        });
+        self.layer_changed();
+    }
+
+    async fn next_input(&self, alive: impl Fn() -> bool) -> Option<Option<Outcome>> {
+        let input = self.widget.next_input(alive).await?;
+        let bounds = self.widget.bounds();
+        let mut editor = self.editor.borrow_mut();
+        let area = editor.text_area(bounds.width, bounds.height);
+        let scrolled = matches!(input, Input::Scroll(_));
+        let outcome = editor.input(input, area);
+        if !scrolled {
+            editor.reveal(area);
+        }
+        drop(editor);
+        self.layer_changed();
+        Some(outcome)
+    }
+
+    fn text(&self) -> String {
+        self.editor.borrow().text.clone()
+    }
+
+    // Redraw, and place the candidate window of the input method at the caret
+    fn layer_changed(&self) {
+        let editor = self.editor.borrow();
+        let context = self.widget.context();
+        let caret = editor.caret_area(self.widget.bounds(), context.scale_factor() as f32);
+        let layer = self.widget.layer();
+        layer.set_ime(Some(ImeRequest {
+            enabled: !editor.password,
+            cursor_area: caret,
+        }));
+        layer.request_redraw();
+    }
+}
+
+// A single line of editable text. Shows `text` until the user edits it, then `on_change` fires with every new
+// text and Enter fires `on_submit`. `placeholder` shows while the field is empty. A `password` shows dots in
+// place of its characters, can't be copied and turns the input method off. `max_length` limits the number
+// of characters, 0 for no limit. Placed like a `canvas`. Only Windows and the headless backend deliver the
+// key and text input that edits it
+#[component]
+pub async fn text_input(
+    context: Option<&WindowContext>,
+    text: &str,
+    placeholder: &str,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    size: Option<f32>,
+    password: bool,
+    max_length: usize,
+    #[default = true] enabled: bool,
+    layout: Option<&LayoutNode>,
+    #[event] on_change: String,
+    #[event] on_submit: String,
+) {
+    #[field]
+    let field: Option<Field> = None;
+    #[field]
+    // Bumped on every render, so only the latest render handles input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let field = field.get_or_insert_with(|| Field::new(&context, false));
+    field.update(
+        &context,
+        (*x, *y, *width, *height),
+        *layout,
+        *enabled,
+        text,
+        placeholder,
+        *size,
+        *password,
+        *max_length,
+    );
+
+    while let Some(outcome) = field.next_input(|| *render_id == current_render).await {
+        match outcome {
+            Some(Outcome::Changed) => {
+                let _ = on_change.emit(field.text());
+            }
+            Some(Outcome::Submitted) => {
+                let _ = on_submit.emit(field.text());
+            }
+            None => (),
+        }
+    }
+}
+
+// Several lines of editable text, wrapped at the width of the field and scrolled with the wheel. Enter starts
+// a new line. Otherwise like a `text_input`
+#[component]
+pub async fn text_area(
+    context: Option<&WindowContext>,
+    text: &str,
+    placeholder: &str,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    size: Option<f32>,
+    max_length: usize,
+    #[default = true] enabled: bool,
+    layout: Option<&LayoutNode>,
+    #[event] on_change: String,
+) {
+    #[field]
+    let field: Option<Field> = None;
+    #[field]
+    // Bumped on every render, so only the latest render handles input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let field = field.get_or_insert_with(|| Field::new(&context, true));
+    field.update(
+        &context,
+        (*x, *y, *width, *height),
+        *layout,
+        *enabled,
+        text,
+        placeholder,
+        *size,
+        false,
+        *max_length,
+    );
+
+    while let Some(outcome) = field.next_input(|| *render_id == current_render).await {
+        if let Some(Outcome::Changed) = outcome {
+            let _ = on_change.emit(field.text());
+        }
+    }
+}
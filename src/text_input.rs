@@ -0,0 +1,29 @@
+use crate::geometry::Rect;
+
+/// Text entered into a window, delivered by the `on_text_input` event of `window`.
+///
+/// Plain typing arrives as `Commit` directly. With an input method (e.g. Pinyin or Kana), the text
+/// being composed arrives as `Preedit` updates until the user confirms it, which then arrives as `Commit`.
+/// Only Windows delivers text input so far, macOS and iOS don't implement `NSTextInputClient` and
+/// `UITextInput` yet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextInputEvent {
+    /// The composition changed. An empty `text` means the composition was cleared or finished.
+    Preedit {
+        text: String,
+        /// Byte range within `text` the input method highlights, or the caret when start equals end.
+        cursor: Option<(usize, usize)>,
+    },
+    /// Text to insert at the caret.
+    Commit(String),
+}
+
+/// What a layer asks of the input method while it has the focus, see `Layer::set_ime`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ImeRequest {
+    /// Whether an input method may compose text, `false` e.g. for password fields.
+    pub enabled: bool,
+    /// The caret in client-area pixels, the candidate window is placed next to it.
+    pub cursor_area: Rect,
+}
@@ -0,0 +1,184 @@
+#[cfg(feature = "text")]
+use crate::text::TextStyle;
+
+/// Whether the system shows light text on dark backgrounds or the other way around.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// The colors of a theme, in `0x00RRGGBB` form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    /// What the layers of a window are drawn on.
+    pub background: u32,
+    /// The inside of text fields and checkboxes, and the knobs of toggles and sliders.
+    pub surface: u32,
+    pub text: u32,
+    pub text_disabled: u32,
+    /// Text and marks on `accent`, e.g. the text of a button.
+    pub on_accent: u32,
+    /// Buttons, checked checkboxes and toggles, and the filled part of sliders and progress bars.
+    pub accent: u32,
+    pub accent_hovered: u32,
+    pub accent_pressed: u32,
+    /// Around the widget that has the keyboard focus.
+    pub focus_ring: u32,
+    pub border: u32,
+    /// The empty part of toggles, sliders and progress bars.
+    pub track: u32,
+    /// The fill of disabled widgets.
+    pub disabled: u32,
+    /// Selected text, and selected text of a field without the focus.
+    pub selection: u32,
+    pub selection_unfocused: u32,
+}
+
+/// The font of a theme.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Typography {
+    /// A family name, or `serif`, `sans-serif` or `monospace`. Empty for the default sans-serif font.
+    pub family: String,
+    /// In logical pixels.
+    pub size: f32,
+    /// Relative to `size`.
+    pub line_height: f32,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self {
+            family: String::new(),
+            size: 14.0,
+            line_height: 1.2,
+        }
+    }
+}
+
+/// The distances of a theme, in logical pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spacing {
+    pub small: f32,
+    /// Between a control and its text, and around the text in a field.
+    pub medium: f32,
+    /// Around the text of a button.
+    pub large: f32,
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self {
+            small: 4.0,
+            medium: 8.0,
+            large: 16.0,
+        }
+    }
+}
+
+/// The corner radii of a theme, in logical pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Radii {
+    /// Checkboxes.
+    pub small: f32,
+    /// Buttons and text fields.
+    pub medium: f32,
+}
+
+impl Default for Radii {
+    fn default() -> Self {
+        Self {
+            small: 3.0,
+            medium: 4.0,
+        }
+    }
+}
+
+/// How the widgets of a window look. See `WindowContext::theme`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub palette: Palette,
+    pub typography: Typography,
+    pub spacing: Spacing,
+    pub radii: Radii,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+impl Theme {
+    /// Dark text on light backgrounds.
+    pub fn light() -> Self {
+        Self {
+            palette: Palette {
+                background: 0xFFFFFF,
+                surface: 0xFFFFFF,
+                text: 0x1F1F1F,
+                text_disabled: 0xA0A0A0,
+                on_accent: 0xFFFFFF,
+                accent: 0x2F6FEB,
+                accent_hovered: 0x4A82F0,
+                accent_pressed: 0x1F56C7,
+                focus_ring: 0x9DBCF7,
+                border: 0x767676,
+                track: 0xD0D0D0,
+                disabled: 0xE2E2E2,
+                selection: 0xC7D9FB,
+                selection_unfocused: 0xDDDDDD,
+            },
+            typography: Typography::default(),
+            spacing: Spacing::default(),
+            radii: Radii::default(),
+        }
+    }
+
+    /// Light text on dark backgrounds.
+    pub fn dark() -> Self {
+        Self {
+            palette: Palette {
+                background: 0x1E1E1E,
+                surface: 0x2B2B2B,
+                text: 0xE8E8E8,
+                text_disabled: 0x6E6E6E,
+                on_accent: 0xFFFFFF,
+                accent: 0x3F7FEF,
+                accent_hovered: 0x5A92F2,
+                accent_pressed: 0x2D64CC,
+                focus_ring: 0x2F5597,
+                border: 0x8A8A8A,
+                track: 0x4A4A4A,
+                disabled: 0x3A3A3A,
+                selection: 0x264F78,
+                selection_unfocused: 0x3A3D41,
+            },
+            typography: Typography::default(),
+            spacing: Spacing::default(),
+            radii: Radii::default(),
+        }
+    }
+
+    /// The built-in theme of `scheme`.
+    pub fn for_scheme(scheme: ColorScheme) -> Self {
+        match scheme {
+            ColorScheme::Light => Self::light(),
+            ColorScheme::Dark => Self::dark(),
+        }
+    }
+
+    /// Text in the theme's font and text color. Needs the `text` feature.
+    #[cfg(feature = "text")]
+    pub fn text_style(&self) -> TextStyle {
+        TextStyle {
+            family: self.typography.family.clone(),
+            size: self.typography.size,
+            line_height: self.typography.line_height,
+            color: self.palette.text,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,750 @@
+use {
+    crate::{
+        canvas::{Painter, paint_region, region_rect},
+        context::{Layer, WindowContext},
+        events::{WindowEvent, WindowEvents},
+        keyboard::{Key, KeyEvent, Modifiers, NamedKey},
+        layout::{LayoutNode, LayoutRect},
+        pointer::PointerButton,
+        scroll::ScrollDelta,
+        surface::PixelBuffer,
+        text::{TextAlign, TextStyle},
+        text_input::TextInputEvent,
+        theme::Theme,
+    },
+    compo::prelude::*,
+    std::cell::Cell,
+    taffy::Size,
+    tiny_skia::{Color, FillRule, Paint, Path, PathBuilder, Rect, Stroke},
+};
+
+// What a widget looks like at the moment
+#[derive(Clone, Copy)]
+pub(crate) struct Look {
+    pub(crate) hovered: bool,
+    pub(crate) pressed: bool,
+    pub(crate) focused: bool,
+    pub(crate) enabled: bool,
+}
+
+// What a widget received, with positions in logical pixels from its top left corner
+pub(crate) enum Input {
+    Press {
+        x: f32,
+        y: f32,
+        click_count: u32,
+        modifiers: Modifiers,
+    },
+    Drag {
+        x: f32,
+        y: f32,
+    },
+    Release {
+        inside: bool,
+    },
+    // Only while focused
+    Key(KeyEvent),
+    // Only while focused
+    Text(TextInputEvent),
+    // Only while hovered
+    Scroll(ScrollDelta),
+}
+
+impl Input {
+    // A click, or Enter or Space while focused
+    pub(crate) fn activates(&self) -> bool {
+        match self {
+            Self::Release { inside } => *inside,
+            Self::Key(key) => {
+                !key.repeat
+                    && matches!(
+                        key.logical_key,
+                        Key::Named(NamedKey::Enter) | Key::Character(' ')
+                    )
+            }
+            _ => false,
+        }
+    }
+}
+
+struct State<T> {
+    hovered: Cell<bool>,
+    pressed: Cell<bool>,
+    enabled: Cell<bool>,
+    // The value shown, which follows the prop whenever the prop changes
+    value: Cell<T>,
+    prop: Cell<Option<T>>,
+    region: Cell<(f32, f32, f32, f32)>,
+    layout: RefCell<Option<LayoutNode>>,
+}
+
+// The parts of a widget that outlive its renders: its layer, its events and its state
+pub(crate) struct Widget<T> {
+    // The context of the latest render, which may bring another theme
+    context: RefCell<WindowContext>,
+    layer: Rc<Layer>,
+    // `None` for widgets without input
+    events: Option<WindowEvents>,
+    state: Rc<State<T>>,
+}
+
+impl<T: Copy + PartialEq + 'static> Widget<T> {
+    pub(crate) fn new(context: &WindowContext, value: T, interactive: bool) -> Self {
+        Self {
+            context: RefCell::new(context.clone()),
+            layer: Rc::new(context.add_layer()),
+            events: interactive.then(|| context.events()),
+            state: Rc::new(State {
+                hovered: Cell::new(false),
+                pressed: Cell::new(false),
+                enabled: Cell::new(true),
+                value: Cell::new(value),
+                prop: Cell::new(None),
+                region: Cell::new((0.0, 0.0, 0.0, 0.0)),
+                layout: RefCell::new(None),
+            }),
+        }
+    }
+
+    // Take the props of a render
+    pub(crate) fn update(
+        &self,
+        context: &WindowContext,
+        region: (f32, f32, f32, f32),
+        layout: Option<&LayoutNode>,
+        enabled: bool,
+        value: T,
+    ) {
+        self.context.replace(context.clone());
+        let state = &self.state;
+        state.region.set(region);
+        *state.layout.borrow_mut() = layout.cloned();
+        state.enabled.set(enabled);
+        if !enabled {
+            state.hovered.set(false);
+            state.pressed.set(false);
+        }
+        if state.prop.replace(Some(value)) != Some(value) {
+            state.value.set(value);
+        }
+        self.layer.set_focusable(enabled && self.events.is_some());
+    }
+
+    pub(crate) fn value(&self) -> T {
+        self.state.value.get()
+    }
+
+    pub(crate) fn set_value(&self, value: T) {
+        self.state.value.set(value);
+        self.layer.request_redraw();
+    }
+
+    pub(crate) fn context(&self) -> WindowContext {
+        self.context.borrow().clone()
+    }
+
+    pub(crate) fn layer(&self) -> &Layer {
+        &self.layer
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.state.enabled.get() && self.context.borrow().is_enabled()
+    }
+
+    // Where the widget is, in logical pixels
+    pub(crate) fn bounds(&self) -> LayoutRect {
+        let context = self.context.borrow();
+        let (width, height) = context.size();
+        let scale_factor = context.scale_factor() as f32;
+        region_rect(
+            self.state.layout.borrow().as_ref(),
+            self.state.region.get(),
+            (width as f32 / scale_factor, height as f32 / scale_factor),
+        )
+    }
+
+    // Size the layout node of the widget by `measure` of `content`, when its style leaves the size open
+    pub(crate) fn measure<C: PartialEq + 'static>(
+        &self,
+        content: C,
+        measure: impl Fn(Option<f32>) -> (f32, f32) + 'static,
+    ) {
+        if let Some(layout) = &*self.state.layout.borrow() {
+            layout.set_measure(content, move |known, _| {
+                let (width, height) = measure(known.width);
+                Size {
+                    width: known.width.unwrap_or(width),
+                    height: known.height.unwrap_or(height),
+                }
+            });
+        }
+    }
+
+    pub(crate) fn set_draw(&self, draw: impl Fn(&mut Painter, Look, T, &Theme) + 'static) {
+        let state = self.state.clone();
+        let layer = Rc::downgrade(&self.layer);
+        let context = self.context();
+        self.layer.set_draw(move |buffer: &mut PixelBuffer| {
+            let look = Look {
+                hovered: state.hovered.get(),
+                pressed: state.pressed.get(),
+                focused: layer.upgrade().is_some_and(|layer| layer.is_focused()),
+                enabled: state.enabled.get() && context.is_enabled(),
+            };
+            let layout = state.layout.borrow().clone();
+            let theme = context.theme();
+            paint_region(buffer, layout.as_ref(), state.region.get(), |painter| {
+                draw(painter, look, state.value.get(), &theme)
+            });
+        });
+    }
+
+    // Wait for input to the widget, keeping track of hovering, pressing and focus. `None` once `alive`
+    // returns `false`
+    pub(crate) async fn next_input(&self, alive: impl Fn() -> bool) -> Option<Input> {
+        let events = self.events.as_ref()?;
+        let state = &self.state;
+        loop {
+            let event = events.next_while(&alive).await?;
+            let bounds = self.bounds();
+            let inside = |x: f64, y: f64| {
+                let (x, y) = (x as f32, y as f32);
+                x >= bounds.x
+                    && x < bounds.x + bounds.width
+                    && y >= bounds.y
+                    && y < bounds.y + bounds.height
+            };
+            let enabled = self.is_enabled();
+            let input = match event {
+                WindowEvent::PointerEnter(e) | WindowEvent::PointerMove(e) if enabled => {
+                    self.set_flag(&state.hovered, inside(e.x, e.y));
+                    state.pressed.get().then_some(Input::Drag {
+                        x: e.x as f32 - bounds.x,
+                        y: e.y as f32 - bounds.y,
+                    })
+                }
+                WindowEvent::PointerLeave(_) => {
+                    self.set_flag(&state.hovered, false);
+                    None
+                }
+                WindowEvent::PointerDown(e)
+                    if enabled && e.button == Some(PointerButton::Primary) && inside(e.x, e.y) =>
+                {
+                    self.set_flag(&state.pressed, true);
+                    self.layer.focus();
+                    Some(Input::Press {
+                        x: e.x as f32 - bounds.x,
+                        y: e.y as f32 - bounds.y,
+                        click_count: e.click_count,
+                        modifiers: e.modifiers,
+                    })
+                }
+                WindowEvent::PointerUp(e)
+                    if state.pressed.get() && e.button == Some(PointerButton::Primary) =>
+                {
+                    self.set_flag(&state.pressed, false);
+                    Some(Input::Release {
+                        inside: enabled && inside(e.x, e.y),
+                    })
+                }
+                WindowEvent::Focused(false) => {
+                    self.set_flag(&state.pressed, false);
+                    None
+                }
+                WindowEvent::KeyDown(key) if enabled && self.layer.is_focused() => {
+                    Some(Input::Key(key))
+                }
+                WindowEvent::TextInput(text) if enabled && self.layer.is_focused() => {
+                    Some(Input::Text(text))
+                }
+                WindowEvent::Scroll(e) if enabled && inside(e.x, e.y) => {
+                    Some(Input::Scroll(e.delta))
+                }
+                _ => None,
+            };
+            if input.is_some() {
+                return input;
+            }
+        }
+    }
+
+    fn set_flag(&self, flag: &Cell<bool>, value: bool) {
+        if flag.replace(value) != value {
+            self.layer.request_redraw();
+        }
+    }
+}
+
+pub(crate) fn paint(color: u32) -> Paint<'static> {
+    let [b, g, r, _] = color.to_le_bytes();
+    let mut paint = Paint::default();
+    paint.set_color(Color::from_rgba8(r, g, b, 0xFF));
+    paint
+}
+
+pub(crate) fn fill(painter: &mut Painter, path: Option<Path>, color: u32) {
+    if let Some(path) = path {
+        painter.fill_path(&path, &paint(color), FillRule::Winding);
+    }
+}
+
+pub(crate) fn stroke(painter: &mut Painter, path: Option<Path>, color: u32, width: f32) {
+    if let Some(path) = path {
+        let stroke = Stroke {
+            width,
+            ..Default::default()
+        };
+        painter.stroke_path(&path, &paint(color), &stroke);
+    }
+}
+
+pub(crate) fn rounded_rect(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Option<Path> {
+    let r = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+    // Where the control points of a quarter circle of cubic curves sit, from the corner
+    let k = r * 0.447_715;
+    let (right, bottom) = (x + width, y + height);
+    let mut builder = PathBuilder::new();
+    builder.move_to(x + r, y);
+    builder.line_to(right - r, y);
+    builder.cubic_to(right - k, y, right, y + k, right, y + r);
+    builder.line_to(right, bottom - r);
+    builder.cubic_to(right, bottom - k, right - k, bottom, right - r, bottom);
+    builder.line_to(x + r, bottom);
+    builder.cubic_to(x + k, bottom, x, bottom - k, x, bottom - r);
+    builder.line_to(x, y + r);
+    builder.cubic_to(x, y + k, x + k, y, x + r, y);
+    builder.close();
+    builder.finish()
+}
+
+fn text_style(theme: &Theme, enabled: bool, align: TextAlign) -> TextStyle {
+    let palette = &theme.palette;
+    TextStyle {
+        color: if enabled {
+            palette.text
+        } else {
+            palette.text_disabled
+        },
+        align,
+        wrap: false,
+        ..theme.text_style()
+    }
+}
+
+// Draw `text` from `x`, centered vertically
+fn draw_text(painter: &mut Painter, text: &str, style: &TextStyle, x: f32) {
+    let (width, height) = (painter.width() - x, painter.height());
+    let (_, text_height) = style.measure(text, Some(width));
+    if let Some(rect) = Rect::from_xywh(x, (height - text_height) / 2.0, width, text_height) {
+        painter.draw_text(text, style, rect);
+    }
+}
+
+// The size of a widget made of a `width` wide control and `text` next to it
+fn with_text(theme: &Theme, text: &str, width: f32, height: f32) -> (f32, f32) {
+    if text.is_empty() {
+        return (width, height);
+    }
+    let style = text_style(theme, true, TextAlign::Start);
+    let (text_width, text_height) = style.measure(text, None);
+    (
+        width + theme.spacing.medium + text_width,
+        height.max(text_height),
+    )
+}
+
+// A push button with a text. `on_click` fires when it is clicked, or on Enter or Space while it has the focus.
+// Placed like a `canvas`. Like every widget, it is drawn on macOS and iOS but can't be clicked or focused
+// there, as those windows deliver no input yet
+#[component]
+pub async fn button(
+    context: Option<&WindowContext>,
+    text: &str,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    #[default = true] enabled: bool,
+    layout: Option<&LayoutNode>,
+    #[event] on_click: (),
+) {
+    #[field]
+    let widget: Option<Widget<()>> = None;
+    #[field]
+    // Bumped on every render, so only the latest render handles input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let widget = widget.get_or_insert_with(|| Widget::new(&context, (), true));
+    widget.update(&context, (*x, *y, *width, *height), *layout, *enabled, ());
+    let text = text.to_string();
+    let (measured, theme) = (text.clone(), context.theme());
+    widget.measure((text.clone(), theme.clone()), move |_| {
+        let style = text_style(&theme, true, TextAlign::Center);
+        let (width, height) = style.measure(&measured, None);
+        let spacing = &theme.spacing;
+        (width + 2.0 * spacing.large, height + 2.0 * spacing.medium)
+    });
+    widget.set_draw(move |painter, look, (), theme| {
+        let (palette, radius) = (&theme.palette, theme.radii.medium);
+        let (width, height) = (painter.width(), painter.height());
+        let background = match look {
+            Look { enabled: false, .. } => palette.disabled,
+            Look { pressed: true, .. } => palette.accent_pressed,
+            Look { hovered: true, .. } => palette.accent_hovered,
+            _ => palette.accent,
+        };
+        fill(
+            painter,
+            rounded_rect(0.0, 0.0, width, height, radius),
+            background,
+        );
+        if look.focused {
+            let ring = rounded_rect(1.0, 1.0, width - 2.0, height - 2.0, radius - 1.0);
+            stroke(painter, ring, palette.focus_ring, 2.0);
+        }
+        let mut style = text_style(theme, look.enabled, TextAlign::Center);
+        if look.enabled {
+            style.color = palette.on_accent;
+        }
+        draw_text(painter, &text, &style, 0.0);
+    });
+
+    while let Some(input) = widget.next_input(|| *render_id == current_render).await {
+        if input.activates() {
+            let _ = on_click.emit(());
+        }
+    }
+}
+
+// A box to check, with a text next to it. Shows `checked` until clicked, or until Space is pressed while it
+// has the focus, then `on_change` fires with the new state. Placed like a `canvas`
+#[component]
+pub async fn checkbox(
+    context: Option<&WindowContext>,
+    text: &str,
+    checked: bool,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    #[default = true] enabled: bool,
+    layout: Option<&LayoutNode>,
+    #[event] on_change: bool,
+) {
+    #[field]
+    let widget: Option<Widget<bool>> = None;
+    #[field]
+    // Bumped on every render, so only the latest render handles input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let widget = widget.get_or_insert_with(|| Widget::new(&context, false, true));
+    widget.update(
+        &context,
+        (*x, *y, *width, *height),
+        *layout,
+        *enabled,
+        *checked,
+    );
+    let text = text.to_string();
+    let (measured, theme) = (text.clone(), context.theme());
+    widget.measure((text.clone(), theme.clone()), move |_| {
+        with_text(&theme, &measured, 16.0, 16.0)
+    });
+    widget.set_draw(move |painter, look, checked, theme| {
+        let (palette, radius) = (&theme.palette, theme.radii.small);
+        let top = ((painter.height() - 16.0) / 2.0).round();
+        let (fill_color, border) = match (look.enabled, checked) {
+            (false, _) => (palette.disabled, palette.text_disabled),
+            (true, true) if look.pressed => (palette.accent_pressed, palette.accent_pressed),
+            (true, true) => (palette.accent, palette.accent),
+            (true, false) if look.hovered || look.pressed => (palette.surface, palette.accent),
+            (true, false) => (palette.surface, palette.border),
+        };
+        fill(
+            painter,
+            rounded_rect(0.0, top, 16.0, 16.0, radius),
+            fill_color,
+        );
+        let outline = rounded_rect(0.5, top + 0.5, 15.0, 15.0, radius - 0.5);
+        stroke(painter, outline, border, 1.0);
+        if checked {
+            let mut mark = PathBuilder::new();
+            mark.move_to(4.0, top + 8.5);
+            mark.line_to(7.0, top + 11.5);
+            mark.line_to(12.0, top + 5.0);
+            let color = if look.enabled {
+                palette.on_accent
+            } else {
+                palette.text_disabled
+            };
+            stroke(painter, mark.finish(), color, 2.0);
+        }
+        if look.focused {
+            let ring = rounded_rect(-2.0, top - 2.0, 20.0, 20.0, radius + 2.0);
+            stroke(painter, ring, palette.focus_ring, 2.0);
+        }
+        let style = text_style(theme, look.enabled, TextAlign::Start);
+        draw_text(painter, &text, &style, 16.0 + theme.spacing.medium);
+    });
+
+    while let Some(input) = widget.next_input(|| *render_id == current_render).await {
+        if input.activates() {
+            let checked = !widget.value();
+            widget.set_value(checked);
+            let _ = on_change.emit(checked);
+        }
+    }
+}
+
+// A switch between on and off, with a text next to it. Behaves like a `checkbox`
+#[component]
+pub async fn toggle(
+    context: Option<&WindowContext>,
+    text: &str,
+    checked: bool,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    #[default = true] enabled: bool,
+    layout: Option<&LayoutNode>,
+    #[event] on_change: bool,
+) {
+    #[field]
+    let widget: Option<Widget<bool>> = None;
+    #[field]
+    // Bumped on every render, so only the latest render handles input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let widget = widget.get_or_insert_with(|| Widget::new(&context, false, true));
+    widget.update(
+        &context,
+        (*x, *y, *width, *height),
+        *layout,
+        *enabled,
+        *checked,
+    );
+    let text = text.to_string();
+    let (measured, theme) = (text.clone(), context.theme());
+    widget.measure((text.clone(), theme.clone()), move |_| {
+        with_text(&theme, &measured, 36.0, 20.0)
+    });
+    widget.set_draw(move |painter, look, checked, theme| {
+        let palette = &theme.palette;
+        let top = ((painter.height() - 20.0) / 2.0).round();
+        let track = match (look.enabled, checked) {
+            (false, _) => palette.disabled,
+            (true, true) if look.pressed => palette.accent_pressed,
+            (true, true) if look.hovered => palette.accent_hovered,
+            (true, true) => palette.accent,
+            (true, false) if look.hovered || look.pressed => palette.border,
+            (true, false) => palette.track,
+        };
+        fill(painter, rounded_rect(0.0, top, 36.0, 20.0, 10.0), track);
+        let knob = if checked { 26.0 } else { 10.0 };
+        let knob_color = if look.enabled {
+            palette.surface
+        } else {
+            palette.text_disabled
+        };
+        fill(
+            painter,
+            PathBuilder::from_circle(knob, top + 10.0, 8.0),
+            knob_color,
+        );
+        if look.focused {
+            let ring = rounded_rect(-2.0, top - 2.0, 40.0, 24.0, 12.0);
+            stroke(painter, ring, palette.focus_ring, 2.0);
+        }
+        let style = text_style(theme, look.enabled, TextAlign::Start);
+        draw_text(painter, &text, &style, 36.0 + theme.spacing.medium);
+    });
+
+    while let Some(input) = widget.next_input(|| *render_id == current_render).await {
+        if input.activates() {
+            let checked = !widget.value();
+            widget.set_value(checked);
+            let _ = on_change.emit(checked);
+        }
+    }
+}
+
+// A value between `min` and `max` picked by dragging a knob along a track, in steps of `step` unless it is 0.
+// The arrow keys move the knob while it has the focus, Page Up and Page Down ten times as far, Home and End to
+// either end. `on_change` fires with every new value. Placed like a `canvas`
+#[component]
+pub async fn slider(
+    context: Option<&WindowContext>,
+    value: f32,
+    min: f32,
+    #[default = 1.0] max: f32,
+    step: f32,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    #[default = true] enabled: bool,
+    layout: Option<&LayoutNode>,
+    #[event] on_change: f32,
+) {
+    #[field]
+    let widget: Option<Widget<f32>> = None;
+    #[field]
+    // Bumped on every render, so only the latest render handles input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let (min, max, step) = (*min, max.max(*min), step.max(0.0));
+    let widget = widget.get_or_insert_with(|| Widget::new(&context, min, true));
+    widget.update(
+        &context,
+        (*x, *y, *width, *height),
+        *layout,
+        *enabled,
+        *value,
+    );
+    widget.measure((), |_| (160.0, 20.0));
+    let fraction = move |value: f32| match max - min {
+        0.0 => 0.0,
+        range => ((value - min) / range).clamp(0.0, 1.0),
+    };
+    widget.set_draw(move |painter, look, value, theme| {
+        let palette = &theme.palette;
+        let (width, height) = (painter.width(), painter.height());
+        let middle = (height / 2.0).round();
+        let knob = 8.0 + (width - 16.0).max(0.0) * fraction(value);
+        let (filled, knob_border) = match look {
+            Look { enabled: false, .. } => (palette.text_disabled, palette.text_disabled),
+            Look { pressed: true, .. } => (palette.accent_pressed, palette.accent_pressed),
+            Look { hovered: true, .. } => (palette.accent_hovered, palette.accent_hovered),
+            _ => (palette.accent, palette.accent),
+        };
+        let track = rounded_rect(8.0, middle - 2.0, (width - 16.0).max(0.0), 4.0, 2.0);
+        let track_color = if look.enabled {
+            palette.track
+        } else {
+            palette.disabled
+        };
+        fill(painter, track, track_color);
+        fill(
+            painter,
+            rounded_rect(8.0, middle - 2.0, knob - 8.0, 4.0, 2.0),
+            filled,
+        );
+        if look.focused {
+            fill(
+                painter,
+                PathBuilder::from_circle(knob, middle, 11.0),
+                palette.focus_ring,
+            );
+        }
+        let knob_circle = PathBuilder::from_circle(knob, middle, 7.0);
+        fill(painter, knob_circle, palette.surface);
+        stroke(
+            painter,
+            PathBuilder::from_circle(knob, middle, 7.0),
+            knob_border,
+            2.0,
+        );
+    });
+
+    // Snapped to the steps, within `min` and `max`
+    let snap = move |value: f32| {
+        let value = match step {
+            0.0 => value,
+            step => min + ((value - min) / step).round() * step,
+        };
+        value.clamp(min, max)
+    };
+    let key_step = if step > 0.0 {
+        step
+    } else {
+        (max - min) / 100.0
+    };
+    while let Some(input) = widget.next_input(|| *render_id == current_render).await {
+        let current = widget.value();
+        let value = match input {
+            Input::Press { x, .. } | Input::Drag { x, .. } => {
+                let track = (widget.bounds().width - 16.0).max(1.0);
+                snap(min + (x - 8.0) / track * (max - min))
+            }
+            Input::Key(key) => match key.logical_key {
+                Key::Named(NamedKey::ArrowLeft | NamedKey::ArrowDown) => snap(current - key_step),
+                Key::Named(NamedKey::ArrowRight | NamedKey::ArrowUp) => snap(current + key_step),
+                Key::Named(NamedKey::PageDown) => snap(current - key_step * 10.0),
+                Key::Named(NamedKey::PageUp) => snap(current + key_step * 10.0),
+                Key::Named(NamedKey::Home) => min,
+                Key::Named(NamedKey::End) => max,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        if value != current {
+            widget.set_value(value);
+            let _ = on_change.emit(value);
+        }
+    }
+}
+
+// How far a task has come, `value` going from 0 to 1. Placed like a `canvas`
+#[component]
+pub async fn progress_bar(
+    context: Option<&WindowContext>,
+    value: f32,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    layout: Option<&LayoutNode>,
+) {
+    #[field]
+    let widget: Option<Widget<f32>> = None;
+
+    let Some(context) = context.cloned() else {
+        return;
+    };
+    let widget = widget.get_or_insert_with(|| Widget::new(&context, 0.0, false));
+    widget.update(
+        &context,
+        (*x, *y, *width, *height),
+        *layout,
+        true,
+        value.clamp(0.0, 1.0),
+    );
+    widget.measure((), |_| (160.0, 8.0));
+    widget.set_draw(|painter, look, value, theme| {
+        let palette = &theme.palette;
+        let (width, height) = (painter.width(), painter.height());
+        fill(
+            painter,
+            rounded_rect(0.0, 0.0, width, height, height / 2.0),
+            palette.track,
+        );
+        let color = if look.enabled {
+            palette.accent
+        } else {
+            palette.text_disabled
+        };
+        let done = rounded_rect(0.0, 0.0, width * value, height, height / 2.0);
+        fill(painter, done, color);
+    });
+}
@@ -1,17 +1,35 @@
-#[cfg(target_os = "android")]
+#[cfg(all(target_os = "android", not(headless)))]
 mod droid;
-#[cfg(target_os = "ios")]
+#[cfg(headless)]
+mod headless;
+#[cfg(any(windows, target_os = "android", headless))]
+mod input;
+#[cfg(all(target_os = "ios", not(headless)))]
 mod ios;
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(headless)))]
 mod mac;
-#[cfg(windows)]
+#[cfg(all(any(target_os = "macos", target_os = "ios"), not(headless)))]
+mod quartz;
+#[cfg(all(windows, not(headless)))]
 mod win;
 
-#[cfg(target_os = "android")]
+#[cfg(all(target_os = "android", not(headless)))]
 pub use droid::*;
-#[cfg(target_os = "ios")]
+#[cfg(headless)]
+pub use headless::*;
+#[cfg(all(target_os = "ios", not(headless)))]
 pub use ios::*;
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", not(headless)))]
 pub use mac::*;
-#[cfg(windows)]
+#[cfg(all(windows, not(headless)))]
 pub use win::*;
+
+#[cfg(any(headless, not(windows)))]
+use crate::drag_drop::{DragData, DropEffect};
+
+/// Start dragging `data` out of the application. Dragging out isn't supported on this platform yet, so the
+/// drag is rejected right away.
+#[cfg(any(headless, not(windows)))]
+pub fn start_drag(_data: &DragData) -> DropEffect {
+    DropEffect::None
+}
@@ -2,6 +2,10 @@ package rust.compo;
 
 import android.app.Activity;
 import android.os.Bundle;
+import android.view.InputDevice;
+import android.view.MotionEvent;
+import android.view.Surface;
+import android.view.SurfaceHolder;
 
 /**
  * CompoActivity - A singleton Activity manager for Rust Compo framework
@@ -9,10 +13,12 @@ import android.os.Bundle;
  * This class provides a static method to access the current Activity instance
  * and ensures only one Activity instance exists at a time.
  */
-public class CompoActivity extends Activity {
+public class CompoActivity extends Activity implements SurfaceHolder.Callback2 {
     @Override
     protected void onCreate(Bundle savedInstanceState) {
         super.onCreate(savedInstanceState);
+        // Native renderers draw into the window's surface, like with NativeActivity
+        getWindow().takeSurface(this);
         on_created();
     }
     
@@ -22,6 +28,99 @@ public class CompoActivity extends Activity {
         on_destroyed();
     }
 
+    @Override
+    public void surfaceCreated(SurfaceHolder holder) {
+        on_surface_changed(holder.getSurface());
+    }
+
+    @Override
+    public void surfaceChanged(SurfaceHolder holder, int format, int width, int height) {
+        on_surface_changed(holder.getSurface());
+    }
+
+    @Override
+    public void surfaceRedrawNeeded(SurfaceHolder holder) {
+    }
+
+    @Override
+    public void surfaceDestroyed(SurfaceHolder holder) {
+        on_surface_changed(null);
+    }
+
+    @Override
+    public void onWindowFocusChanged(boolean hasFocus) {
+        super.onWindowFocusChanged(hasFocus);
+        on_focus_changed(hasFocus);
+    }
+
+    @Override
+    public boolean dispatchTouchEvent(MotionEvent event) {
+        dispatchPointerEvent(event);
+        return super.dispatchTouchEvent(event);
+    }
+
+    @Override
+    public boolean dispatchGenericMotionEvent(MotionEvent event) {
+        // Hovering mice and styluses
+        if (event.isFromSource(InputDevice.SOURCE_CLASS_POINTER)) {
+            dispatchPointerEvent(event);
+        }
+        return super.dispatchGenericMotionEvent(event);
+    }
+
+    private void dispatchPointerEvent(MotionEvent event) {
+        float density = getResources().getDisplayMetrics().density;
+        int action = event.getActionMasked();
+        if (action == MotionEvent.ACTION_SCROLL) {
+            on_scroll(
+                event.getX() / density,
+                event.getY() / density,
+                event.getAxisValue(MotionEvent.AXIS_HSCROLL),
+                event.getAxisValue(MotionEvent.AXIS_VSCROLL),
+                event.getMetaState()
+            );
+            return;
+        }
+        int start = 0;
+        int end = event.getPointerCount();
+        if (action == MotionEvent.ACTION_POINTER_DOWN || action == MotionEvent.ACTION_POINTER_UP) {
+            // Only one of the pointers went down or up
+            start = event.getActionIndex();
+            end = start + 1;
+        }
+        for (int i = start; i < end; i++) {
+            on_pointer(
+                action,
+                event.getPointerId(i),
+                event.getToolType(i),
+                event.getX(i) / density,
+                event.getY(i) / density,
+                event.getPressure(i),
+                event.getAxisValue(MotionEvent.AXIS_TILT, i),
+                event.getOrientation(i),
+                event.getMetaState(),
+                event.getActionButton(),
+                event.getButtonState()
+            );
+        }
+    }
+
     private native void on_created();
     private native void on_destroyed();
+    private native void on_focus_changed(boolean focused);
+    private native void on_surface_changed(Surface surface);
+    private native void on_pointer(
+        int action,
+        int pointerId,
+        int toolType,
+        float x,
+        float y,
+        float pressure,
+        float tilt,
+        float orientation,
+        int metaState,
+        int actionButton,
+        int buttonState
+    );
+    private native void on_scroll(float x, float y, float hscroll, float vscroll, int metaState);
 }
\ No newline at end of file
@@ -1,22 +1,92 @@
 #![allow(unused)]
 
+#[cfg(feature = "raw-window-handle")]
+use raw_window_handle::{AndroidDisplayHandle, AndroidNdkWindowHandle};
 use {
+    super::input::{
+        ClickCounter, InputQueue, ScrollPhases, Wake, earliest, reaches_disabled, yield_now,
+    },
+    crate::{
+        context::WindowContext,
+        events::{WindowEvent, WindowEvents},
+        frame::{FrameClock, FrameTime},
+        gesture::{GestureConfig, GestureEvent, GestureRecognizer},
+        handle::NativeHandle,
+        inject::InputInjector,
+        keyboard::Modifiers,
+        pointer::{PointerButton, PointerEvent, PointerType},
+        record::{Recorder, WindowProps},
+        scroll::{ScrollDelta, ScrollEvent, ScrollPhase},
+        surface::SoftwareSurface,
+        theme::{ColorScheme, Theme},
+    },
     compo::prelude::*,
     compo_platform_loop::prelude::vm_exec,
     jni::{
         JNIEnv,
         errors::Result as JniResult,
-        objects::{GlobalRef, JObject},
+        objects::{GlobalRef, JObject, JString},
+        sys::{self, jboolean, jfloat, jint, jobject},
+    },
+    std::{
+        cell::Cell,
+        ffi::c_void,
+        ptr::{NonNull, null_mut},
+        slice,
+        time::{Duration, Instant},
     },
-    std::cell::Cell,
     tracing::{error, info},
 };
 
 thread_local! {
     static ACTIVITY: Cell<Option<GlobalRef>> = Cell::new(None);
     static ACTIVITY_REQUEST_RENDERING: EventListener<'static, ()> = EventListener::default();
+    static INPUT: Rc<InputQueue<WindowEvent>> = Default::default();
+    static CLICKS: RefCell<ClickCounter> = Default::default();
+    static GESTURES: RefCell<GestureRecognizer> = Default::default();
+    static SCROLL: RefCell<ScrollPhases> = Default::default();
+    // The `ANativeWindow` of the activity's surface, while it exists
+    static SURFACE: Cell<Option<NonNull<c_void>>> = const { Cell::new(None) };
+}
+
+// `ANativeWindow_Buffer`
+#[repr(C)]
+struct NativeWindowBuffer {
+    width: i32,
+    height: i32,
+    stride: i32,
+    format: i32,
+    bits: *mut c_void,
+    reserved: [u32; 6],
 }
 
+// `WINDOW_FORMAT_RGBX_8888`, whose bytes are in R, G, B order
+const WINDOW_FORMAT_RGBX_8888: i32 = 2;
+
+#[link(name = "android")]
+unsafe extern "C" {
+    fn ANativeWindow_fromSurface(env: *mut sys::JNIEnv, surface: jobject) -> *mut c_void;
+    fn ANativeWindow_release(window: *mut c_void);
+    fn ANativeWindow_getWidth(window: *mut c_void) -> i32;
+    fn ANativeWindow_getHeight(window: *mut c_void) -> i32;
+    fn ANativeWindow_setBuffersGeometry(
+        window: *mut c_void,
+        width: i32,
+        height: i32,
+        format: i32,
+    ) -> i32;
+    fn ANativeWindow_lock(
+        window: *mut c_void,
+        buffer: *mut NativeWindowBuffer,
+        dirty: *mut c_void,
+    ) -> i32;
+    fn ANativeWindow_unlockAndPost(window: *mut c_void) -> i32;
+}
+
+// `ViewConfiguration` defaults for double taps, in milliseconds and density-independent pixels
+const DOUBLE_TAP_TIMEOUT: Duration = Duration::from_millis(300);
+const DOUBLE_TAP_SLOP: f64 = 100.0;
+
 #[allow(non_snake_case)]
 #[unsafe(no_mangle)]
 unsafe extern "system" fn Java_rust_compo_CompoActivity_on_1created(env: JNIEnv, this: JObject) {
@@ -31,16 +101,408 @@ unsafe extern "system" fn Java_rust_compo_CompoActivity_on_1destroyed(env: JNIEn
     ACTIVITY_REQUEST_RENDERING.with(|i| i.new_emitter().emit(()));
 }
 
+#[allow(non_snake_case)]
+#[unsafe(no_mangle)]
+unsafe extern "system" fn Java_rust_compo_CompoActivity_on_1surface_1changed(
+    env: JNIEnv,
+    this: JObject,
+    surface: JObject,
+) {
+    let window = if surface.is_null() {
+        None
+    } else {
+        NonNull::new(unsafe { ANativeWindow_fromSurface(env.get_raw(), surface.as_raw()) })
+    };
+    if let Some(previous) = SURFACE.replace(window) {
+        unsafe { ANativeWindow_release(previous.as_ptr()) };
+    }
+    ACTIVITY_REQUEST_RENDERING.with(|i| i.new_emitter().emit(()));
+}
+
+// Touches also feed the gesture recognizer, whose gestures follow the touch that completed them
+fn push(input: &InputQueue<WindowEvent>, event: WindowEvent) {
+    let now = Instant::now();
+    let gestures = GESTURES.with_borrow_mut(|gestures| match &event {
+        WindowEvent::PointerDown(event) => gestures.pointer_down(event, now),
+        WindowEvent::PointerMove(event) => gestures.pointer_move(event, now),
+        WindowEvent::PointerUp(event) => gestures.pointer_up(event, now),
+        _ => Vec::new(),
+    });
+    input.push(event);
+    gestures
+        .into_iter()
+        .for_each(|g| input.push(WindowEvent::Gesture(g)));
+}
+
+fn modifiers(meta_state: jint) -> Modifiers {
+    let mut modifiers = Modifiers::NONE;
+    if meta_state & 0x1 != 0 {
+        // META_SHIFT_ON
+        modifiers |= Modifiers::SHIFT;
+    }
+    if meta_state & 0x1000 != 0 {
+        // META_CTRL_ON
+        modifiers |= Modifiers::CONTROL;
+    }
+    if meta_state & 0x2 != 0 {
+        // META_ALT_ON
+        modifiers |= Modifiers::ALT;
+    }
+    if meta_state & 0x10000 != 0 {
+        // META_META_ON
+        modifiers |= Modifiers::SUPER;
+    }
+    modifiers
+}
+
+fn pointer_button(tool_type: jint, button: jint) -> PointerButton {
+    match (tool_type, button) {
+        (4, _) => PointerButton::Eraser,             // TOOL_TYPE_ERASER
+        (_, 0x2 | 0x40) => PointerButton::Secondary, // BUTTON_SECONDARY | BUTTON_STYLUS_SECONDARY
+        (_, 0x4) => PointerButton::Middle,           // BUTTON_TERTIARY
+        (_, 0x8) => PointerButton::Back,             // BUTTON_BACK
+        (_, 0x10) => PointerButton::Forward,         // BUTTON_FORWARD
+        _ => PointerButton::Primary,
+    }
+}
+
+#[allow(non_snake_case, clippy::too_many_arguments)]
+#[unsafe(no_mangle)]
+unsafe extern "system" fn Java_rust_compo_CompoActivity_on_1pointer(
+    env: JNIEnv,
+    this: JObject,
+    action: jint,
+    pointer_id: jint,
+    tool_type: jint,
+    x: jfloat,
+    y: jfloat,
+    pressure: jfloat,
+    tilt: jfloat,
+    orientation: jfloat,
+    meta_state: jint,
+    action_button: jint,
+    button_state: jint,
+) {
+    let pointer_type = match tool_type {
+        2 | 4 => PointerType::Pen, // TOOL_TYPE_STYLUS | TOOL_TYPE_ERASER
+        3 => PointerType::Mouse,   // TOOL_TYPE_MOUSE
+        _ => PointerType::Touch,
+    };
+    // `tilt` leans away from the screen normal in the direction of `orientation`, both in radians
+    let (tilt_x, tilt_y) = match pointer_type {
+        PointerType::Pen => {
+            let tilt = tilt.tan();
+            (
+                (tilt * orientation.sin()).atan().to_degrees(),
+                (-tilt * orientation.cos()).atan().to_degrees(),
+            )
+        }
+        _ => (0.0, 0.0),
+    };
+    let mut event = PointerEvent {
+        pointer_id: pointer_id as u32,
+        pointer_type,
+        x: x as f64,
+        y: y as f64,
+        button: None,
+        click_count: 0,
+        modifiers: modifiers(meta_state),
+        pressure: pressure.clamp(0.0, 1.0),
+        tilt_x,
+        tilt_y,
+    };
+    let button = pointer_button(
+        tool_type,
+        if action_button != 0 {
+            action_button
+        } else {
+            button_state
+        },
+    );
+
+    INPUT.with(|input| match action {
+        0 | 5 => {
+            // ACTION_DOWN | ACTION_POINTER_DOWN
+            event.button = Some(button);
+            event.click_count = CLICKS.with_borrow_mut(|c| {
+                c.press(
+                    button,
+                    event.x,
+                    event.y,
+                    DOUBLE_TAP_TIMEOUT,
+                    DOUBLE_TAP_SLOP,
+                )
+            });
+            if pointer_type == PointerType::Touch {
+                // Fingers can't hover, so they enter on contact
+                push(input, WindowEvent::PointerEnter(event.clone()));
+            }
+            push(input, WindowEvent::PointerDown(event));
+        }
+        3 => {
+            // ACTION_CANCEL, the system took the touch away, so it must not complete as a gesture
+            event.button = Some(button);
+            event.pressure = 0.0;
+            GESTURES.with_borrow_mut(|gestures| gestures.cancel(&event));
+            input.push(WindowEvent::PointerUp(event.clone()));
+            if pointer_type == PointerType::Touch {
+                input.push(WindowEvent::PointerLeave(event));
+            }
+        }
+        1 | 6 => {
+            // ACTION_UP | ACTION_POINTER_UP
+            event.button = Some(button);
+            event.click_count = CLICKS.with_borrow(|c| c.current());
+            event.pressure = 0.0;
+            push(input, WindowEvent::PointerUp(event.clone()));
+            if pointer_type == PointerType::Touch {
+                push(input, WindowEvent::PointerLeave(event));
+            }
+        }
+        2 | 7 => push(input, WindowEvent::PointerMove(event)), // ACTION_MOVE | ACTION_HOVER_MOVE
+        9 => push(input, WindowEvent::PointerEnter(event)),    // ACTION_HOVER_ENTER
+        10 => push(input, WindowEvent::PointerLeave(event)),   // ACTION_HOVER_EXIT
+        _ => (),
+    });
+}
+
+#[allow(non_snake_case)]
+#[unsafe(no_mangle)]
+unsafe extern "system" fn Java_rust_compo_CompoActivity_on_1scroll(
+    env: JNIEnv,
+    this: JObject,
+    x: jfloat,
+    y: jfloat,
+    hscroll: jfloat,
+    vscroll: jfloat,
+    meta_state: jint,
+) {
+    // Android reports no gestures for wheels, so a pause in the events ends one
+    let event = SCROLL.with_borrow_mut(|s| {
+        s.scroll(
+            ScrollEvent {
+                x: x as f64,
+                y: y as f64,
+                // `AXIS_VSCROLL` is positive when the wheel rotates away from the user, which scrolls up
+                delta: ScrollDelta::Lines {
+                    x: hscroll as f64,
+                    y: -vscroll as f64,
+                },
+                phase: ScrollPhase::Changed,
+                modifiers: modifiers(meta_state),
+            },
+            Instant::now(),
+        )
+    });
+    INPUT.with(|input| push(input, WindowEvent::Scroll(event)));
+}
+
+#[allow(non_snake_case)]
+#[unsafe(no_mangle)]
+unsafe extern "system" fn Java_rust_compo_CompoActivity_on_1focus_1changed(
+    env: JNIEnv,
+    this: JObject,
+    focused: jboolean,
+) {
+    INPUT.with(|input| input.push(WindowEvent::Focused(focused != 0)));
+}
+
+// Hand out the activity's surface to graphics libraries
+#[cfg_attr(not(feature = "raw-window-handle"), allow(unused_variables))]
+fn set_native_handle(native_handle: &NativeHandle, surface: NonNull<c_void>) {
+    native_handle.set_available();
+    #[cfg(feature = "raw-window-handle")]
+    native_handle.set(
+        AndroidNdkWindowHandle::new(surface).into(),
+        AndroidDisplayHandle::new().into(),
+    );
+}
+
+// Draw a frame of the software surface into the activity's surface. The locked buffer may hold any older
+// frame, so the whole frame is copied even if only parts of it were drawn
+fn paint(window: NonNull<c_void>, surface: &SoftwareSurface) {
+    let window = window.as_ptr();
+    let mut buffer = NativeWindowBuffer {
+        width: 0,
+        height: 0,
+        stride: 0,
+        format: 0,
+        bits: null_mut(),
+        reserved: [0; 6],
+    };
+    let locked = unsafe {
+        ANativeWindow_setBuffersGeometry(window, 0, 0, WINDOW_FORMAT_RGBX_8888);
+        ANativeWindow_lock(window, &mut buffer, null_mut())
+    };
+    if locked != 0 {
+        error!("Failed to lock the native window: {}", locked);
+        return;
+    }
+    surface.present(
+        buffer.width as u32,
+        buffer.height as u32,
+        |pixels, width, height, _| {
+            for y in 0..height as usize {
+                let row = &pixels[y * width as usize..][..width as usize];
+                let line = unsafe {
+                    slice::from_raw_parts_mut(
+                        buffer.bits.cast::<u32>().add(y * buffer.stride as usize),
+                        width as usize,
+                    )
+                };
+                // 0x00RRGGBB to the bytes R, G, B, X
+                for (out, pixel) in line.iter_mut().zip(row) {
+                    *out =
+                        0xFF000000 | (pixel >> 16 & 0xFF) | (pixel & 0xFF00) | (pixel & 0xFF) << 16;
+                }
+            }
+        },
+    );
+    unsafe { ANativeWindow_unlockAndPost(window) };
+}
+
 async fn get_activity() -> GlobalRef {
-    match ACTIVITY.with(|i| unsafe { transmute::<_, &mut Option<GlobalRef>>(i.as_ptr()) }.clone()) {
-        Some(a) if !a.is_null() => a,
-        _ => {
+    match current_activity() {
+        Some(a) => a,
+        None => {
             ACTIVITY_REQUEST_RENDERING.with(|i| i.listen()).await;
             Box::pin(get_activity()).await
         }
     }
 }
 
+// The activity, if it was created already
+fn current_activity() -> Option<GlobalRef> {
+    ACTIVITY
+        .with(|i| unsafe { transmute::<_, &mut Option<GlobalRef>>(i.as_ptr()) }.clone())
+        .filter(|activity| !activity.is_null())
+}
+
+//noinspection SpellCheckingInspection
+// The `ClipboardManager` of the activity
+fn clipboard_manager<'a>(env: &mut JNIEnv<'a>, activity: &JObject) -> JniResult<JObject<'a>> {
+    let name = env.new_string("clipboard")?;
+    env.call_method(
+        activity,
+        "getSystemService",
+        "(Ljava/lang/String;)Ljava/lang/Object;",
+        &[(&name).into()],
+    )?
+    .l()
+}
+
+//noinspection SpellCheckingInspection
+/// The text on the clipboard, if it holds any.
+pub fn clipboard_text() -> Option<String> {
+    let activity = current_activity()?;
+    vm_exec(|mut env| {
+        let manager = clipboard_manager(&mut env, activity.as_obj())?;
+        let clip = env
+            .call_method(
+                &manager,
+                "getPrimaryClip",
+                "()Landroid/content/ClipData;",
+                &[],
+            )?
+            .l()?;
+        if clip.is_null() {
+            return Ok(None);
+        }
+        let item = env
+            .call_method(
+                &clip,
+                "getItemAt",
+                "(I)Landroid/content/ClipData$Item;",
+                &[0.into()],
+            )?
+            .l()?;
+        let text = env
+            .call_method(
+                &item,
+                "coerceToText",
+                "(Landroid/content/Context;)Ljava/lang/CharSequence;",
+                &[activity.as_obj().into()],
+            )?
+            .l()?;
+        let text = env
+            .call_method(&text, "toString", "()Ljava/lang/String;", &[])?
+            .l()?;
+        let text = env.get_string(&JString::from(text))?.into();
+        Ok(Some(text))
+    })
+    .unwrap_or_else(|e| {
+        error!("Can't read the clipboard: {}", e);
+        None
+    })
+}
+
+//noinspection SpellCheckingInspection
+/// Put `text` on the clipboard, in place of what it held.
+pub fn set_clipboard_text(text: &str) {
+    let Some(activity) = current_activity() else {
+        return;
+    };
+    let result = vm_exec(|mut env| {
+        let manager = clipboard_manager(&mut env, activity.as_obj())?;
+        let label = env.new_string("")?;
+        let text = env.new_string(text)?;
+        let clip = env
+            .call_static_method(
+                "android/content/ClipData",
+                "newPlainText",
+                "(Ljava/lang/CharSequence;Ljava/lang/CharSequence;)Landroid/content/ClipData;",
+                &[(&label).into(), (&text).into()],
+            )?
+            .l()?;
+        env.call_method(
+            &manager,
+            "setPrimaryClip",
+            "(Landroid/content/ClipData;)V",
+            &[(&clip).into()],
+        )?;
+        Ok(())
+    });
+    if let Err(e) = result {
+        error!("Can't write the clipboard: {}", e);
+    }
+}
+
+//noinspection SpellCheckingInspection
+/// Whether the system is in light or dark mode, as set with the dark theme in the display settings.
+pub fn system_color_scheme() -> ColorScheme {
+    let Some(activity) = current_activity() else {
+        return ColorScheme::Light;
+    };
+    let ui_mode = vm_exec(|mut env| {
+        let resources = env
+            .call_method(
+                activity.as_obj(),
+                "getResources",
+                "()Landroid/content/res/Resources;",
+                &[],
+            )?
+            .l()?;
+        let configuration = env
+            .call_method(
+                &resources,
+                "getConfiguration",
+                "()Landroid/content/res/Configuration;",
+                &[],
+            )?
+            .l()?;
+        env.get_field(&configuration, "uiMode", "I")?.i()
+    });
+    match ui_mode {
+        // UI_MODE_NIGHT_MASK, UI_MODE_NIGHT_YES
+        Ok(ui_mode) if ui_mode & 0x30 == 0x20 => ColorScheme::Dark,
+        Ok(_) => ColorScheme::Light,
+        Err(e) => {
+            error!("Can't read the UI mode: {}", e);
+            ColorScheme::Light
+        }
+    }
+}
+
 // Window component for Android
 #[component]
 pub async fn window(
@@ -49,6 +511,24 @@ pub async fn window(
     #[default = 640] height: i32,
     #[default = true] visible: bool,
     #[default = true] enabled: bool,
+    #[default = 1.0] opacity: f32,
+    #[event] on_pointer_enter: PointerEvent,
+    #[event] on_pointer_leave: PointerEvent,
+    #[event] on_pointer_move: PointerEvent,
+    #[event] on_pointer_down: PointerEvent,
+    #[event] on_pointer_up: PointerEvent,
+    #[event] on_scroll: ScrollEvent,
+    gesture_config: GestureConfig,
+    #[event] on_gesture: GestureEvent,
+    injector: Option<&InputInjector>,
+    recorder: Option<&Recorder>,
+    native_handle: Option<&NativeHandle>,
+    surface: Option<&SoftwareSurface>,
+    frame_clock: Option<&FrameClock>,
+    #[event] on_redraw: FrameTime,
+    theme: Option<&Theme>,
+    context: Option<&WindowContext>,
+    events: Option<&WindowEvents>,
 ) {
     let activity_obj = get_activity().await;
     let this2 = this.clone();
@@ -60,6 +540,37 @@ pub async fn window(
     // This is a field of the component's internal structure, not a variable in the current scope, so it can persist across multiple renders
     #[field]
     let window_initialized: bool = false;
+    #[field]
+    // Bumped on every render, so only the latest render forwards input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    // Keep the stream itself, the borrowed prop only lives until the next render
+    let events = events.cloned();
+    let recorder = recorder.cloned();
+    // Whatever the props leave out comes from the context
+    let native_handle = native_handle.or(context.map(WindowContext::native_handle));
+    let surface = surface.or(context.map(WindowContext::surface));
+    let frame_clock = frame_clock
+        .or(context.map(WindowContext::frame_clock))
+        .cloned();
+    let context = context.cloned();
+    if let Some(recorder) = &recorder {
+        // The activity fills the screen, it has no position of its own
+        recorder.record_props(WindowProps {
+            title: title.to_string(),
+            width: *width,
+            height: *height,
+            left: 0,
+            top: 0,
+            visible: *visible,
+            enabled: *enabled,
+            opacity: *opacity,
+        });
+    }
+    let input = INPUT.with(Rc::clone);
+    GESTURES.with_borrow_mut(|g| g.set_config(*gesture_config));
 
     if *visible {
         if !*window_initialized {
@@ -103,6 +614,11 @@ pub async fn window(
                 );
             }
         }
+
+        // Update window opacity (supports reactive updates)
+        if let Err(e) = update_window_opacity(&activity_obj, *opacity) {
+            error!("Failed to update window opacity: {:?}", e);
+        }
     } else if *window_initialized {
         // Hide window when visible is false
         if let Err(e) = hide_android_window(&activity_obj) {
@@ -110,6 +626,150 @@ pub async fn window(
         } else {
             info!("Android window hidden");
         }
+        input.clear();
+        GESTURES.with_borrow_mut(|g| g.reset());
+        SCROLL.with_borrow_mut(|s| s.reset());
+        if let Some(injector) = injector {
+            injector.detach();
+        }
+    }
+
+    // Graphics libraries draw into the activity's surface, which comes and goes with the activity
+    if let Some(native_handle) = native_handle {
+        match SURFACE.get() {
+            Some(surface) if *visible && *window_initialized => {
+                set_native_handle(native_handle, surface);
+                let window = surface.as_ptr();
+                let (width, height) = unsafe {
+                    (
+                        ANativeWindow_getWidth(window),
+                        ANativeWindow_getHeight(window),
+                    )
+                };
+                native_handle.set_size(width.max(0) as u32, height.max(0) as u32);
+            }
+            _ => native_handle.clear(),
+        }
+    }
+
+    if let Some(surface) = surface
+        && let Some(window) = SURFACE.get()
+        && *visible
+        && *window_initialized
+    {
+        paint(window, surface);
+    }
+
+    // Synthetic input joins the activity's input. It doesn't pass the window, which drops touches while
+    // disabled, so it is dropped here
+    if let Some(injector) = injector
+        && *visible
+        && *window_initialized
+    {
+        let enabled = *enabled;
+        injector.attach(move |event, recognize| {
+            if !enabled && !reaches_disabled(&event) {
+                return;
+            }
+            INPUT.with(|input| {
+                if recognize {
+                    push(input, event);
+                } else {
+                    input.push(event);
+                }
+            })
+        });
+    }
+
+    if let Some(events) = &events {
+        events.set_closed(!(*visible && *window_initialized));
+    }
+    if let Some(context) = &context {
+        context.set_closed(!(*visible && *window_initialized));
+        context.set_enabled(*enabled);
+        context.set_theme(*theme);
+        match density(&activity_obj) {
+            Ok(density) => context.set_scale_factor(density),
+            Err(e) => error!("Failed to get the display density: {:?}", e),
+        }
+    }
+    // Frames stop along with the activity's surface, e.g. while the app is in the background
+    if let Some(frame_clock) = &frame_clock {
+        frame_clock.set_visible(*visible && *window_initialized && SURFACE.get().is_some());
+        match refresh_interval(&activity_obj) {
+            Ok(interval) => frame_clock.set_interval(interval),
+            Err(e) => error!("Failed to get the refresh rate: {:?}", e),
+        }
+    }
+
+    // Forward input to the event props until the next render replaces this one
+    while *visible && *window_initialized {
+        let deadline = || {
+            let input = [
+                GESTURES.with_borrow(|g| g.deadline()),
+                SCROLL.with_borrow(|s| s.deadline()),
+            ];
+            earliest(input.into_iter().flatten().min(), frame_clock.as_ref())
+        };
+        let next = match input.next(|| *render_id == current_render, deadline).await {
+            Some(Wake::Input(next)) => next,
+            Some(Wake::Deadline) => {
+                let now = Instant::now();
+                // A long press fires while the finger rests, without any input to carry it
+                if let Some(gesture) = GESTURES.with_borrow_mut(|g| g.tick(now)) {
+                    input.push(WindowEvent::Gesture(gesture));
+                }
+                // So does the end of a scroll, once the wheel events stop
+                if let Some(scroll) = SCROLL.with_borrow_mut(|s| s.tick(now)) {
+                    input.push(WindowEvent::Scroll(scroll));
+                }
+                if let Some((frame, true)) = frame_clock.as_ref().and_then(|c| c.tick(now)) {
+                    if let Some(surface) = surface
+                        && let Some(window) = SURFACE.get()
+                    {
+                        paint(window, surface);
+                    }
+                    let _ = on_redraw.emit(frame);
+                }
+                continue;
+            }
+            None => break,
+        };
+        if let Some(events) = &events {
+            events.push(next.clone());
+        }
+        if let Some(context) = &context {
+            context.dispatch(&next);
+        }
+        if let Some(recorder) = &recorder {
+            recorder.record_event(&next);
+        }
+        match next {
+            WindowEvent::PointerEnter(event) => {
+                let _ = on_pointer_enter.emit(event);
+            }
+            WindowEvent::PointerLeave(event) => {
+                let _ = on_pointer_leave.emit(event);
+            }
+            WindowEvent::PointerMove(event) => {
+                let _ = on_pointer_move.emit(event);
+            }
+            WindowEvent::PointerDown(event) => {
+                let _ = on_pointer_down.emit(event);
+            }
+            WindowEvent::PointerUp(event) => {
+                let _ = on_pointer_up.emit(event);
+            }
+            WindowEvent::Scroll(event) => {
+                let _ = on_scroll.emit(event);
+            }
+            WindowEvent::Gesture(event) => {
+                let _ = on_gesture.emit(event);
+            }
+            // Focus changes are only delivered through `events`
+            _ => {}
+        }
+        yield_now().await;
     }
 }
 
@@ -259,6 +919,93 @@ fn update_window_enabled(activity: &JObject, enabled: bool) -> JniResult<()> {
     })
 }
 
+//noinspection SpellCheckingInspection
+// Update window opacity
+fn update_window_opacity(activity: &JObject, opacity: f32) -> JniResult<()> {
+    vm_exec(|mut env| {
+        // Get window from activity
+        let window = env.call_method(activity, "getWindow", "()Landroid/view/Window;", &[])?;
+        let window_obj = window.l()?;
+
+        // Get window attributes
+        let attributes = env.call_method(
+            &window_obj,
+            "getAttributes",
+            "()Landroid/view/WindowManager$LayoutParams;",
+            &[],
+        )?;
+        let attributes_obj = attributes.l()?;
+
+        // Update alpha, which applies to the whole window
+        env.set_field(
+            &attributes_obj,
+            "alpha",
+            "F",
+            opacity.clamp(0.0, 1.0).into(),
+        )?;
+
+        // Apply the attributes
+        env.call_method(
+            &window_obj,
+            "setAttributes",
+            "(Landroid/view/WindowManager$LayoutParams;)V",
+            &[(&attributes_obj).into()],
+        )?;
+
+        Ok(())
+    })
+}
+
+// The time between two refreshes of the activity's display
+fn refresh_interval(activity: &JObject) -> JniResult<Duration> {
+    vm_exec(|mut env| {
+        let manager = env
+            .call_method(
+                activity,
+                "getWindowManager",
+                "()Landroid/view/WindowManager;",
+                &[],
+            )?
+            .l()?;
+        let display = env
+            .call_method(
+                &manager,
+                "getDefaultDisplay",
+                "()Landroid/view/Display;",
+                &[],
+            )?
+            .l()?;
+        let rate = env
+            .call_method(&display, "getRefreshRate", "()F", &[])?
+            .f()?;
+        Ok(Duration::from_secs_f64(1.0 / rate.max(1.0) as f64))
+    })
+}
+
+// Physical pixels per density-independent pixel of the activity's display
+fn density(activity: &JObject) -> JniResult<f64> {
+    vm_exec(|mut env| {
+        let resources = env
+            .call_method(
+                activity,
+                "getResources",
+                "()Landroid/content/res/Resources;",
+                &[],
+            )?
+            .l()?;
+        let metrics = env
+            .call_method(
+                &resources,
+                "getDisplayMetrics",
+                "()Landroid/util/DisplayMetrics;",
+                &[],
+            )?
+            .l()?;
+        let density = env.get_field(&metrics, "density", "F")?.f()?;
+        Ok(density as f64)
+    })
+}
+
 //noinspection SpellCheckingInspection
 // Hide Android window
 fn hide_android_window(activity: &JObject) -> JniResult<()> {
@@ -0,0 +1,351 @@
+use {
+    super::input::{
+        Dispatcher, EventProps, InputQueue, Wake, earliest, reaches_disabled, yield_now,
+    },
+    crate::{
+        context::WindowContext,
+        drag_drop::DragEvent,
+        events::{WindowEvent, WindowEvents},
+        frame::{FrameClock, FrameTime},
+        geometry::Rect,
+        gesture::{GestureConfig, GestureEvent, GestureRecognizer},
+        handle::NativeHandle,
+        inject::InputInjector,
+        keybinding::Keymap,
+        keyboard::KeyEvent,
+        pointer::PointerEvent,
+        record::{Recorder, WindowProps},
+        scroll::ScrollEvent,
+        surface::SoftwareSurface,
+        text_input::TextInputEvent,
+        theme::{ColorScheme, Theme},
+    },
+    compo::prelude::*,
+    std::{cell::Cell, time::Instant},
+    tracing::info,
+};
+
+thread_local! {
+    // Windows currently shown, the loop stops once the last one closes
+    static OPEN_WINDOWS: Cell<usize> = const { Cell::new(0) };
+    static QUIT: Cell<bool> = const { Cell::new(false) };
+    // There is no system clipboard to share, so the windows share one of their own
+    static CLIPBOARD: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// The text on the clipboard, if it holds any. The headless backend keeps a clipboard of its own, which
+/// starts out empty.
+pub fn clipboard_text() -> Option<String> {
+    CLIPBOARD.with_borrow(Clone::clone)
+}
+
+/// Put `text` on the clipboard, in place of what it held.
+pub fn set_clipboard_text(text: &str) {
+    CLIPBOARD.set(Some(text.to_owned()));
+}
+
+/// Whether the system is in light or dark mode. There is no system to follow, so this is always light mode;
+/// inject `WindowEvent::ColorSchemeChanged` to try out dark mode.
+pub fn system_color_scheme() -> ColorScheme {
+    ColorScheme::Light
+}
+
+/// Run `entry` as the root component on the headless backend.
+///
+/// Runs until the last window closes, e.g. through `InputInjector::request_close`, or until `Keymap::QUIT`
+/// is triggered.
+pub fn run<'a, C, F>(entry: F)
+where
+    C: Component<'a> + 'a,
+    F: AsyncFn(Weak<C>) + 'a,
+{
+    QUIT.set(false);
+    Loop::new()
+        .register_poll_handler(|r#loop| {
+            if QUIT.take() {
+                r#loop.quit();
+            }
+        })
+        .run(entry);
+}
+
+// Per-window state shared with the injectors attached to the window
+#[derive(Default)]
+struct WindowState {
+    input: InputQueue<WindowEvent>,
+    enabled: Cell<bool>,
+    gestures: RefCell<GestureRecognizer>,
+}
+
+impl WindowState {
+    fn push(&self, input: WindowEvent, recognize: bool) {
+        if !self.enabled.get() && !reaches_disabled(&input) {
+            return;
+        }
+        // Touches also feed the gesture recognizer, whose gestures follow the touch that completed them
+        let now = Instant::now();
+        let gestures = match &input {
+            _ if !recognize => Vec::new(),
+            WindowEvent::PointerDown(event) => self.gestures.borrow_mut().pointer_down(event, now),
+            WindowEvent::PointerMove(event) => self.gestures.borrow_mut().pointer_move(event, now),
+            WindowEvent::PointerUp(event) => self.gestures.borrow_mut().pointer_up(event, now),
+            _ => Vec::new(),
+        };
+        self.input.push(input);
+        gestures
+            .into_iter()
+            .for_each(|g| self.input.push(WindowEvent::Gesture(g)));
+    }
+}
+
+// Window component, without any window on screen. Input only comes from an `InputInjector`
+#[component]
+pub async fn window(
+    #[default = "Window"] title: &str,
+    #[default = 800] width: i32,
+    #[default = 600] height: i32,
+    left: i32,
+    top: i32,
+    #[default = true] visible: bool,
+    #[default = true] enabled: bool,
+    #[default = 1.0] opacity: f32,
+    #[default = true] ime_enabled: bool,
+    ime_cursor_area: Rect,
+    #[event] on_key_down: KeyEvent,
+    #[event] on_key_up: KeyEvent,
+    #[event] on_text_input: TextInputEvent,
+    #[event] on_pointer_enter: PointerEvent,
+    #[event] on_pointer_leave: PointerEvent,
+    #[event] on_pointer_move: PointerEvent,
+    #[event] on_pointer_down: PointerEvent,
+    #[event] on_pointer_up: PointerEvent,
+    #[event] on_scroll: ScrollEvent,
+    gesture_config: GestureConfig,
+    #[event] on_gesture: GestureEvent,
+    #[event] on_drag: DragEvent,
+    keymap: Option<&Keymap>,
+    #[event] on_action: String,
+    injector: Option<&InputInjector>,
+    recorder: Option<&Recorder>,
+    native_handle: Option<&NativeHandle>,
+    surface: Option<&SoftwareSurface>,
+    frame_clock: Option<&FrameClock>,
+    #[event] on_redraw: FrameTime,
+    theme: Option<&Theme>,
+    context: Option<&WindowContext>,
+    events: Option<&WindowEvents>,
+) {
+    #[field]
+    // While the window exists, it stops existing when hidden, closed or dropped
+    let open: Option<OpenWindow> = None;
+    #[field]
+    let state: Rc<WindowState> = Rc::new(WindowState::default());
+    #[field]
+    // Bumped on every render, so only the latest render forwards input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    // Keep the stream itself, the borrowed prop only lives until the next render
+    let events = events.cloned();
+    let keymap = keymap.cloned();
+    let recorder = recorder.cloned();
+    if let Some(recorder) = &recorder {
+        recorder.record_props(WindowProps {
+            title: title.to_string(),
+            width: *width,
+            height: *height,
+            left: *left,
+            top: *top,
+            visible: *visible,
+            enabled: *enabled,
+            opacity: *opacity,
+        });
+    }
+    let injector = injector.cloned();
+    // Whatever the props leave out comes from the context
+    let native_handle = native_handle
+        .or(context.map(WindowContext::native_handle))
+        .cloned();
+    let surface = surface.or(context.map(WindowContext::surface)).cloned();
+    let frame_clock = frame_clock
+        .or(context.map(WindowContext::frame_clock))
+        .cloned();
+    let context = context.cloned();
+
+    // There is no native window to draw into, only its size
+    if let Some(native_handle) = &native_handle {
+        native_handle.set_unsupported();
+        native_handle.set_size((*width).max(0) as u32, (*height).max(0) as u32);
+    }
+
+    if *visible {
+        if open.is_none() {
+            *open = Some(OpenWindow::new());
+        }
+        state.enabled.set(*enabled);
+        state.gestures.borrow_mut().set_config(*gesture_config);
+        // Frames stay in memory, where `SoftwareSurface::pixels` reads them back
+        if let Some(surface) = &surface {
+            draw(surface, *width, *height);
+        }
+        if let Some(injector) = &injector {
+            let target = Rc::downgrade(state);
+            injector.attach(move |event, recognize| {
+                if let Some(state) = target.upgrade() {
+                    state.push(event, recognize);
+                }
+            });
+        }
+        info!(
+            "Headless window updated: {} ({}x{} at {}, {}), enabled: {}, IME: {} at {:?}",
+            title, width, height, left, top, enabled, ime_enabled, ime_cursor_area
+        );
+    } else if open.is_some() {
+        close(open, state, injector.as_ref());
+    }
+
+    if let Some(events) = &events {
+        events.set_closed(open.is_none());
+    }
+    if let Some(context) = &context {
+        context.set_closed(open.is_none());
+        context.set_enabled(*enabled);
+        context.set_theme(*theme);
+    }
+    // Without a display to follow, frames keep the default rate of 60 Hz
+    if let Some(frame_clock) = &frame_clock {
+        frame_clock.set_visible(open.is_some());
+    }
+
+    let props = EventProps {
+        on_key_down,
+        on_key_up,
+        on_text_input,
+        on_pointer_enter,
+        on_pointer_leave,
+        on_pointer_move,
+        on_pointer_down,
+        on_pointer_up,
+        on_scroll,
+        on_gesture,
+        on_drag,
+        on_action,
+        keymap: keymap.as_ref(),
+        events: events.as_ref(),
+        context: context.as_ref(),
+        recorder: recorder.as_ref(),
+    };
+    let mut dispatcher = Dispatcher::default();
+
+    // Forward input to the event props until the next render replaces this one
+    while open.is_some() {
+        let deadline = || earliest(state.gestures.borrow().deadline(), frame_clock.as_ref());
+        let next = match state
+            .input
+            .next(|| *render_id == current_render, deadline)
+            .await
+        {
+            Some(Wake::Input(next)) => next,
+            Some(Wake::Deadline) => {
+                let now = Instant::now();
+                // A long press fires while the finger rests, without any input to carry it
+                if let Some(gesture) = state.gestures.borrow_mut().tick(now) {
+                    state.push(WindowEvent::Gesture(gesture), false);
+                }
+                if let Some((frame, true)) = frame_clock.as_ref().and_then(|c| c.tick(now)) {
+                    if let Some(surface) = &surface {
+                        let (width, height) = surface.size();
+                        draw(surface, width as i32, height as i32);
+                    }
+                    let _ = on_redraw.emit(frame);
+                }
+                continue;
+            }
+            None => break,
+        };
+        match dispatcher.dispatch(next, &props) {
+            Some(WindowEvent::Action(action)) => match action.as_str() {
+                Keymap::CLOSE_WINDOW => state.push(WindowEvent::CloseRequested, true),
+                Keymap::QUIT => QUIT.set(true),
+                _ => {}
+            },
+            Some(WindowEvent::CloseRequested) => {
+                // Like a native window, the window goes away right after the request
+                close(open, state, injector.as_ref());
+                if let Some(frame_clock) = &frame_clock {
+                    frame_clock.set_visible(false);
+                }
+                if let Some(events) = &events {
+                    events.set_closed(true);
+                }
+                if let Some(context) = &context {
+                    context.set_closed(true);
+                }
+            }
+            Some(WindowEvent::Resized { width, height }) => {
+                if let Some(native_handle) = &native_handle {
+                    native_handle.set_size(width.max(0) as u32, height.max(0) as u32);
+                }
+                if let Some(surface) = &surface {
+                    draw(surface, width, height);
+                }
+            }
+            _ => {}
+        }
+        yield_now().await;
+    }
+}
+
+fn draw(surface: &SoftwareSurface, width: i32, height: i32) {
+    surface.present(width.max(0) as u32, height.max(0) as u32, |_, _, _, _| {});
+}
+
+// Counts a window as open while it exists, the loop stops once the last one is gone, even if its component
+// is dropped without closing it
+struct OpenWindow;
+
+impl OpenWindow {
+    fn new() -> Self {
+        OPEN_WINDOWS.set(OPEN_WINDOWS.get() + 1);
+        Self
+    }
+}
+
+impl Drop for OpenWindow {
+    fn drop(&mut self) {
+        // The thread may be exiting, along with its thread locals
+        let _ = OPEN_WINDOWS.try_with(|open| {
+            open.set(open.get() - 1);
+            if open.get() == 0 {
+                let _ = QUIT.try_with(|quit| quit.set(true));
+            }
+        });
+    }
+}
+
+// The window stops existing
+fn close(open: &mut Option<OpenWindow>, state: &WindowState, injector: Option<&InputInjector>) {
+    *open = None;
+    state.input.clear();
+    state.gestures.borrow_mut().reset();
+    if let Some(injector) = injector {
+        injector.detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_stops_once_the_last_window_is_dropped() {
+        QUIT.set(false);
+        let (first, second) = (OpenWindow::new(), OpenWindow::new());
+        drop(first);
+        assert!(!QUIT.get());
+        drop(second);
+        assert!(QUIT.get());
+        assert_eq!(OPEN_WINDOWS.get(), 0);
+    }
+}
@@ -0,0 +1,463 @@
+#[cfg(any(windows, target_os = "android", headless))]
+use crate::events::WindowEvent;
+#[cfg(not(headless))]
+use crate::pointer::PointerButton;
+#[cfg(any(not(headless), test))]
+use {
+    crate::scroll::{ScrollDelta, ScrollPhase},
+    std::time::Duration,
+};
+#[cfg(any(windows, headless))]
+use {
+    crate::{
+        context::WindowContext,
+        drag_drop::DragEvent,
+        events::WindowEvents,
+        gesture::GestureEvent,
+        keybinding::{Dispatch, Keymap},
+        keyboard::KeyEvent,
+        pointer::PointerEvent,
+        record::Recorder,
+        text_input::TextInputEvent,
+    },
+    compo::prelude::EventEmitter,
+};
+use {
+    crate::{frame::FrameClock, scroll::ScrollEvent},
+    std::{cell::RefCell, collections::VecDeque, future::poll_fn, task::Poll, time::Instant},
+};
+
+// How long a scroll gesture waits for the next wheel message before it ends
+#[cfg(any(not(headless), test))]
+const SCROLL_IDLE: Duration = Duration::from_millis(150);
+
+// What an input loop woke up for
+pub(super) enum Wake<T> {
+    Input(T),
+    Deadline,
+}
+
+// Input received from the platform, waiting to be emitted through the component's events
+pub(super) struct InputQueue<T>(RefCell<VecDeque<T>>);
+
+impl<T> Default for InputQueue<T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<T> InputQueue<T> {
+    pub(super) fn push(&self, input: T) {
+        self.0.borrow_mut().push_back(input);
+    }
+
+    pub(super) fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+
+    // Wait until the platform has queued some input or `deadline` has passed, or until `active` turns false.
+    // `deadline` is asked again on every poll, timers may start while waiting
+    pub(super) async fn next(
+        &self,
+        active: impl Fn() -> bool,
+        deadline: impl Fn() -> Option<Instant>,
+    ) -> Option<Wake<T>> {
+        poll_fn(|_| {
+            if !active() {
+                return Poll::Ready(None);
+            }
+            if let Some(input) = self.0.borrow_mut().pop_front() {
+                return Poll::Ready(Some(Wake::Input(input)));
+            }
+            match deadline() {
+                Some(deadline) if Instant::now() >= deadline => Poll::Ready(Some(Wake::Deadline)),
+                _ => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+// The earlier of a timer's deadline and the frame clock's
+pub(super) fn earliest(
+    deadline: Option<Instant>,
+    frame_clock: Option<&FrameClock>,
+) -> Option<Instant> {
+    match (deadline, frame_clock.and_then(FrameClock::deadline)) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+// Give up the current poll once, so listeners can subscribe again before the next event is emitted
+pub(super) async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|_| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+// Whether a disabled window still gets `event`. It gets no input, but hears about closing, resizing and the
+// focus
+#[cfg(any(windows, target_os = "android", headless))]
+pub(super) fn reaches_disabled(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::CloseRequested | WindowEvent::Resized { .. } | WindowEvent::Focused(_)
+    )
+}
+
+// Run a key press through the window's keymap, then through the application-wide one
+#[cfg(any(windows, headless))]
+fn dispatch_key(keymap: Option<&Keymap>, event: &KeyEvent) -> Dispatch {
+    let global = Keymap::global();
+    match keymap.map(|k| k.dispatch(event)) {
+        Some(Dispatch::Unhandled) | None => global.dispatch(event),
+        Some(dispatch) => {
+            global.clear_pending();
+            dispatch
+        }
+    }
+}
+
+// The receivers of a window's events: its event props, its keymap and the `events`, `context` and `recorder`
+// props
+#[cfg(any(windows, headless))]
+pub(super) struct EventProps<'a, 'b> {
+    pub(super) on_key_down: &'b EventEmitter<'a, KeyEvent>,
+    pub(super) on_key_up: &'b EventEmitter<'a, KeyEvent>,
+    pub(super) on_text_input: &'b EventEmitter<'a, TextInputEvent>,
+    pub(super) on_pointer_enter: &'b EventEmitter<'a, PointerEvent>,
+    pub(super) on_pointer_leave: &'b EventEmitter<'a, PointerEvent>,
+    pub(super) on_pointer_move: &'b EventEmitter<'a, PointerEvent>,
+    pub(super) on_pointer_down: &'b EventEmitter<'a, PointerEvent>,
+    pub(super) on_pointer_up: &'b EventEmitter<'a, PointerEvent>,
+    pub(super) on_scroll: &'b EventEmitter<'a, ScrollEvent>,
+    pub(super) on_gesture: &'b EventEmitter<'a, GestureEvent>,
+    pub(super) on_drag: &'b EventEmitter<'a, DragEvent>,
+    pub(super) on_action: &'b EventEmitter<'a, String>,
+    pub(super) keymap: Option<&'b Keymap>,
+    pub(super) events: Option<&'b WindowEvents>,
+    pub(super) context: Option<&'b WindowContext>,
+    pub(super) recorder: Option<&'b Recorder>,
+}
+
+// Takes the events of a window from its input queue to its `EventProps`. Platform and injected events all
+// pass through here, so they are handled alike
+#[cfg(any(windows, headless))]
+#[derive(Default)]
+pub(super) struct Dispatcher {
+    // Text typed by a key press that triggered or continued a key binding is dropped as well
+    swallow_text: bool,
+}
+
+#[cfg(any(windows, headless))]
+impl Dispatcher {
+    // Run `event` through the keymaps and hand it on. Returns what is left for the backend to do: actions,
+    // close requests and resizes
+    pub(super) fn dispatch(
+        &mut self,
+        event: WindowEvent,
+        props: &EventProps<'_, '_>,
+    ) -> Option<WindowEvent> {
+        let event = match event {
+            WindowEvent::KeyDown(event) => {
+                self.swallow_text = true;
+                match dispatch_key(props.keymap, &event) {
+                    Dispatch::Action(action) => WindowEvent::Action(action),
+                    Dispatch::Pending | Dispatch::Aborted => return None,
+                    Dispatch::Unhandled => {
+                        self.swallow_text = false;
+                        WindowEvent::KeyDown(event)
+                    }
+                }
+            }
+            WindowEvent::TextInput(TextInputEvent::Commit(_)) if self.swallow_text => return None,
+            WindowEvent::Focused(false) => {
+                props.keymap.iter().for_each(|k| k.clear_pending());
+                Keymap::global().clear_pending();
+                WindowEvent::Focused(false)
+            }
+            event => event,
+        };
+        if let Some(events) = props.events {
+            events.push(event.clone());
+        }
+        if let Some(context) = props.context {
+            context.dispatch(&event);
+        }
+        if let Some(recorder) = props.recorder {
+            recorder.record_event(&event);
+        }
+        match event {
+            WindowEvent::KeyDown(event) => {
+                let _ = props.on_key_down.emit(event);
+            }
+            WindowEvent::KeyUp(event) => {
+                let _ = props.on_key_up.emit(event);
+            }
+            WindowEvent::TextInput(event) => {
+                let _ = props.on_text_input.emit(event);
+            }
+            WindowEvent::PointerEnter(event) => {
+                let _ = props.on_pointer_enter.emit(event);
+            }
+            WindowEvent::PointerLeave(event) => {
+                let _ = props.on_pointer_leave.emit(event);
+            }
+            WindowEvent::PointerMove(event) => {
+                let _ = props.on_pointer_move.emit(event);
+            }
+            WindowEvent::PointerDown(event) => {
+                let _ = props.on_pointer_down.emit(event);
+            }
+            WindowEvent::PointerUp(event) => {
+                let _ = props.on_pointer_up.emit(event);
+            }
+            WindowEvent::Scroll(event) => {
+                let _ = props.on_scroll.emit(event);
+            }
+            WindowEvent::Gesture(event) => {
+                let _ = props.on_gesture.emit(event);
+            }
+            WindowEvent::Drag(event) => {
+                let _ = props.on_drag.emit(event);
+            }
+            WindowEvent::Action(action) => {
+                let _ = props.on_action.emit(action.clone());
+                return Some(WindowEvent::Action(action));
+            }
+            event @ (WindowEvent::CloseRequested | WindowEvent::Resized { .. }) => {
+                return Some(event);
+            }
+            // Only delivered through `events`, and applied to the theme by the context
+            WindowEvent::Focused(_) | WindowEvent::ColorSchemeChanged(_) => {}
+        }
+        None
+    }
+}
+
+// Counts presses of the same button close together in time and space, for `PointerEvent::click_count`
+#[cfg(not(headless))]
+#[derive(Default)]
+pub(super) struct ClickCounter {
+    last: Option<(Instant, f64, f64, PointerButton)>,
+    count: u32,
+}
+
+#[cfg(not(headless))]
+impl ClickCounter {
+    pub(super) fn press(
+        &mut self,
+        button: PointerButton,
+        x: f64,
+        y: f64,
+        interval: Duration,
+        distance: f64,
+    ) -> u32 {
+        let now = Instant::now();
+        self.count = match self.last {
+            Some((time, last_x, last_y, last_button))
+                if last_button == button
+                    && now.duration_since(time) <= interval
+                    && (x - last_x).abs() <= distance
+                    && (y - last_y).abs() <= distance =>
+            {
+                self.count + 1
+            }
+            _ => 1,
+        };
+        self.last = Some((now, x, y, button));
+        self.count
+    }
+
+    // The count the release of a press reports
+    pub(super) fn current(&self) -> u32 {
+        self.count.max(1)
+    }
+}
+
+// Gives wheel messages, which come without gestures, the phases of one: the first event begins a gesture,
+// and it ends once no event came for `SCROLL_IDLE`
+#[cfg(any(not(headless), test))]
+#[derive(Default)]
+pub(super) struct ScrollPhases {
+    last: Option<(Instant, ScrollEvent)>,
+}
+
+#[cfg(any(not(headless), test))]
+impl ScrollPhases {
+    // Whether the ongoing gesture scrolls by pixels, so its later events do as well
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub(super) fn is_precise(&self) -> bool {
+        matches!(
+            self.last,
+            Some((
+                _,
+                ScrollEvent {
+                    delta: ScrollDelta::Pixels { .. },
+                    ..
+                }
+            ))
+        )
+    }
+
+    // Give `event` the phase it has in the ongoing gesture, or begin one
+    pub(super) fn scroll(&mut self, mut event: ScrollEvent, now: Instant) -> ScrollEvent {
+        event.phase = match self.last {
+            Some(_) => ScrollPhase::Changed,
+            None => ScrollPhase::Began,
+        };
+        self.last = Some((now, event.clone()));
+        event
+    }
+
+    // When the ongoing gesture ends unless another event comes
+    pub(super) fn deadline(&self) -> Option<Instant> {
+        self.last.as_ref().map(|(time, _)| *time + SCROLL_IDLE)
+    }
+
+    // End the gesture once its deadline passed, with an event at its last position that scrolls no further
+    pub(super) fn tick(&mut self, now: Instant) -> Option<ScrollEvent> {
+        if self.deadline().is_none_or(|deadline| now < deadline) {
+            return None;
+        }
+        let (_, mut event) = self.last.take()?;
+        event.delta = match event.delta {
+            ScrollDelta::Lines { .. } => ScrollDelta::Lines { x: 0.0, y: 0.0 },
+            ScrollDelta::Pixels { .. } => ScrollDelta::Pixels { x: 0.0, y: 0.0 },
+        };
+        event.phase = ScrollPhase::Ended;
+        Some(event)
+    }
+
+    // The window went away, along with the gesture
+    pub(super) fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(windows, headless))]
+    use crate::keyboard::{Key, KeyCode};
+    use {super::*, crate::keyboard::Modifiers};
+
+    fn wheel(delta: ScrollDelta) -> ScrollEvent {
+        ScrollEvent {
+            x: 10.0,
+            y: 20.0,
+            delta,
+            phase: ScrollPhase::Changed,
+            modifiers: Modifiers::default(),
+        }
+    }
+
+    #[test]
+    fn scroll_gesture_begins_changes_and_ends() {
+        let mut phases = ScrollPhases::default();
+        let start = Instant::now();
+        let lines = ScrollDelta::Lines { x: 0.0, y: 1.0 };
+        assert_eq!(phases.scroll(wheel(lines), start).phase, ScrollPhase::Began);
+        let later = start + SCROLL_IDLE / 2;
+        assert_eq!(
+            phases.scroll(wheel(lines), later).phase,
+            ScrollPhase::Changed
+        );
+        assert_eq!(phases.deadline(), Some(later + SCROLL_IDLE));
+        assert_eq!(phases.tick(later + SCROLL_IDLE / 2), None);
+
+        let ended = phases.tick(later + SCROLL_IDLE).unwrap();
+        assert_eq!(ended.phase, ScrollPhase::Ended);
+        assert_eq!(ended.delta, ScrollDelta::Lines { x: 0.0, y: 0.0 });
+        assert_eq!((ended.x, ended.y), (10.0, 20.0));
+        assert_eq!(phases.deadline(), None);
+        let next = later + SCROLL_IDLE * 2;
+        assert_eq!(phases.scroll(wheel(lines), next).phase, ScrollPhase::Began);
+    }
+
+    #[test]
+    fn precise_scroll_gesture() {
+        let mut phases = ScrollPhases::default();
+        let now = Instant::now();
+        assert!(!phases.is_precise());
+        phases.scroll(wheel(ScrollDelta::Pixels { x: 0.0, y: 3.5 }), now);
+        assert!(phases.is_precise());
+        let ended = phases.tick(now + SCROLL_IDLE).unwrap();
+        assert_eq!(ended.delta, ScrollDelta::Pixels { x: 0.0, y: 0.0 });
+        assert!(!phases.is_precise());
+    }
+
+    #[test]
+    fn reset_scroll_gesture() {
+        let mut phases = ScrollPhases::default();
+        let now = Instant::now();
+        phases.scroll(wheel(ScrollDelta::Lines { x: 1.0, y: 0.0 }), now);
+        phases.reset();
+        assert_eq!(phases.tick(now + SCROLL_IDLE), None);
+    }
+
+    #[cfg(any(windows, headless))]
+    #[test]
+    fn key_binding_swallows_its_text() {
+        let keymap = Keymap::new();
+        keymap.add_action("greet", &["H"]).unwrap();
+        let events = WindowEvents::default();
+        let (key, text, pointer, scroll, gesture, drag, action) = Default::default();
+        let props = EventProps {
+            on_key_down: &key,
+            on_key_up: &key,
+            on_text_input: &text,
+            on_pointer_enter: &pointer,
+            on_pointer_leave: &pointer,
+            on_pointer_move: &pointer,
+            on_pointer_down: &pointer,
+            on_pointer_up: &pointer,
+            on_scroll: &scroll,
+            on_gesture: &gesture,
+            on_drag: &drag,
+            on_action: &action,
+            keymap: Some(&keymap),
+            events: Some(&events),
+            context: None,
+            recorder: None,
+        };
+        let press = |c: char| {
+            WindowEvent::KeyDown(KeyEvent {
+                physical_key: KeyCode::KeyH,
+                logical_key: Key::Character(c),
+                text: Some(c.to_string()),
+                repeat: false,
+                modifiers: Modifiers::NONE,
+            })
+        };
+        let commit = |c: char| WindowEvent::TextInput(TextInputEvent::Commit(c.to_string()));
+
+        let mut dispatcher = Dispatcher::default();
+        let action = WindowEvent::Action("greet".to_owned());
+        assert_eq!(
+            dispatcher.dispatch(press('h'), &props),
+            Some(action.clone())
+        );
+        assert_eq!(dispatcher.dispatch(commit('h'), &props), None);
+        assert_eq!(events.try_next(), Some(action));
+        assert_eq!(events.try_next(), None);
+
+        // Other keys and their text pass through
+        assert_eq!(dispatcher.dispatch(press('x'), &props), None);
+        assert_eq!(dispatcher.dispatch(commit('x'), &props), None);
+        assert_eq!(events.try_next(), Some(press('x')));
+        assert_eq!(events.try_next(), Some(commit('x')));
+        let resize = WindowEvent::Resized {
+            width: 1,
+            height: 2,
+        };
+        assert_eq!(dispatcher.dispatch(resize.clone(), &props), Some(resize));
+    }
+}
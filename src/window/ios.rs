@@ -1,12 +1,71 @@
 use {
+    super::quartz,
+    crate::{
+        context::WindowContext,
+        drag_drop::DragEvent,
+        events::WindowEvents,
+        frame::{FrameClock, FrameTime},
+        geometry::Rect,
+        gesture::{GestureConfig, GestureEvent},
+        handle::NativeHandle,
+        inject::InputInjector,
+        keybinding::Keymap,
+        keyboard::KeyEvent,
+        pointer::PointerEvent,
+        record::{Recorder, WindowProps},
+        scroll::ScrollEvent,
+        surface::SoftwareSurface,
+        text_input::TextInputEvent,
+        theme::{ColorScheme, Theme},
+    },
     compo::prelude::*,
     objc2::{ClassType, MainThreadMarker, MainThreadOnly, Message, msg_send, rc::Retained},
-    objc2_foundation::{NSObjectProtocol, NSPoint, NSRect, NSSize},
-    objc2_ui_kit::{UIApplication, UIColor, UIScene, UIViewController, UIWindow, UIWindowScene},
+    objc2_foundation::{NSObjectProtocol, NSPoint, NSRect, NSSize, NSString},
+    objc2_ui_kit::{
+        UIApplication, UIColor, UIPasteboard, UIScene, UITraitCollection, UIUserInterfaceStyle,
+        UIView, UIViewController, UIWindow, UIWindowScene,
+    },
+    std::time::Duration,
     tracing::{error, info},
 };
+#[cfg(feature = "raw-window-handle")]
+use {
+    raw_window_handle::{UiKitDisplayHandle, UiKitWindowHandle},
+    std::ptr::NonNull,
+};
+
+/// Whether the system is in light or dark mode, as set in the Display & Brightness settings.
+pub fn system_color_scheme() -> ColorScheme {
+    let style = unsafe { UITraitCollection::currentTraitCollection().userInterfaceStyle() };
+    if style == UIUserInterfaceStyle::Dark {
+        ColorScheme::Dark
+    } else {
+        ColorScheme::Light
+    }
+}
+
+// A `0x00RRGGBB` color
+fn ui_color(color: u32) -> Retained<UIColor> {
+    let [b, g, r, _] = color.to_le_bytes();
+    let channel = |c: u8| c as f64 / 255.0;
+    UIColor::colorWithRed_green_blue_alpha(channel(r), channel(g), channel(b), 1.0)
+}
+
+/// The text on the clipboard, if it holds any.
+pub fn clipboard_text() -> Option<String> {
+    let pasteboard = UIPasteboard::generalPasteboard();
+    unsafe { pasteboard.string() }.map(|text| text.to_string())
+}
+
+/// Put `text` on the clipboard, in place of what it held.
+pub fn set_clipboard_text(text: &str) {
+    let pasteboard = UIPasteboard::generalPasteboard();
+    unsafe { pasteboard.setString(Some(&NSString::from_str(text))) };
+}
 
-// Window component for iOS
+// Window component for iOS. It shows and draws, but doesn't forward touches, key or text input yet: the
+// input props are taken so apps build the same everywhere, but their events never fire and no injector is
+// attached
 #[component]
 pub async fn window(
     #[default = "Window example"] title: &str,
@@ -16,6 +75,32 @@ pub async fn window(
     #[default = 100] top: i32,
     #[default = true] visible: bool,
     #[default = true] enabled: bool,
+    #[default = 1.0] opacity: f32,
+    #[default = true] ime_enabled: bool,
+    ime_cursor_area: Rect,
+    #[event] on_key_down: KeyEvent,
+    #[event] on_key_up: KeyEvent,
+    #[event] on_text_input: TextInputEvent,
+    #[event] on_pointer_enter: PointerEvent,
+    #[event] on_pointer_leave: PointerEvent,
+    #[event] on_pointer_move: PointerEvent,
+    #[event] on_pointer_down: PointerEvent,
+    #[event] on_pointer_up: PointerEvent,
+    #[event] on_scroll: ScrollEvent,
+    gesture_config: GestureConfig,
+    #[event] on_gesture: GestureEvent,
+    #[event] on_drag: DragEvent,
+    keymap: Option<&Keymap>,
+    #[event] on_action: String,
+    injector: Option<&InputInjector>,
+    recorder: Option<&Recorder>,
+    native_handle: Option<&NativeHandle>,
+    surface: Option<&SoftwareSurface>,
+    frame_clock: Option<&FrameClock>,
+    #[event] on_redraw: FrameTime,
+    theme: Option<&Theme>,
+    context: Option<&WindowContext>,
+    events: Option<&WindowEvents>,
 ) {
     // Get main thread marker
     let Some(mtm) = MainThreadMarker::new() else {
@@ -28,6 +113,31 @@ pub async fn window(
     let window: Option<Retained<UIWindow>> = None;
     #[field]
     let view_controller: Option<Retained<UIViewController>> = None;
+    #[field]
+    // Bumped on every render, so only the latest render produces frames
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    if let Some(recorder) = recorder {
+        recorder.record_props(WindowProps {
+            title: title.to_string(),
+            width: *width,
+            height: *height,
+            left: *left,
+            top: *top,
+            visible: *visible,
+            enabled: *enabled,
+            opacity: *opacity,
+        });
+    }
+    // Whatever the props leave out comes from the context
+    let native_handle = native_handle.or(context.map(WindowContext::native_handle));
+    // Keep the objects themselves, the borrowed props only live until the next render
+    let surface = surface.or(context.map(WindowContext::surface)).cloned();
+    let frame_clock = frame_clock
+        .or(context.map(WindowContext::frame_clock))
+        .cloned();
 
     if *visible {
         if window.is_none() {
@@ -59,14 +169,6 @@ pub async fn window(
                 // Create root view controller
                 let root_controller = UIViewController::new(mtm);
 
-                // Set background color
-                if let Some(view) = root_controller.view() {
-                    let white_color = UIColor::whiteColor();
-                    view.setBackgroundColor(Some(&white_color));
-                } else {
-                    error!("Can't get the view in root controller.");
-                }
-
                 // Set root view controller
                 window_instance.setRootViewController(Some(&root_controller));
 
@@ -98,9 +200,32 @@ pub async fn window(
                 // Set window enabled state
                 window_ref.setUserInteractionEnabled(*enabled);
 
+                // Set window opacity
+                window_ref.setAlpha(opacity.clamp(0.0, 1.0) as f64);
+
                 // Make window visible and key
                 window_ref.makeKeyAndVisible();
 
+                if let Some(context) = context {
+                    context.set_scale_factor(window_ref.contentScaleFactor());
+                }
+
+                if let Some(surface) = &surface
+                    && let Some(view) = view_controller.as_ref().and_then(|c| c.view())
+                {
+                    paint(&view, surface);
+                }
+
+                // Graphics libraries draw into the root view
+                if let Some(native_handle) = native_handle
+                    && let Some(view) = view_controller.as_ref().and_then(|c| c.view())
+                {
+                    set_native_handle(native_handle, &view, view_controller.as_deref());
+                    let (scale, size) = (view.contentScaleFactor(), view.bounds().size);
+                    native_handle
+                        .set_size((size.width * scale) as u32, (size.height * scale) as u32);
+                }
+
                 info!(
                     "iOS window updated: {}x{} at ({}, {}), enabled: {}, visible: {}",
                     *width, *height, *left, *top, *enabled, *visible
@@ -110,6 +235,9 @@ pub async fn window(
             error!("Failed to create iOS window.");
         }
     } else if let Some(window_ref) = window.take() {
+        if let Some(native_handle) = native_handle {
+            native_handle.clear();
+        }
         // Hide window when visible is false
         unsafe {
             window_ref.setHidden(true);
@@ -118,6 +246,77 @@ pub async fn window(
         }
         info!("iOS window hidden");
     }
+
+    if let Some(context) = context {
+        context.set_closed(window.is_none());
+        context.set_enabled(*enabled);
+        context.set_theme(*theme);
+    }
+    if let Some(events) = events {
+        events.set_closed(window.is_none());
+    }
+    // Shows where nothing is drawn, e.g. before the first frame
+    if let Some(view) = view_controller.as_ref().and_then(|c| c.view()) {
+        let background = match context {
+            Some(context) => context.background(),
+            None => {
+                theme
+                    .map_or_else(|| Theme::for_scheme(system_color_scheme()), Clone::clone)
+                    .palette
+                    .background
+            }
+        };
+        view.setBackgroundColor(Some(&ui_color(background)));
+    }
+    if let Some(frame_clock) = &frame_clock {
+        frame_clock.set_visible(window.is_some());
+        if let Some(window_ref) = window.as_ref() {
+            let rate = window_ref.screen().maximumFramesPerSecond().max(1);
+            frame_clock.set_interval(Duration::from_secs_f64(1.0 / rate as f64));
+        }
+    }
+
+    // Produce frames until the next render replaces this one
+    while let Some(frame_clock) = &frame_clock
+        && let Some((frame, redraw)) = frame_clock
+            .wait(|| *render_id == current_render && window.is_some())
+            .await
+    {
+        if redraw {
+            if let Some(surface) = &surface
+                && let Some(view) = view_controller.as_ref().and_then(|c| c.view())
+            {
+                paint(&view, surface);
+            }
+            let _ = on_redraw.emit(frame);
+        }
+    }
+}
+
+// Hand out the root view to graphics libraries
+#[cfg_attr(not(feature = "raw-window-handle"), allow(unused_variables))]
+fn set_native_handle(
+    native_handle: &NativeHandle,
+    view: &UIView,
+    view_controller: Option<&UIViewController>,
+) {
+    native_handle.set_available();
+    #[cfg(feature = "raw-window-handle")]
+    {
+        let mut handle = UiKitWindowHandle::new(NonNull::from(view).cast());
+        handle.ui_view_controller = view_controller.map(|c| NonNull::from(c).cast());
+        native_handle.set(handle.into(), UiKitDisplayHandle::new().into());
+    }
+}
+
+// The software surface shows as the contents of the root view's layer
+fn paint(view: &UIView, surface: &SoftwareSurface) {
+    if let Some(layer) = quartz::layer(view) {
+        let scale = view.contentScaleFactor();
+        let size = view.bounds().size;
+        let (width, height) = (size.width * scale, size.height * scale);
+        quartz::paint(&layer, surface, width as u32, height as u32, scale);
+    }
 }
 
 // Helper function to get current window scene (iOS 13+)
@@ -1,18 +1,143 @@
 use {
+    super::quartz,
+    crate::{
+        context::WindowContext,
+        drag_drop::DragEvent,
+        events::WindowEvents,
+        frame::{FrameClock, FrameTime},
+        geometry::Rect,
+        gesture::{GestureConfig, GestureEvent},
+        handle::NativeHandle,
+        inject::InputInjector,
+        keybinding::Keymap,
+        keyboard::{Key, KeyEvent, Modifiers, NamedKey},
+        pointer::PointerEvent,
+        record::{Recorder, WindowProps},
+        scroll::ScrollEvent,
+        surface::SoftwareSurface,
+        text_input::TextInputEvent,
+        theme::{ColorScheme, Theme},
+    },
     compo::prelude::*,
     objc2::{MainThreadMarker, MainThreadOnly, msg_send, rc::Retained},
     objc2_app_kit::{
         NSApplication, NSApplicationActivationPolicy, NSBackingStoreType, NSEventModifierFlags,
-        NSMenu, NSMenuItem, NSWindow, NSWindowStyleMask,
+        NSMenu, NSMenuItem, NSPasteboard, NSPasteboardTypeString, NSView, NSWindow,
+        NSWindowStyleMask,
     },
-    objc2_foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize, NSString},
-    std::sync::Once,
+    objc2_foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize, NSString, NSUserDefaults},
+    std::{sync::Once, time::Duration},
     tracing::{error, info},
 };
+#[cfg(feature = "raw-window-handle")]
+use {
+    raw_window_handle::{AppKitDisplayHandle, AppKitWindowHandle},
+    std::ptr::NonNull,
+};
 
 static MENU_SETUP: Once = Once::new();
 
-// Setup application menu with keyboard shortcuts
+/// Whether the system is in light or dark mode, as set in the Appearance settings.
+pub fn system_color_scheme() -> ColorScheme {
+    let defaults = NSUserDefaults::standardUserDefaults();
+    match defaults.stringForKey(&NSString::from_str("AppleInterfaceStyle")) {
+        Some(style) if style.to_string() == "Dark" => ColorScheme::Dark,
+        _ => ColorScheme::Light,
+    }
+}
+
+/// The text on the clipboard, if it holds any.
+pub fn clipboard_text() -> Option<String> {
+    let pasteboard = NSPasteboard::generalPasteboard();
+    pasteboard
+        .stringForType(unsafe { NSPasteboardTypeString })
+        .map(|text| text.to_string())
+}
+
+/// Put `text` on the clipboard, in place of what it held.
+pub fn set_clipboard_text(text: &str) {
+    let pasteboard = NSPasteboard::generalPasteboard();
+    pasteboard.clearContents();
+    pasteboard.setString_forType(&NSString::from_str(text), unsafe { NSPasteboardTypeString });
+}
+
+// The key equivalent of the first single-key binding of `action` in the global keymap, empty if there is none
+fn key_equivalent(action: &str) -> (Retained<NSString>, NSEventModifierFlags) {
+    let shortcut = Keymap::global()
+        .bindings(action)
+        .into_iter()
+        .find_map(|binding| match binding.0.as_slice() {
+            [shortcut] => Some(*shortcut),
+            _ => None,
+        });
+    let Some(shortcut) = shortcut else {
+        return (NSString::from_str(""), NSEventModifierFlags::empty());
+    };
+
+    // Function keys use the private-use characters of `NSEvent`, e.g. `NSUpArrowFunctionKey`
+    let key = match shortcut.key {
+        Key::Character(c) => c,
+        Key::Named(key) => match key {
+            NamedKey::Enter => '\r',
+            NamedKey::Tab => '\t',
+            NamedKey::Escape => '\u{1b}',
+            NamedKey::Backspace => '\u{8}',
+            NamedKey::Delete => '\u{7f}',
+            NamedKey::ArrowUp => '\u{f700}',
+            NamedKey::ArrowDown => '\u{f701}',
+            NamedKey::ArrowLeft => '\u{f702}',
+            NamedKey::ArrowRight => '\u{f703}',
+            NamedKey::F1 => '\u{f704}',
+            NamedKey::F2 => '\u{f705}',
+            NamedKey::F3 => '\u{f706}',
+            NamedKey::F4 => '\u{f707}',
+            NamedKey::F5 => '\u{f708}',
+            NamedKey::F6 => '\u{f709}',
+            NamedKey::F7 => '\u{f70a}',
+            NamedKey::F8 => '\u{f70b}',
+            NamedKey::F9 => '\u{f70c}',
+            NamedKey::F10 => '\u{f70d}',
+            NamedKey::F11 => '\u{f70e}',
+            NamedKey::F12 => '\u{f70f}',
+            NamedKey::F13 => '\u{f710}',
+            NamedKey::F14 => '\u{f711}',
+            NamedKey::F15 => '\u{f712}',
+            NamedKey::F16 => '\u{f713}',
+            NamedKey::F17 => '\u{f714}',
+            NamedKey::F18 => '\u{f715}',
+            NamedKey::F19 => '\u{f716}',
+            NamedKey::F20 => '\u{f717}',
+            NamedKey::F21 => '\u{f718}',
+            NamedKey::F22 => '\u{f719}',
+            NamedKey::F23 => '\u{f71a}',
+            NamedKey::F24 => '\u{f71b}',
+            NamedKey::Insert => '\u{f727}',
+            NamedKey::Home => '\u{f729}',
+            NamedKey::End => '\u{f72b}',
+            NamedKey::PageUp => '\u{f72c}',
+            NamedKey::PageDown => '\u{f72d}',
+            _ => return (NSString::from_str(""), NSEventModifierFlags::empty()),
+        },
+        Key::Dead(_) | Key::Unidentified => {
+            return (NSString::from_str(""), NSEventModifierFlags::empty());
+        }
+    };
+
+    let mut flags = NSEventModifierFlags::empty();
+    for (modifier, flag) in [
+        (Modifiers::SUPER, NSEventModifierFlags::Command),
+        (Modifiers::SHIFT, NSEventModifierFlags::Shift),
+        (Modifiers::ALT, NSEventModifierFlags::Option),
+        (Modifiers::CONTROL, NSEventModifierFlags::Control),
+    ] {
+        if shortcut.modifiers.contains(modifier) {
+            flags |= flag;
+        }
+    }
+    (NSString::from_str(&key.to_string()), flags)
+}
+
+// Setup application menu with the shortcuts of `Keymap::QUIT` and `Keymap::CLOSE_WINDOW`
 unsafe fn setup_app_menu(app: &NSApplication, mtm: MainThreadMarker) {
     // Create main menu bar
     let main_menu = NSMenu::new(mtm);
@@ -21,15 +146,15 @@ unsafe fn setup_app_menu(app: &NSApplication, mtm: MainThreadMarker) {
     let app_menu_item = NSMenuItem::new(mtm);
     let app_menu = NSMenu::new(mtm);
 
-    // Add Quit menu item with Command+Q shortcut
+    // Add Quit menu item, with Command+Q unless the keymap says otherwise
     let quit_title = NSString::from_str("Quit");
-    let quit_key = NSString::from_str("q");
+    let (quit_key, quit_modifiers) = key_equivalent(Keymap::QUIT);
     let quit_item: Retained<NSMenuItem> = msg_send![NSMenuItem::alloc(mtm),
         initWithTitle: &*quit_title,
         action: objc2::sel!(terminate:),
         keyEquivalent: &*quit_key,
     ];
-    quit_item.setKeyEquivalentModifierMask(NSEventModifierFlags::Command);
+    quit_item.setKeyEquivalentModifierMask(quit_modifiers);
     unsafe { quit_item.setTarget(Some(&*app)) };
     app_menu.addItem(&quit_item);
 
@@ -44,15 +169,15 @@ unsafe fn setup_app_menu(app: &NSApplication, mtm: MainThreadMarker) {
     let window_menu = NSMenu::new(mtm);
     window_menu.setTitle(&window_menu_title);
 
-    // Add Close Window menu item with Command+W shortcut
+    // Add Close Window menu item, with Command+W unless the keymap says otherwise
     let close_title = NSString::from_str("Close Window");
-    let close_key = NSString::from_str("w");
+    let (close_key, close_modifiers) = key_equivalent(Keymap::CLOSE_WINDOW);
     let close_item: Retained<NSMenuItem> = msg_send![NSMenuItem::alloc(mtm),
         initWithTitle: &*close_title,
         action: objc2::sel!(performClose:),
         keyEquivalent: &*close_key,
     ];
-    close_item.setKeyEquivalentModifierMask(NSEventModifierFlags::Command);
+    close_item.setKeyEquivalentModifierMask(close_modifiers);
     window_menu.addItem(&close_item);
 
     window_menu_item.setSubmenu(Some(&window_menu));
@@ -62,7 +187,8 @@ unsafe fn setup_app_menu(app: &NSApplication, mtm: MainThreadMarker) {
     app.setMainMenu(Some(&main_menu));
 }
 
-// Window component
+// Window component. It shows and draws, but doesn't forward mouse, key or text input yet: the input props
+// are taken so apps build the same everywhere, but their events never fire and no injector is attached
 #[component]
 pub async fn window(
     #[default = "Window"] title: &str,
@@ -72,10 +198,61 @@ pub async fn window(
     #[default = 100] top: i32,
     #[default = true] visible: bool,
     #[default = true] enabled: bool,
+    #[default = 1.0] opacity: f32,
+    #[default = true] ime_enabled: bool,
+    ime_cursor_area: Rect,
+    #[event] on_key_down: KeyEvent,
+    #[event] on_key_up: KeyEvent,
+    #[event] on_text_input: TextInputEvent,
+    #[event] on_pointer_enter: PointerEvent,
+    #[event] on_pointer_leave: PointerEvent,
+    #[event] on_pointer_move: PointerEvent,
+    #[event] on_pointer_down: PointerEvent,
+    #[event] on_pointer_up: PointerEvent,
+    #[event] on_scroll: ScrollEvent,
+    gesture_config: GestureConfig,
+    #[event] on_gesture: GestureEvent,
+    #[event] on_drag: DragEvent,
+    keymap: Option<&Keymap>,
+    #[event] on_action: String,
+    injector: Option<&InputInjector>,
+    recorder: Option<&Recorder>,
+    native_handle: Option<&NativeHandle>,
+    surface: Option<&SoftwareSurface>,
+    frame_clock: Option<&FrameClock>,
+    #[event] on_redraw: FrameTime,
+    theme: Option<&Theme>,
+    context: Option<&WindowContext>,
+    events: Option<&WindowEvents>,
 ) {
     #[field]
     // This is a field of the component's internal structure, not a variable in the current scope, so it can persist across multiple renders
     let window: Option<Retained<NSWindow>> = None;
+    #[field]
+    // Bumped on every render, so only the latest render produces frames
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    if let Some(recorder) = recorder {
+        recorder.record_props(WindowProps {
+            title: title.to_string(),
+            width: *width,
+            height: *height,
+            left: *left,
+            top: *top,
+            visible: *visible,
+            enabled: *enabled,
+            opacity: *opacity,
+        });
+    }
+    // Whatever the props leave out comes from the context
+    let native_handle = native_handle.or(context.map(WindowContext::native_handle));
+    // Keep the objects themselves, the borrowed props only live until the next render
+    let surface = surface.or(context.map(WindowContext::surface)).cloned();
+    let frame_clock = frame_clock
+        .or(context.map(WindowContext::frame_clock))
+        .cloned();
 
     let Some(mtm) = MainThreadMarker::new() else {
         error!("Window component must be running on main thread.");
@@ -138,9 +315,30 @@ pub async fn window(
                 // Set window enabled state (supports reactive updates)
                 window_ref.setIgnoresMouseEvents(!*enabled);
 
+                // Set window opacity (supports reactive updates)
+                window_ref.setAlphaValue(opacity.clamp(0.0, 1.0) as f64);
+
                 // Make window visible
                 window_ref.makeKeyAndOrderFront(None);
 
+                if let Some(context) = context {
+                    context.set_scale_factor(window_ref.backingScaleFactor());
+                }
+
+                if let Some(surface) = &surface {
+                    paint(window_ref, surface);
+                }
+
+                // Graphics libraries draw into the content view
+                if let Some(native_handle) = native_handle
+                    && let Some(view) = window_ref.contentView()
+                {
+                    set_native_handle(native_handle, &view);
+                    let (scale, size) = (window_ref.backingScaleFactor(), view.bounds().size);
+                    native_handle
+                        .set_size((size.width * scale) as u32, (size.height * scale) as u32);
+                }
+
                 // Get window content size for logging
                 let content_rect: NSRect = msg_send![&**window_ref, contentRectForFrameRect: frame];
                 info!(
@@ -152,9 +350,69 @@ pub async fn window(
             error!("Failed to create window.");
         }
     } else if let Some(window_ref) = window.take() {
+        if let Some(native_handle) = native_handle {
+            native_handle.clear();
+        }
         // If visible is false and window exists, close the window
         unsafe {
             let _: () = msg_send![&*window_ref, close];
         }
     }
+
+    if let Some(context) = context {
+        context.set_closed(window.is_none());
+        context.set_enabled(*enabled);
+        context.set_theme(*theme);
+    }
+    if let Some(events) = events {
+        events.set_closed(window.is_none());
+    }
+    if let Some(frame_clock) = &frame_clock {
+        frame_clock.set_visible(window.is_some());
+        if let Some(screen) = window.as_ref().and_then(|w| w.screen()) {
+            let rate = screen.maximumFramesPerSecond().max(1);
+            frame_clock.set_interval(Duration::from_secs_f64(1.0 / rate as f64));
+        }
+    }
+
+    // Produce frames until the next render replaces this one
+    while let Some(frame_clock) = &frame_clock
+        && let Some((frame, redraw)) = frame_clock
+            .wait(|| *render_id == current_render && window.is_some())
+            .await
+    {
+        if redraw {
+            if let Some(window_ref) = window.as_ref()
+                && let Some(surface) = &surface
+            {
+                paint(window_ref, surface);
+            }
+            let _ = on_redraw.emit(frame);
+        }
+    }
+}
+
+// Hand out the content view to graphics libraries
+#[cfg_attr(not(feature = "raw-window-handle"), allow(unused_variables))]
+fn set_native_handle(native_handle: &NativeHandle, view: &NSView) {
+    native_handle.set_available();
+    #[cfg(feature = "raw-window-handle")]
+    {
+        let handle = AppKitWindowHandle::new(NonNull::from(view).cast());
+        native_handle.set(handle.into(), AppKitDisplayHandle::new().into());
+    }
+}
+
+// The software surface shows as the contents of the content view's layer
+fn paint(window: &NSWindow, surface: &SoftwareSurface) {
+    let Some(view) = window.contentView() else {
+        return;
+    };
+    view.setWantsLayer(true);
+    if let Some(layer) = quartz::layer(&view) {
+        let scale = window.backingScaleFactor();
+        let size = view.bounds().size;
+        let (width, height) = (size.width * scale, size.height * scale);
+        quartz::paint(&layer, surface, width as u32, height as u32, scale);
+    }
 }
@@ -0,0 +1,61 @@
+use {
+    crate::surface::SoftwareSurface,
+    objc2::{msg_send, rc::Retained, runtime::AnyObject},
+    objc2_core_foundation::{CFData, CFRetained, CFType},
+    objc2_core_graphics::{
+        CGBitmapInfo, CGColorRenderingIntent, CGColorSpace, CGDataProvider, CGImage,
+        CGImageAlphaInfo, CGImageByteOrderInfo,
+    },
+    objc2_quartz_core::CALayer,
+    std::ptr::null,
+};
+
+// Draw a frame of `width` x `height` physical pixels and show it as the contents of `layer`
+pub(super) fn paint(
+    layer: &CALayer,
+    surface: &SoftwareSurface,
+    width: u32,
+    height: u32,
+    scale: f64,
+) {
+    // A collapsed view has nothing to show
+    if width == 0 || height == 0 {
+        return;
+    }
+    surface.present(width, height, |pixels, width, height, _| {
+        let Some(image) = image(pixels, width, height) else {
+            return;
+        };
+        let contents: &CFType = &image;
+        let contents: &AnyObject = contents.as_ref();
+        unsafe { layer.setContents(Some(contents)) };
+        layer.setContentsScale(scale);
+    });
+}
+
+// The layer of a view, which backs every view on iOS and layer-backed views on macOS
+pub(super) fn layer(view: &AnyObject) -> Option<Retained<CALayer>> {
+    unsafe { msg_send![view, layer] }
+}
+
+// 0x00RRGGBB words in little endian, i.e. the bytes B, G, R, X
+fn image(pixels: &[u32], width: u32, height: u32) -> Option<CFRetained<CGImage>> {
+    let bytes = unsafe { pixels.align_to::<u8>().1 };
+    let provider = CGDataProvider::with_cf_data(Some(&CFData::from_bytes(bytes)))?;
+    let color_space = CGColorSpace::new_device_rgb()?;
+    unsafe {
+        CGImage::new(
+            width as usize,
+            height as usize,
+            8,
+            32,
+            width as usize * 4,
+            Some(&color_space),
+            CGBitmapInfo(CGImageByteOrderInfo::Order32Little.0 | CGImageAlphaInfo::NoneSkipFirst.0),
+            Some(&provider),
+            null(),
+            false,
+            CGColorRenderingIntent::RenderingIntentDefault,
+        )
+    }
+}
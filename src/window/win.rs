@@ -1,25 +1,896 @@
+mod ole;
+
+pub use ole::{clipboard_text, set_clipboard_text, start_drag};
+
 use {
+    super::input::{
+        ClickCounter, Dispatcher, EventProps, InputQueue, ScrollPhases, Wake, earliest,
+        reaches_disabled, yield_now,
+    },
+    crate::{
+        capture::{Capture, CaptureError},
+        context::WindowContext,
+        drag_drop::DragEvent,
+        events::{WindowEvent, WindowEvents},
+        frame::{FrameClock, FrameTime},
+        geometry::Rect,
+        gesture::{GestureConfig, GestureEvent, GestureRecognizer},
+        handle::NativeHandle,
+        inject::InputInjector,
+        keybinding::Keymap,
+        keyboard::{Key, KeyCode, KeyEvent, Modifiers, NamedKey},
+        pointer::{PointerButton, PointerEvent, PointerType},
+        record::{Recorder, WindowProps},
+        scroll::{ScrollDelta, ScrollEvent, ScrollPhase},
+        surface::SoftwareSurface,
+        text_input::{ImeRequest, TextInputEvent},
+        theme::{ColorScheme, Theme},
+    },
     compo::prelude::*,
+    std::{
+        cell::Cell,
+        ffi::c_void,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
     tracing::{error, info},
     windows::{
         Win32::{
-            Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM},
-            Graphics::Gdi::HBRUSH,
-            System::LibraryLoader::GetModuleHandleW,
+            Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
+            Graphics::Gdi::{
+                BI_RGB, BITMAPINFO, BITMAPINFOHEADER, BeginPaint, CreateCompatibleBitmap,
+                CreateCompatibleDC, DIB_RGB_COLORS, DeleteDC, DeleteObject, EndPaint, GetDC,
+                GetDIBits, GetDeviceCaps, HBRUSH, HDC, IntersectClipRect, InvalidateRect,
+                PAINTSTRUCT, ReleaseDC, RestoreDC, SaveDC, ScreenToClient, SelectObject,
+                SetDIBitsToDevice, VREFRESH,
+            },
+            Storage::Xps::{PRINT_WINDOW_FLAGS, PW_CLIENTONLY, PrintWindow},
+            System::{
+                LibraryLoader::GetModuleHandleW,
+                Registry::{HKEY_CURRENT_USER, RRF_RT_REG_DWORD, RegGetValueW},
+            },
             UI::{
-                Input::KeyboardAndMouse::EnableWindow,
+                Controls::WM_MOUSELEAVE,
+                HiDpi::GetDpiForWindow,
+                Input::{
+                    Ime::{
+                        ATTR_TARGET_CONVERTED, ATTR_TARGET_NOTCONVERTED, CANDIDATEFORM,
+                        CFS_EXCLUDE, CFS_POINT, COMPOSITIONFORM, GCS_COMPATTR, GCS_COMPSTR,
+                        GCS_CURSORPOS, GCS_RESULTSTR, HIMC, IACE_DEFAULT, IME_COMPOSITION_STRING,
+                        ImmAssociateContextEx, ImmGetCompositionStringW, ImmGetContext,
+                        ImmReleaseContext, ImmSetCandidateWindow, ImmSetCompositionWindow,
+                    },
+                    KeyboardAndMouse::{
+                        EnableWindow, GetDoubleClickTime, GetKeyState, GetKeyboardState,
+                        IsWindowEnabled, ReleaseCapture, SetCapture, TME_LEAVE, TRACKMOUSEEVENT,
+                        ToUnicode, TrackMouseEvent, VIRTUAL_KEY, VK_APPS, VK_BACK, VK_CAPITAL,
+                        VK_CONTROL, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_F2, VK_F3,
+                        VK_F4, VK_F5, VK_F6, VK_F7, VK_F8, VK_F9, VK_F10, VK_F11, VK_F12, VK_F13,
+                        VK_F14, VK_F15, VK_F16, VK_F17, VK_F18, VK_F19, VK_F20, VK_F21, VK_F22,
+                        VK_F23, VK_F24, VK_HOME, VK_INSERT, VK_LCONTROL, VK_LEFT, VK_LMENU,
+                        VK_LSHIFT, VK_LWIN, VK_MENU, VK_NEXT, VK_NUMLOCK, VK_PAUSE, VK_PRIOR,
+                        VK_RCONTROL, VK_RETURN, VK_RIGHT, VK_RMENU, VK_RSHIFT, VK_RWIN, VK_SCROLL,
+                        VK_SHIFT, VK_SNAPSHOT, VK_TAB, VK_UP,
+                    },
+                    Pointer::{
+                        GetPointerPenInfo, GetPointerTouchInfo, GetPointerType,
+                        POINTER_FLAG_CANCELED, POINTER_INFO, POINTER_PEN_INFO, POINTER_TOUCH_INFO,
+                    },
+                },
                 WindowsAndMessaging::{
                     CREATESTRUCTW, CS_HREDRAW, CS_VREDRAW, CW_USEDEFAULT, CreateWindowExW,
-                    DefWindowProcW, DestroyWindow, GWLP_USERDATA, GetClientRect, HCURSOR, HICON,
-                    PostQuitMessage, RegisterClassW, SW_SHOW, SWP_NOZORDER, SetWindowLongPtrW,
-                    SetWindowPos, SetWindowTextW, ShowWindow, WM_CREATE, WM_DESTROY, WNDCLASSW,
-                    WS_EX_LEFT, WS_OVERLAPPEDWINDOW,
+                    DefWindowProcW, DestroyWindow, GWL_EXSTYLE, GWLP_USERDATA, GetClientRect,
+                    GetMessageExtraInfo, GetSystemMetrics, GetWindowLongPtrW, HCURSOR, HICON,
+                    KF_EXTENDED, KF_REPEAT, LWA_ALPHA, PEN_FLAG_BARREL, PEN_FLAG_ERASER,
+                    PEN_MASK_PRESSURE, PEN_MASK_TILT_X, PEN_MASK_TILT_Y, POINTER_INPUT_TYPE,
+                    PT_PEN, PT_TOUCH, PW_RENDERFULLCONTENT, PostMessageW, PostQuitMessage,
+                    RegisterClassW, SM_CXDOUBLECLK, SPI_GETWHEELSCROLLCHARS,
+                    SPI_GETWHEELSCROLLLINES, SW_SHOW, SWP_NOZORDER,
+                    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SetLayeredWindowAttributes,
+                    SetWindowLongPtrW, SetWindowPos, SetWindowTextW, ShowWindow,
+                    SystemParametersInfoW, TOUCH_MASK_PRESSURE, WHEEL_DELTA, WM_CHAR, WM_CLOSE,
+                    WM_CREATE, WM_DESTROY, WM_IME_COMPOSITION, WM_IME_ENDCOMPOSITION, WM_KEYDOWN,
+                    WM_KEYUP, WM_KILLFOCUS, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN,
+                    WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_NCDESTROY,
+                    WM_PAINT, WM_POINTERDOWN, WM_POINTERENTER, WM_POINTERLEAVE, WM_POINTERUP,
+                    WM_POINTERUPDATE, WM_PRINTCLIENT, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SETFOCUS,
+                    WM_SETTINGCHANGE, WM_SIZE, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_XBUTTONDOWN,
+                    WM_XBUTTONUP, WNDCLASSW, WS_EX_LAYERED, WS_EX_LEFT, WS_OVERLAPPEDWINDOW,
                 },
             },
         },
-        core::{PCWSTR, w},
+        core::{Error, PCWSTR, w},
     },
 };
+#[cfg(feature = "raw-window-handle")]
+use {
+    raw_window_handle::{Win32WindowHandle, WindowsDisplayHandle},
+    std::num::NonZeroIsize,
+    windows::Win32::UI::WindowsAndMessaging::GWLP_HINSTANCE,
+};
+
+// Per-window state shared with the window procedure, which keeps a reference in `GWLP_USERDATA`
+#[derive(Default)]
+struct WindowState {
+    input: InputQueue<WindowEvent>,
+    // A high surrogate from `WM_CHAR`, waiting for its low half
+    high_surrogate: Cell<Option<u16>>,
+    composing: Cell<bool>,
+    mouse_inside: Cell<bool>,
+    clicks: RefCell<ClickCounter>,
+    scroll: RefCell<ScrollPhases>,
+    gestures: RefCell<GestureRecognizer>,
+    surface: RefCell<Option<SoftwareSurface>>,
+}
+
+impl WindowState {
+    fn push(&self, input: WindowEvent) {
+        // Touches also feed the gesture recognizer, whose gestures follow the touch that completed them
+        let now = Instant::now();
+        let gestures = match &input {
+            WindowEvent::PointerDown(event) => self.gestures.borrow_mut().pointer_down(event, now),
+            WindowEvent::PointerMove(event) => self.gestures.borrow_mut().pointer_move(event, now),
+            WindowEvent::PointerUp(event) => self.gestures.borrow_mut().pointer_up(event, now),
+            _ => Vec::new(),
+        };
+        self.input.push(input);
+        gestures
+            .into_iter()
+            .for_each(|g| self.input.push(WindowEvent::Gesture(g)));
+    }
+
+    fn push_char(&self, unit: u16) {
+        let text = match unit {
+            0xD800..=0xDBFF => {
+                self.high_surrogate.set(Some(unit));
+                return;
+            }
+            0xDC00..=0xDFFF => match self.high_surrogate.take() {
+                Some(high) => String::from_utf16_lossy(&[high, unit]),
+                None => return,
+            },
+            _ => String::from_utf16_lossy(&[unit]),
+        };
+        // Backspace, Enter, Escape and friends are left to the key events
+        if !text.chars().any(char::is_control) {
+            self.push(WindowEvent::TextInput(TextInputEvent::Commit(text)));
+        }
+    }
+
+    fn end_composition(&self) {
+        if self.composing.replace(false) {
+            self.push(WindowEvent::TextInput(TextInputEvent::Preedit {
+                text: String::new(),
+                cursor: None,
+            }));
+        }
+    }
+}
+
+fn current_modifiers() -> Modifiers {
+    let pressed = |vk: VIRTUAL_KEY| unsafe { GetKeyState(vk.0 as i32) } < 0;
+    let mut modifiers = Modifiers::NONE;
+    if pressed(VK_SHIFT) {
+        modifiers |= Modifiers::SHIFT;
+    }
+    if pressed(VK_CONTROL) {
+        modifiers |= Modifiers::CONTROL;
+    }
+    if pressed(VK_MENU) {
+        modifiers |= Modifiers::ALT;
+    }
+    if pressed(VK_LWIN) || pressed(VK_RWIN) {
+        modifiers |= Modifiers::SUPER;
+    }
+
+    modifiers
+}
+
+fn named_key(vk: VIRTUAL_KEY) -> Option<NamedKey> {
+    Some(match vk {
+        VK_MENU | VK_LMENU | VK_RMENU => NamedKey::Alt,
+        VK_CAPITAL => NamedKey::CapsLock,
+        VK_CONTROL | VK_LCONTROL | VK_RCONTROL => NamedKey::Control,
+        VK_NUMLOCK => NamedKey::NumLock,
+        VK_SCROLL => NamedKey::ScrollLock,
+        VK_SHIFT | VK_LSHIFT | VK_RSHIFT => NamedKey::Shift,
+        VK_LWIN | VK_RWIN => NamedKey::Super,
+        VK_RETURN => NamedKey::Enter,
+        VK_TAB => NamedKey::Tab,
+        VK_DOWN => NamedKey::ArrowDown,
+        VK_LEFT => NamedKey::ArrowLeft,
+        VK_RIGHT => NamedKey::ArrowRight,
+        VK_UP => NamedKey::ArrowUp,
+        VK_END => NamedKey::End,
+        VK_HOME => NamedKey::Home,
+        VK_NEXT => NamedKey::PageDown,
+        VK_PRIOR => NamedKey::PageUp,
+        VK_BACK => NamedKey::Backspace,
+        VK_DELETE => NamedKey::Delete,
+        VK_INSERT => NamedKey::Insert,
+        VK_ESCAPE => NamedKey::Escape,
+        VK_APPS => NamedKey::ContextMenu,
+        VK_PAUSE => NamedKey::Pause,
+        VK_SNAPSHOT => NamedKey::PrintScreen,
+        VK_F1 => NamedKey::F1,
+        VK_F2 => NamedKey::F2,
+        VK_F3 => NamedKey::F3,
+        VK_F4 => NamedKey::F4,
+        VK_F5 => NamedKey::F5,
+        VK_F6 => NamedKey::F6,
+        VK_F7 => NamedKey::F7,
+        VK_F8 => NamedKey::F8,
+        VK_F9 => NamedKey::F9,
+        VK_F10 => NamedKey::F10,
+        VK_F11 => NamedKey::F11,
+        VK_F12 => NamedKey::F12,
+        VK_F13 => NamedKey::F13,
+        VK_F14 => NamedKey::F14,
+        VK_F15 => NamedKey::F15,
+        VK_F16 => NamedKey::F16,
+        VK_F17 => NamedKey::F17,
+        VK_F18 => NamedKey::F18,
+        VK_F19 => NamedKey::F19,
+        VK_F20 => NamedKey::F20,
+        VK_F21 => NamedKey::F21,
+        VK_F22 => NamedKey::F22,
+        VK_F23 => NamedKey::F23,
+        VK_F24 => NamedKey::F24,
+        _ => return None,
+    })
+}
+
+// Ask the active keyboard layout what the key produces, returning the UTF-16 units and whether it is a dead
+// key
+fn translate_key(vk: VIRTUAL_KEY, scancode: u32, state: &[u8; 256]) -> (Vec<u16>, bool) {
+    let mut buffer = [0u16; 8];
+    // Bit 2 keeps the keyboard state untouched, so dead keys still combine when `WM_CHAR` is generated
+    let len = unsafe { ToUnicode(vk.0 as u32, scancode, Some(state), &mut buffer, 1 << 2) };
+    match len {
+        0 => (Vec::new(), false),
+        l if l < 0 => (buffer[..1].to_vec(), true),
+        l => (buffer[..(l as usize).min(buffer.len())].to_vec(), false),
+    }
+}
+
+// Work out the logical key and text from the keyboard state, with `translate` asking the layout for a state
+fn translated_key(
+    mut state: [u8; 256],
+    pressed: bool,
+    translate: impl Fn(&[u8; 256]) -> (Vec<u16>, bool),
+) -> (Key, Option<String>) {
+    let (units, dead) = translate(&state);
+    let produced = String::from_utf16_lossy(&units);
+    if dead {
+        (Key::Dead(produced.chars().next()), None)
+    } else if produced.is_empty() || produced.chars().any(char::is_control) {
+        // Control combinations produce control characters, or nothing at all for digits and most punctuation
+        // like Ctrl+/, report the key itself without Control
+        for i in [VK_CONTROL, VK_LCONTROL, VK_RCONTROL] {
+            state[i.0 as usize] = 0;
+        }
+        let (units, _) = translate(&state);
+        let key = String::from_utf16_lossy(&units)
+            .chars()
+            .next()
+            .filter(|c| !c.is_control())
+            .map_or(Key::Unidentified, Key::Character);
+        (key, None)
+    } else {
+        let key = produced
+            .chars()
+            .next()
+            .map_or(Key::Unidentified, Key::Character);
+        (key, Some(produced).filter(|_| pressed))
+    }
+}
+
+fn key_event(wparam: WPARAM, lparam: LPARAM, pressed: bool) -> KeyEvent {
+    let vk = VIRTUAL_KEY(wparam.0 as u16);
+    let flags = ((lparam.0 as usize >> 16) & 0xFFFF) as u32;
+    let mut scancode = flags & 0xFF;
+    if flags & KF_EXTENDED != 0 {
+        scancode |= 0xE000;
+    }
+    let modifiers = current_modifiers();
+
+    let (logical_key, text) = if let Some(named) = named_key(vk) {
+        (Key::Named(named), None)
+    } else {
+        let mut state = [0u8; 256];
+        if unsafe { GetKeyboardState(&mut state) }.is_err() {
+            (Key::Unidentified, None)
+        } else {
+            translated_key(state, pressed, |state| {
+                translate_key(vk, scancode & 0xFF, state)
+            })
+        }
+    };
+
+    KeyEvent {
+        physical_key: KeyCode::from_scancode(scancode),
+        logical_key,
+        text,
+        repeat: pressed && flags & KF_REPEAT != 0,
+        modifiers,
+    }
+}
+
+// Read one of the composition strings or attribute arrays of the input context as raw bytes
+fn composition_data(himc: HIMC, kind: IME_COMPOSITION_STRING) -> Vec<u8> {
+    let size = unsafe { ImmGetCompositionStringW(himc, kind, None, 0) };
+    if size <= 0 {
+        return Vec::new();
+    }
+    let mut buffer = vec![0u8; size as usize];
+    let size = unsafe {
+        ImmGetCompositionStringW(himc, kind, Some(buffer.as_mut_ptr().cast()), size as u32)
+    };
+    buffer.truncate(size.max(0) as usize);
+    buffer
+}
+
+fn composition_text(himc: HIMC, kind: IME_COMPOSITION_STRING) -> Vec<u16> {
+    composition_data(himc, kind)
+        .chunks_exact(2)
+        .map(|i| u16::from_ne_bytes([i[0], i[1]]))
+        .collect()
+}
+
+// Convert an offset in UTF-16 units into a byte offset in the UTF-8 string
+fn utf8_offset(units: &[u16], offset: usize) -> usize {
+    String::from_utf16_lossy(&units[..offset.min(units.len())]).len()
+}
+
+fn preedit(himc: HIMC) -> TextInputEvent {
+    let units = composition_text(himc, GCS_COMPSTR);
+    let attributes = composition_data(himc, GCS_COMPATTR);
+    let caret = unsafe { ImmGetCompositionStringW(himc, GCS_CURSORPOS, None, 0) }.max(0) as usize;
+
+    // Highlight the clause being converted, falling back to the caret
+    let is_target = |a: &u8| matches!(*a as u32, ATTR_TARGET_CONVERTED | ATTR_TARGET_NOTCONVERTED);
+    let (start, end) = match (
+        attributes.iter().position(is_target),
+        attributes.iter().rposition(is_target),
+    ) {
+        (Some(start), Some(end)) => (start, end + 1),
+        _ => (caret, caret),
+    };
+
+    TextInputEvent::Preedit {
+        text: String::from_utf16_lossy(&units),
+        cursor: Some((utf8_offset(&units, start), utf8_offset(&units, end))),
+    }
+}
+
+// Draw a frame of the software surface and copy it into the client area. The system asks for this when
+// the window's contents were lost, so the whole frame is drawn
+fn paint(hwnd: HWND, surface: &SoftwareSurface) {
+    let mut paint = PAINTSTRUCT::default();
+    let hdc = unsafe { BeginPaint(hwnd, &mut paint) };
+    surface.invalidate();
+    present(hwnd, hdc, surface);
+    let _ = unsafe { EndPaint(hwnd, &paint) };
+}
+
+// Draw a frame for the frame clock, copying only the damaged areas into the client area
+fn redraw(hwnd: HWND, surface: &SoftwareSurface) {
+    let hdc = unsafe { GetDC(Some(hwnd)) };
+    present(hwnd, hdc, surface);
+    unsafe { ReleaseDC(Some(hwnd), hdc) };
+}
+
+fn present(hwnd: HWND, hdc: HDC, surface: &SoftwareSurface) {
+    let mut rect = RECT::default();
+    let _ = unsafe { GetClientRect(hwnd, &mut rect) };
+    let (width, height) = (
+        (rect.right - rect.left) as u32,
+        (rect.bottom - rect.top) as u32,
+    );
+    // A minimized window has nothing to show
+    if width == 0 || height == 0 {
+        return;
+    }
+    surface.present(width, height, |pixels, width, height, damage| {
+        let info = bitmap_info(width, height);
+        for rect in damage {
+            // Clipping to the rectangle copies only its pixels out of the whole frame
+            unsafe {
+                let saved = SaveDC(hdc);
+                IntersectClipRect(
+                    hdc,
+                    rect.x,
+                    rect.y,
+                    rect.x + rect.width,
+                    rect.y + rect.height,
+                );
+                SetDIBitsToDevice(
+                    hdc,
+                    0,
+                    0,
+                    width,
+                    height,
+                    0,
+                    0,
+                    0,
+                    height,
+                    pixels.as_ptr().cast(),
+                    &info,
+                    DIB_RGB_COLORS,
+                );
+                let _ = RestoreDC(hdc, saved);
+            }
+        }
+    });
+}
+
+// Copy the last frame into `hdc` without drawing a new one, for `PrintWindow`
+fn print_client(hdc: HDC, surface: &SoftwareSurface) {
+    let (width, height) = surface.size();
+    let pixels = surface.pixels();
+    if pixels.is_empty() {
+        return;
+    }
+    unsafe {
+        SetDIBitsToDevice(
+            hdc,
+            0,
+            0,
+            width,
+            height,
+            0,
+            0,
+            0,
+            height,
+            pixels.as_ptr().cast(),
+            &bitmap_info(width, height),
+            DIB_RGB_COLORS,
+        );
+    }
+}
+
+// The layout of the software surface's pixels
+fn bitmap_info(width: u32, height: u32) -> BITMAPINFO {
+    BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            // Negative for rows top to bottom
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+// Read back the client area. `PW_RENDERFULLCONTENT` takes it from the compositor, so what graphics
+// libraries drew is included, and older systems ask the window procedure with `WM_PRINTCLIENT`
+fn capture_window(hwnd: HWND) -> Result<Capture, CaptureError> {
+    let mut rect = RECT::default();
+    unsafe { GetClientRect(hwnd, &mut rect) }.map_err(|e| CaptureError::Platform(Arc::new(e)))?;
+    let (width, height) = (
+        (rect.right - rect.left) as u32,
+        (rect.bottom - rect.top) as u32,
+    );
+    // A minimized window has no client area
+    if width == 0 || height == 0 {
+        return Ok(Capture::from_frame(&[], 0, 0));
+    }
+    let mut frame = vec![0u32; width as usize * height as usize];
+    let lines = unsafe {
+        let window_dc = GetDC(Some(hwnd));
+        let dc = CreateCompatibleDC(Some(window_dc));
+        let bitmap = CreateCompatibleBitmap(window_dc, width as i32, height as i32);
+        let old = SelectObject(dc, bitmap.into());
+        let flags = PRINT_WINDOW_FLAGS(PW_CLIENTONLY.0 | PW_RENDERFULLCONTENT);
+        let printed = PrintWindow(hwnd, dc, flags).as_bool();
+        SelectObject(dc, old);
+        let mut info = bitmap_info(width, height);
+        let lines = if printed {
+            GetDIBits(
+                dc,
+                bitmap,
+                0,
+                height,
+                Some(frame.as_mut_ptr().cast()),
+                &mut info,
+                DIB_RGB_COLORS,
+            )
+        } else {
+            0
+        };
+        let _ = DeleteObject(bitmap.into());
+        let _ = DeleteDC(dc);
+        ReleaseDC(Some(hwnd), window_dc);
+        lines
+    };
+    if lines == 0 {
+        return Err(CaptureError::Platform(Arc::new(Error::from_thread())));
+    }
+    Ok(Capture::from_frame(&frame, width, height))
+}
+
+// The time between two refreshes of the display showing the window
+fn refresh_interval(hwnd: HWND) -> Duration {
+    let hdc = unsafe { GetDC(Some(hwnd)) };
+    let rate = unsafe { GetDeviceCaps(Some(hdc), VREFRESH) };
+    unsafe { ReleaseDC(Some(hwnd), hdc) };
+    // 0 and 1 stand for the hardware's default rate
+    Duration::from_secs_f64(1.0 / if rate > 1 { rate as f64 } else { 60.0 })
+}
+
+// Hand out the window to graphics libraries, which create their surfaces from its handle, and let it be read
+// back
+fn set_native_handle(native_handle: &NativeHandle, hwnd: HWND) {
+    native_handle.set_available();
+    native_handle.set_reader(move || capture_window(hwnd));
+    #[cfg(feature = "raw-window-handle")]
+    {
+        let mut handle = Win32WindowHandle::new(NonZeroIsize::new(hwnd.0 as isize).unwrap());
+        handle.hinstance = NonZeroIsize::new(unsafe { GetWindowLongPtrW(hwnd, GWLP_HINSTANCE) });
+        native_handle.set(handle.into(), WindowsDisplayHandle::new().into());
+    }
+}
+
+// Make the window translucent below an opacity of 1. Only then is it layered, as some graphics libraries
+// can't present to layered windows
+fn set_opacity(hwnd: HWND, opacity: f32) {
+    let style = unsafe { GetWindowLongPtrW(hwnd, GWL_EXSTYLE) };
+    let layered = WS_EX_LAYERED.0 as isize;
+    if opacity < 1.0 {
+        if style & layered == 0 {
+            unsafe { SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | layered) };
+        }
+        let alpha = (opacity.max(0.0) * 255.0).round() as u8;
+        let _ = unsafe { SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA) };
+    } else if style & layered != 0 {
+        unsafe { SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style & !layered) };
+    }
+}
+
+// Enable or disable the input method, and move its windows next to the caret
+fn update_ime(hwnd: HWND, enabled: bool, cursor_area: Rect) {
+    unsafe {
+        // Detaching the input context is how a window opts out of IME, e.g. for password fields
+        let _ = ImmAssociateContextEx(
+            hwnd,
+            HIMC::default(),
+            if enabled { IACE_DEFAULT } else { 0 },
+        );
+        if !enabled {
+            return;
+        }
+
+        let himc = ImmGetContext(hwnd);
+        if himc.is_invalid() {
+            return;
+        }
+        let position = POINT {
+            x: cursor_area.x,
+            y: cursor_area.y,
+        };
+        let composition = COMPOSITIONFORM {
+            dwStyle: CFS_POINT,
+            ptCurrentPos: position,
+            rcArea: RECT::default(),
+        };
+        let _ = ImmSetCompositionWindow(himc, &composition);
+        let candidate = CANDIDATEFORM {
+            dwIndex: 0,
+            dwStyle: CFS_EXCLUDE,
+            ptCurrentPos: position,
+            rcArea: RECT {
+                left: cursor_area.x,
+                top: cursor_area.y,
+                right: cursor_area.right(),
+                bottom: cursor_area.bottom(),
+            },
+        };
+        let _ = ImmSetCandidateWindow(himc, &candidate);
+        let _ = ImmReleaseContext(hwnd, himc);
+    }
+}
+
+// Mouse buttons held, as reported in the `wParam` of mouse messages: `MK_LBUTTON`, `MK_RBUTTON`,
+// `MK_MBUTTON`, `MK_XBUTTON1` and `MK_XBUTTON2`
+const MOUSE_BUTTONS: usize = 0x0001 | 0x0002 | 0x0010 | 0x0020 | 0x0040;
+
+/// Whether the system is in light or dark mode, as chosen for apps in the personalization settings.
+pub fn system_color_scheme() -> ColorScheme {
+    let mut light = 1u32;
+    let mut size = size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut light as *mut u32 as *mut c_void),
+            Some(&mut size),
+        )
+    };
+    // Systems from before dark mode have no such value
+    if result.is_ok() && light == 0 {
+        ColorScheme::Dark
+    } else {
+        ColorScheme::Light
+    }
+}
+
+// Physical pixels per logical unit
+fn scale_factor(hwnd: HWND) -> f64 {
+    match unsafe { GetDpiForWindow(hwnd) } {
+        0 => 1.0,
+        dpi => dpi as f64 / 96.0,
+    }
+}
+
+// Mouse messages synthesized from touch and pen input, which already arrive as `WM_POINTER*`
+fn is_promoted_mouse_message() -> bool {
+    let extra = unsafe { GetMessageExtraInfo() }.0 as u32;
+    extra & 0xFFFFFF00 == 0xFF515700 // MI_WP_SIGNATURE
+}
+
+fn count_clicks(state: &WindowState, button: PointerButton, x: f64, y: f64) -> u32 {
+    let interval = Duration::from_millis(unsafe { GetDoubleClickTime() } as u64);
+    let distance = unsafe { GetSystemMetrics(SM_CXDOUBLECLK) } as f64 / 2.0;
+    state
+        .clicks
+        .borrow_mut()
+        .press(button, x, y, interval, distance)
+}
+
+fn handle_mouse(state: &WindowState, hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) {
+    let scale = scale_factor(hwnd);
+    let x = (lparam.0 & 0xFFFF) as i16 as f64 / scale;
+    let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as f64 / scale;
+    let held = wparam.0 & MOUSE_BUTTONS != 0;
+    let mut event = PointerEvent {
+        pointer_id: 0,
+        pointer_type: PointerType::Mouse,
+        x,
+        y,
+        button: None,
+        click_count: 0,
+        modifiers: current_modifiers(),
+        pressure: if held { 0.5 } else { 0.0 },
+        tilt_x: 0.0,
+        tilt_y: 0.0,
+    };
+
+    if !state.mouse_inside.replace(true) {
+        // Ask for `WM_MOUSELEAVE` once the cursor is gone
+        let mut track = TRACKMOUSEEVENT {
+            cbSize: size_of::<TRACKMOUSEEVENT>() as u32,
+            dwFlags: TME_LEAVE,
+            hwndTrack: hwnd,
+            dwHoverTime: 0,
+        };
+        let _ = unsafe { TrackMouseEvent(&mut track) };
+        state.push(WindowEvent::PointerEnter(event.clone()));
+    }
+
+    let xbutton = || match (wparam.0 >> 16) & 0xFFFF {
+        1 => PointerButton::Back, // XBUTTON1
+        _ => PointerButton::Forward,
+    };
+    match msg {
+        WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN => {
+            let button = match msg {
+                WM_LBUTTONDOWN => PointerButton::Primary,
+                WM_RBUTTONDOWN => PointerButton::Secondary,
+                WM_MBUTTONDOWN => PointerButton::Middle,
+                _ => xbutton(),
+            };
+            // Keep receiving mouse messages while dragging outside the window
+            unsafe { SetCapture(hwnd) };
+            event.button = Some(button);
+            event.click_count = count_clicks(state, button, x, y);
+            state.push(WindowEvent::PointerDown(event));
+        }
+        WM_LBUTTONUP | WM_RBUTTONUP | WM_MBUTTONUP | WM_XBUTTONUP => {
+            event.button = Some(match msg {
+                WM_LBUTTONUP => PointerButton::Primary,
+                WM_RBUTTONUP => PointerButton::Secondary,
+                WM_MBUTTONUP => PointerButton::Middle,
+                _ => xbutton(),
+            });
+            event.click_count = state.clicks.borrow().current();
+            if !held {
+                let _ = unsafe { ReleaseCapture() };
+            }
+            state.push(WindowEvent::PointerUp(event));
+        }
+        _ => state.push(WindowEvent::PointerMove(event)),
+    }
+}
+
+// Logical pixels a line scrolls by when precise wheel messages are turned into pixels, as browsers do
+const PIXELS_PER_LINE: f64 = 100.0 / 3.0;
+// `SPI_GETWHEELSCROLLLINES` with scrolling by pages
+const WHEEL_PAGESCROLL: u32 = u32::MAX;
+
+// Wheel and touchpad scrolling, `WM_MOUSEWHEEL` positions are in screen coordinates
+fn handle_wheel(state: &WindowState, hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) {
+    let mut point = POINT {
+        x: (lparam.0 & 0xFFFF) as i16 as i32,
+        y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+    };
+    let _ = unsafe { ScreenToClient(hwnd, &mut point) };
+    let scale = scale_factor(hwnd);
+    // Multiples of `WHEEL_DELTA` per notch. Precision touchpads and free-spinning wheels send smaller steps,
+    // which scroll by pixels for the rest of the gesture
+    let units = ((wparam.0 >> 16) & 0xFFFF) as i16 as i32;
+    let mut phases = state.scroll.borrow_mut();
+    let precise = units % WHEEL_DELTA as i32 != 0 || phases.is_precise();
+    let steps = units as f64 / WHEEL_DELTA as f64;
+    let (x, y) = match msg {
+        // Rotating the wheel away from the user scrolls up
+        WM_MOUSEWHEEL => (0.0, -steps),
+        _ => (steps, 0.0),
+    };
+    let delta = if precise {
+        let pixels = wheel_scroll_lines(msg) * PIXELS_PER_LINE;
+        ScrollDelta::Pixels {
+            x: x * pixels,
+            y: y * pixels,
+        }
+    } else {
+        ScrollDelta::Lines { x, y }
+    };
+    // Win32 reports no gestures, even for precision touchpads, so a pause in the messages ends one
+    let event = phases.scroll(
+        ScrollEvent {
+            x: point.x as f64 / scale,
+            y: point.y as f64 / scale,
+            delta,
+            phase: ScrollPhase::Changed,
+            modifiers: current_modifiers(),
+        },
+        Instant::now(),
+    );
+    drop(phases);
+    state.push(WindowEvent::Scroll(event));
+}
+
+// The lines or, for `WM_MOUSEHWHEEL`, the characters a notch scrolls by, as set in the mouse settings
+fn wheel_scroll_lines(msg: u32) -> f64 {
+    let action = match msg {
+        WM_MOUSEWHEEL => SPI_GETWHEELSCROLLLINES,
+        _ => SPI_GETWHEELSCROLLCHARS,
+    };
+    let mut lines = 0u32;
+    let got = unsafe {
+        SystemParametersInfoW(
+            action,
+            0,
+            Some((&raw mut lines).cast()),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    // `WHEEL_PAGESCROLL` scrolls by pages, which the window doesn't know of
+    match got {
+        Ok(()) if lines != 0 && lines != WHEEL_PAGESCROLL => lines as f64,
+        _ => 3.0,
+    }
+}
+
+// Translate `WM_POINTER*` messages of touch and pen input, mouse input keeps using the classic messages
+fn handle_pointer(state: &WindowState, hwnd: HWND, msg: u32, wparam: WPARAM) -> bool {
+    let pointer_id = (wparam.0 & 0xFFFF) as u32;
+    let mut kind = POINTER_INPUT_TYPE::default();
+    if unsafe { GetPointerType(pointer_id, &mut kind) }.is_err() {
+        return false;
+    }
+
+    let (info, pointer_type, pressure, tilt_x, tilt_y, button) = if kind == PT_TOUCH {
+        let mut touch = POINTER_TOUCH_INFO::default();
+        if unsafe { GetPointerTouchInfo(pointer_id, &mut touch) }.is_err() {
+            return false;
+        }
+        let pressure = if touch.touchMask & TOUCH_MASK_PRESSURE != 0 {
+            touch.pressure as f32 / 1024.0
+        } else {
+            0.5
+        };
+        let info = touch.pointerInfo;
+        (
+            info,
+            PointerType::Touch,
+            pressure,
+            0.0,
+            0.0,
+            PointerButton::Primary,
+        )
+    } else if kind == PT_PEN {
+        let mut pen = POINTER_PEN_INFO::default();
+        if unsafe { GetPointerPenInfo(pointer_id, &mut pen) }.is_err() {
+            return false;
+        }
+        let pressure = if pen.penMask & PEN_MASK_PRESSURE != 0 {
+            pen.pressure as f32 / 1024.0
+        } else {
+            0.5
+        };
+        let tilt_x = if pen.penMask & PEN_MASK_TILT_X != 0 {
+            pen.tiltX as f32
+        } else {
+            0.0
+        };
+        let tilt_y = if pen.penMask & PEN_MASK_TILT_Y != 0 {
+            pen.tiltY as f32
+        } else {
+            0.0
+        };
+        let button = if pen.penFlags & PEN_FLAG_ERASER != 0 {
+            PointerButton::Eraser
+        } else if pen.penFlags & PEN_FLAG_BARREL != 0 {
+            PointerButton::Secondary
+        } else {
+            PointerButton::Primary
+        };
+        (
+            pen.pointerInfo,
+            PointerType::Pen,
+            pressure,
+            tilt_x,
+            tilt_y,
+            button,
+        )
+    } else {
+        return false;
+    };
+
+    let POINTER_INFO {
+        mut ptPixelLocation,
+        pointerFlags,
+        ..
+    } = info;
+    let _ = unsafe { ScreenToClient(hwnd, &mut ptPixelLocation) };
+    let scale = scale_factor(hwnd);
+    let x = ptPixelLocation.x as f64 / scale;
+    let y = ptPixelLocation.y as f64 / scale;
+    let mut event = PointerEvent {
+        pointer_id,
+        pointer_type,
+        x,
+        y,
+        button: None,
+        click_count: 0,
+        modifiers: current_modifiers(),
+        pressure: if msg == WM_POINTERUP { 0.0 } else { pressure },
+        tilt_x,
+        tilt_y,
+    };
+
+    state.push(match msg {
+        WM_POINTERENTER => WindowEvent::PointerEnter(event),
+        WM_POINTERLEAVE => WindowEvent::PointerLeave(event),
+        WM_POINTERDOWN => {
+            event.button = Some(button);
+            event.click_count = count_clicks(state, button, x, y);
+            WindowEvent::PointerDown(event)
+        }
+        WM_POINTERUP if pointerFlags.contains(POINTER_FLAG_CANCELED) => {
+            // The system took the pointer away, so whatever it was doing must not complete as a gesture
+            event.button = Some(button);
+            state.gestures.borrow_mut().cancel(&event);
+            state.input.push(WindowEvent::PointerUp(event));
+            return true;
+        }
+        WM_POINTERUP => {
+            event.button = Some(button);
+            event.click_count = state.clicks.borrow().current();
+            WindowEvent::PointerUp(event)
+        }
+        _ => WindowEvent::PointerMove(event),
+    });
+    true
+}
 
 // Window procedure callback function
 unsafe extern "system" fn window_proc(
@@ -28,17 +899,171 @@ unsafe extern "system" fn window_proc(
     wparam: WPARAM,
     lparam: LPARAM,
 ) -> LRESULT {
+    let state = unsafe { (GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const WindowState).as_ref() };
+
     match msg {
         WM_CREATE => {
             let create_struct = lparam.0 as *const CREATESTRUCTW;
-            let window_ptr = unsafe { (*create_struct).lpCreateParams };
+            let window_ptr = unsafe { (*create_struct).lpCreateParams } as *const WindowState;
+            // The window holds its own reference until `WM_NCDESTROY`, so the state outlives a component
+            // dropped while the window is still around
+            unsafe { Rc::increment_strong_count(window_ptr) };
             unsafe { SetWindowLongPtrW(hwnd, GWLP_USERDATA, window_ptr as isize) };
             LRESULT::default()
         }
+        WM_NCDESTROY => {
+            let window_ptr =
+                unsafe { SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0) } as *const WindowState;
+            if !window_ptr.is_null() {
+                drop(unsafe { Rc::from_raw(window_ptr) });
+            }
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
         WM_DESTROY => {
+            // Also when closed through `WM_CLOSE`, where the component doesn't destroy the window itself
+            ole::revoke(hwnd);
             unsafe { PostQuitMessage(0) };
             LRESULT::default()
         }
+        WM_CLOSE => {
+            if let Some(state) = state {
+                state.push(WindowEvent::CloseRequested);
+            }
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        WM_PAINT => match state.and_then(|state| state.surface.borrow().clone()) {
+            Some(surface) => {
+                paint(hwnd, &surface);
+                LRESULT::default()
+            }
+            None => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+        },
+        WM_PRINTCLIENT => match state.and_then(|state| state.surface.borrow().clone()) {
+            Some(surface) => {
+                print_client(HDC(wparam.0 as _), &surface);
+                LRESULT::default()
+            }
+            None => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+        },
+        WM_SIZE => {
+            if let Some(state) = state {
+                state.push(WindowEvent::Resized {
+                    width: (lparam.0 & 0xFFFF) as i32,
+                    height: ((lparam.0 >> 16) & 0xFFFF) as i32,
+                });
+            }
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        WM_SETFOCUS | WM_KILLFOCUS => {
+            if let Some(state) = state {
+                state.push(WindowEvent::Focused(msg == WM_SETFOCUS));
+            }
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        WM_KEYDOWN | WM_SYSKEYDOWN | WM_KEYUP | WM_SYSKEYUP => {
+            if let Some(state) = state {
+                let pressed = msg == WM_KEYDOWN || msg == WM_SYSKEYDOWN;
+                let event = key_event(wparam, lparam, pressed);
+                state.push(if pressed {
+                    WindowEvent::KeyDown(event)
+                } else {
+                    WindowEvent::KeyUp(event)
+                });
+            }
+            // Still let the system handle menu activation and Alt+F4
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        WM_CHAR => {
+            if let Some(state) = state {
+                state.push_char(wparam.0 as u16);
+            }
+            LRESULT::default()
+        }
+        WM_IME_COMPOSITION => {
+            let mut lparam = lparam;
+            if let Some(state) = state {
+                let flags = IME_COMPOSITION_STRING(lparam.0 as u32);
+                let himc = unsafe { ImmGetContext(hwnd) };
+                if flags.contains(GCS_RESULTSTR) {
+                    let result = composition_text(himc, GCS_RESULTSTR);
+                    state.end_composition();
+                    state.push(WindowEvent::TextInput(TextInputEvent::Commit(
+                        String::from_utf16_lossy(&result),
+                    )));
+                    // The result is delivered here, so the default procedure must not repeat it as
+                    // `WM_IME_CHAR`
+                    lparam.0 &= !(GCS_RESULTSTR.0 as isize);
+                }
+                if flags.contains(GCS_COMPSTR) {
+                    let event = preedit(himc);
+                    if matches!(&event, TextInputEvent::Preedit { text, .. } if !text.is_empty()) {
+                        state.composing.set(true);
+                        state.push(WindowEvent::TextInput(event));
+                    } else {
+                        state.end_composition();
+                    }
+                }
+                let _ = unsafe { ImmReleaseContext(hwnd, himc) };
+            }
+            // The default procedure keeps the system composition window up to date
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        WM_MOUSEMOVE | WM_LBUTTONDOWN | WM_LBUTTONUP | WM_RBUTTONDOWN | WM_RBUTTONUP
+        | WM_MBUTTONDOWN | WM_MBUTTONUP | WM_XBUTTONDOWN | WM_XBUTTONUP => {
+            if let Some(state) = state
+                && !is_promoted_mouse_message()
+            {
+                handle_mouse(state, hwnd, msg, wparam, lparam);
+            }
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+            if let Some(state) = state {
+                handle_wheel(state, hwnd, msg, wparam, lparam);
+            }
+            LRESULT::default()
+        }
+        WM_MOUSELEAVE => {
+            if let Some(state) = state {
+                state.mouse_inside.set(false);
+                state.push(WindowEvent::PointerLeave(PointerEvent {
+                    pointer_id: 0,
+                    pointer_type: PointerType::Mouse,
+                    x: f64::NAN,
+                    y: f64::NAN,
+                    button: None,
+                    click_count: 0,
+                    modifiers: current_modifiers(),
+                    pressure: 0.0,
+                    tilt_x: 0.0,
+                    tilt_y: 0.0,
+                }));
+            }
+            LRESULT::default()
+        }
+        WM_POINTERENTER | WM_POINTERLEAVE | WM_POINTERDOWN | WM_POINTERUP | WM_POINTERUPDATE => {
+            match state {
+                Some(state) if handle_pointer(state, hwnd, msg, wparam) => LRESULT::default(),
+                _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+            }
+        }
+        WM_IME_ENDCOMPOSITION => {
+            if let Some(state) = state {
+                state.end_composition();
+            }
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        // Switching between light and dark mode changes the "ImmersiveColorSet"
+        WM_SETTINGCHANGE => {
+            if let Some(state) = state
+                && lparam.0 != 0
+                && unsafe { PCWSTR(lparam.0 as *const u16).to_string() }
+                    .is_ok_and(|area| area == "ImmersiveColorSet")
+            {
+                state.push(WindowEvent::ColorSchemeChanged(system_color_scheme()));
+            }
+            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
         _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
     }
 }
@@ -53,10 +1078,70 @@ pub async fn window(
     #[default = CW_USEDEFAULT] top: i32,
     #[default = true] visible: bool,
     #[default = true] enabled: bool,
+    #[default = 1.0] opacity: f32,
+    #[default = true] ime_enabled: bool,
+    ime_cursor_area: Rect,
+    #[event] on_key_down: KeyEvent,
+    #[event] on_key_up: KeyEvent,
+    #[event] on_text_input: TextInputEvent,
+    #[event] on_pointer_enter: PointerEvent,
+    #[event] on_pointer_leave: PointerEvent,
+    #[event] on_pointer_move: PointerEvent,
+    #[event] on_pointer_down: PointerEvent,
+    #[event] on_pointer_up: PointerEvent,
+    #[event] on_scroll: ScrollEvent,
+    gesture_config: GestureConfig,
+    #[event] on_gesture: GestureEvent,
+    #[event] on_drag: DragEvent,
+    keymap: Option<&Keymap>,
+    #[event] on_action: String,
+    injector: Option<&InputInjector>,
+    recorder: Option<&Recorder>,
+    native_handle: Option<&NativeHandle>,
+    surface: Option<&SoftwareSurface>,
+    frame_clock: Option<&FrameClock>,
+    #[event] on_redraw: FrameTime,
+    theme: Option<&Theme>,
+    context: Option<&WindowContext>,
+    events: Option<&WindowEvents>,
 ) {
     #[field]
     // This is a field of the component's internal structure, not a variable in the current scope, so it can persist across multiple renders
     let hwnd: Option<HWND> = None;
+    #[field]
+    // Filled by the window procedure, its address is handed over through `lpCreateParams`
+    let state: Rc<WindowState> = Rc::new(WindowState::default());
+    #[field]
+    // Bumped on every render, so only the latest render forwards input
+    let render_id: u64 = 0;
+
+    *render_id += 1;
+    let current_render = *render_id;
+    // Keep the stream itself, the borrowed prop only lives until the next render
+    let events = events.cloned();
+    let keymap = keymap.cloned();
+    let recorder = recorder.cloned();
+    // Whatever the props leave out comes from the context
+    let native_handle = native_handle
+        .or(context.map(WindowContext::native_handle))
+        .cloned();
+    let surface = surface.or(context.map(WindowContext::surface));
+    let frame_clock = frame_clock
+        .or(context.map(WindowContext::frame_clock))
+        .cloned();
+    let context = context.cloned();
+    if let Some(recorder) = &recorder {
+        recorder.record_props(WindowProps {
+            title: title.to_string(),
+            width: *width,
+            height: *height,
+            left: *left,
+            top: *top,
+            visible: *visible,
+            enabled: *enabled,
+            opacity: *opacity,
+        });
+    }
 
     if *visible {
         if hwnd.is_none() {
@@ -102,7 +1187,7 @@ pub async fn window(
                     None,
                     None,
                     Some(h_instance),
-                    None,
+                    Some(Rc::as_ptr(state) as *const c_void),
                 ) {
                     Ok(h) => h,
                     Err(e) => {
@@ -111,6 +1196,8 @@ pub async fn window(
                     }
                 };
 
+                // Accept drops from other applications
+                ole::register(hwnd_value);
                 hwnd.replace(hwnd_value);
             }
         }
@@ -140,6 +1227,65 @@ pub async fn window(
             // Update window enabled state (supports reactive updates)
             let _ = unsafe { EnableWindow(*hwnd, *enabled) };
 
+            // Update window opacity (supports reactive updates)
+            set_opacity(*hwnd, *opacity);
+
+            // Update input method state and candidate window position (supports reactive updates). The
+            // focused layer of the context, e.g. a text field, may ask for its own
+            let (ime_hwnd, ime_enabled, ime_cursor_area) = (*hwnd, *ime_enabled, *ime_cursor_area);
+            let apply_ime = move |ime: Option<ImeRequest>| match ime {
+                Some(ime) => update_ime(ime_hwnd, ime_enabled && ime.enabled, ime.cursor_area),
+                None => update_ime(ime_hwnd, ime_enabled, ime_cursor_area),
+            };
+            apply_ime(context.as_ref().and_then(WindowContext::ime));
+            if let Some(context) = &context {
+                context.set_ime_handler(apply_ime);
+            }
+
+            state.gestures.borrow_mut().set_config(*gesture_config);
+
+            // Every render repaints, the props the draw callback reads may have changed
+            state.surface.replace(surface.cloned());
+            if surface.is_some() {
+                let _ = unsafe { InvalidateRect(Some(*hwnd), None, false) };
+            }
+
+            if let Some(frame_clock) = &frame_clock {
+                frame_clock.set_interval(refresh_interval(*hwnd));
+            }
+
+            if let Some(native_handle) = &native_handle {
+                set_native_handle(native_handle, *hwnd);
+            }
+
+            if let Some(context) = &context {
+                context.set_scale_factor(scale_factor(*hwnd));
+            }
+
+            // Synthetic input joins the window procedure's input, close requests take the platform's way. It
+            // doesn't pass the window procedure, so a window disabled with `EnableWindow` drops it here
+            if let Some(injector) = injector {
+                let target = Rc::downgrade(state);
+                let hwnd = *hwnd;
+                injector.attach(move |event, recognize| match event {
+                    WindowEvent::CloseRequested => {
+                        let _ = unsafe { PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+                    }
+                    event
+                        if !unsafe { IsWindowEnabled(hwnd) }.as_bool()
+                            && !reaches_disabled(&event) => {}
+                    event => {
+                        if let Some(state) = target.upgrade() {
+                            if recognize {
+                                state.push(event);
+                            } else {
+                                state.input.push(event);
+                            }
+                        }
+                    }
+                });
+            }
+
             // Get client area size
             let mut rect = RECT {
                 left: 0,
@@ -148,6 +1294,12 @@ pub async fn window(
                 bottom: 0,
             };
             let _ = unsafe { GetClientRect(*hwnd, &mut rect) };
+            if let Some(native_handle) = &native_handle {
+                native_handle.set_size(
+                    (rect.right - rect.left) as u32,
+                    (rect.bottom - rect.top) as u32,
+                );
+            }
 
             info!(
                 "Window updated with client area: {}x{}",
@@ -159,6 +1311,192 @@ pub async fn window(
         }
     } else if let Some(hwnd) = hwnd.take() {
         // If shown is false and window exists, destroy the window
+        ole::revoke(hwnd);
         let _ = unsafe { DestroyWindow(hwnd) };
+        state.input.clear();
+        state.gestures.borrow_mut().reset();
+        state.scroll.borrow_mut().reset();
+        if let Some(injector) = injector {
+            injector.detach();
+        }
+        if let Some(native_handle) = &native_handle {
+            native_handle.clear();
+        }
+    }
+
+    if let Some(events) = &events {
+        events.set_closed(hwnd.is_none());
+    }
+    if let Some(context) = &context {
+        context.set_closed(hwnd.is_none());
+        context.set_enabled(*enabled);
+        context.set_theme(*theme);
+    }
+    if let Some(frame_clock) = &frame_clock {
+        frame_clock.set_visible(hwnd.is_some());
+    }
+
+    let props = EventProps {
+        on_key_down,
+        on_key_up,
+        on_text_input,
+        on_pointer_enter,
+        on_pointer_leave,
+        on_pointer_move,
+        on_pointer_down,
+        on_pointer_up,
+        on_scroll,
+        on_gesture,
+        on_drag,
+        on_action,
+        keymap: keymap.as_ref(),
+        events: events.as_ref(),
+        context: context.as_ref(),
+        recorder: recorder.as_ref(),
+    };
+    let mut dispatcher = Dispatcher::default();
+
+    // Forward input to the event props until the next render replaces this one, or the window is closed
+    let window = hwnd;
+    while let Some(hwnd) = *window {
+        let deadline = || {
+            let input = [
+                state.gestures.borrow().deadline(),
+                state.scroll.borrow().deadline(),
+            ];
+            earliest(input.into_iter().flatten().min(), frame_clock.as_ref())
+        };
+        let next = match state
+            .input
+            .next(|| *render_id == current_render, deadline)
+            .await
+        {
+            Some(Wake::Input(next)) => next,
+            Some(Wake::Deadline) => {
+                let now = Instant::now();
+                // A long press fires while the finger rests, without any input to carry it
+                if let Some(gesture) = state.gestures.borrow_mut().tick(now) {
+                    state.push(WindowEvent::Gesture(gesture));
+                }
+                // So does the end of a scroll, once the wheel messages stop
+                let scroll = state.scroll.borrow_mut().tick(now);
+                if let Some(scroll) = scroll {
+                    state.push(WindowEvent::Scroll(scroll));
+                }
+                if let Some((frame, true)) = frame_clock.as_ref().and_then(|c| c.tick(now)) {
+                    let surface = state.surface.borrow().clone();
+                    match surface {
+                        Some(surface) => redraw(hwnd, &surface),
+                        None => {
+                            let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        }
+                    }
+                    let _ = on_redraw.emit(frame);
+                }
+                continue;
+            }
+            None => break,
+        };
+        match dispatcher.dispatch(next, &props) {
+            Some(WindowEvent::Action(action)) => match action.as_str() {
+                Keymap::CLOSE_WINDOW => {
+                    let _ = unsafe { PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+                }
+                Keymap::QUIT => unsafe { PostQuitMessage(0) },
+                _ => {}
+            },
+            Some(WindowEvent::CloseRequested) => {
+                // `DefWindowProcW` destroyed the window while handling `WM_CLOSE`, the next render with
+                // `visible` creates a new one
+                window.take();
+                state.input.clear();
+                state.gestures.borrow_mut().reset();
+                state.scroll.borrow_mut().reset();
+                if let Some(injector) = injector {
+                    injector.detach();
+                }
+                if let Some(native_handle) = &native_handle {
+                    native_handle.clear();
+                }
+                if let Some(frame_clock) = &frame_clock {
+                    frame_clock.set_visible(false);
+                }
+                if let Some(events) = &events {
+                    events.set_closed(true);
+                }
+                if let Some(context) = &context {
+                    context.set_closed(true);
+                }
+            }
+            // The window may have moved to a display of another scale
+            Some(WindowEvent::Resized { width, height }) => {
+                if let Some(native_handle) = &native_handle {
+                    native_handle.set_size(width.max(0) as u32, height.max(0) as u32);
+                }
+                if let Some(context) = &context {
+                    context.set_scale_factor(scale_factor(hwnd));
+                }
+            }
+            _ => {}
+        }
+        yield_now().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOWN: u8 = 0x80;
+
+    // A US layout for one key, where Control turns letters into control characters and drops everything else
+    fn us_layout(c: char) -> impl Fn(&[u8; 256]) -> (Vec<u16>, bool) {
+        move |state| {
+            let produced = if state[VK_CONTROL.0 as usize] & DOWN == 0 {
+                c.to_string()
+            } else if c.is_ascii_lowercase() {
+                char::from(c as u8 - b'a' + 1).to_string()
+            } else {
+                String::new()
+            };
+            (produced.encode_utf16().collect(), false)
+        }
+    }
+
+    fn with_control() -> [u8; 256] {
+        let mut state = [0u8; 256];
+        state[VK_CONTROL.0 as usize] = DOWN;
+        state[VK_LCONTROL.0 as usize] = DOWN;
+        state
+    }
+
+    #[test]
+    fn plain_key_produces_text() {
+        let key = translated_key([0; 256], true, us_layout('a'));
+        assert_eq!(key, (Key::Character('a'), Some("a".to_owned())));
+        assert_eq!(
+            translated_key([0; 256], false, us_layout('a')),
+            (Key::Character('a'), None)
+        );
+    }
+
+    #[test]
+    fn control_letter_reports_the_letter() {
+        let key = translated_key(with_control(), true, us_layout('c'));
+        assert_eq!(key, (Key::Character('c'), None));
+    }
+
+    #[test]
+    fn control_punctuation_and_digits_report_the_key() {
+        for c in ['/', '=', '1', '+'] {
+            let key = translated_key(with_control(), true, us_layout(c));
+            assert_eq!(key, (Key::Character(c), None));
+        }
+    }
+
+    #[test]
+    fn dead_key() {
+        let key = translated_key([0; 256], true, |_| (vec!['^' as u16], true));
+        assert_eq!(key, (Key::Dead(Some('^')), None));
     }
 }
@@ -0,0 +1,449 @@
+use {
+    super::{WindowState, scale_factor},
+    crate::{
+        drag_drop::{DragData, DragEvent, DragFormats, DropEffect, DropResponse},
+        events::WindowEvent,
+    },
+    std::{
+        cell::RefCell,
+        ffi::c_void,
+        mem::ManuallyDrop,
+        path::PathBuf,
+        ptr::{copy_nonoverlapping, null_mut},
+    },
+    windows::{
+        Win32::{
+            Foundation::{
+                DRAGDROP_S_CANCEL, DRAGDROP_S_DROP, DRAGDROP_S_USEDEFAULTCURSORS, GlobalFree,
+                HGLOBAL, HWND, POINT, POINTL, S_OK,
+            },
+            Graphics::Gdi::ScreenToClient,
+            System::{
+                Com::{
+                    CoTaskMemFree, DATADIR_GET, DVASPECT_CONTENT, FORMATETC, IDataObject,
+                    STGMEDIUM, STGMEDIUM_0, TYMED_HGLOBAL,
+                },
+                DataExchange::{GetClipboardFormatNameW, RegisterClipboardFormatW},
+                Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock},
+                Ole::{
+                    CF_HDROP, CF_UNICODETEXT, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_LINK,
+                    DROPEFFECT_MOVE, DROPEFFECT_NONE, DoDragDrop, IDropSource, IDropSource_Impl,
+                    IDropTarget, IDropTarget_Impl, OleFlushClipboard, OleGetClipboard,
+                    OleInitialize, OleSetClipboard, RegisterDragDrop, ReleaseStgMedium,
+                    RevokeDragDrop,
+                },
+                SystemServices::{MK_LBUTTON, MK_MBUTTON, MK_RBUTTON, MODIFIERKEYS_FLAGS},
+            },
+            UI::{
+                Shell::{DROPFILES, DragQueryFileW, HDROP, SHCreateDataObject},
+                WindowsAndMessaging::{GWLP_USERDATA, GetWindowLongPtrW},
+            },
+        },
+        core::{BOOL, HRESULT, PCWSTR, Ref, Result, implement, w},
+    },
+};
+
+// Registered clipboard format of dragged links
+const URL_FORMAT: PCWSTR = w!("UniformResourceLocatorW");
+
+fn to_drop_effect(effect: DropEffect) -> DROPEFFECT {
+    match effect {
+        DropEffect::None => DROPEFFECT_NONE,
+        DropEffect::Copy => DROPEFFECT_COPY,
+        DropEffect::Move => DROPEFFECT_MOVE,
+        DropEffect::Link => DROPEFFECT_LINK,
+    }
+}
+
+fn from_drop_effect(effect: DROPEFFECT) -> DropEffect {
+    if effect.contains(DROPEFFECT_MOVE) {
+        DropEffect::Move
+    } else if effect.contains(DROPEFFECT_COPY) {
+        DropEffect::Copy
+    } else if effect.contains(DROPEFFECT_LINK) {
+        DropEffect::Link
+    } else {
+        DropEffect::None
+    }
+}
+
+fn utf16_until_nul(units: &[u16]) -> String {
+    let len = units.iter().position(|&u| u == 0).unwrap_or(units.len());
+    String::from_utf16_lossy(&units[..len])
+}
+
+fn global_bytes(global: HGLOBAL) -> Vec<u8> {
+    let size = unsafe { GlobalSize(global) };
+    let ptr = unsafe { GlobalLock(global) } as *const u8;
+    if ptr.is_null() {
+        return Vec::new();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, size) }.to_vec();
+    let _ = unsafe { GlobalUnlock(global) };
+    bytes
+}
+
+fn global_text(global: HGLOBAL) -> String {
+    let bytes = global_bytes(global);
+    let units = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect::<Vec<_>>();
+    utf16_until_nul(&units)
+}
+
+fn dropped_files(global: HGLOBAL) -> Vec<PathBuf> {
+    let hdrop = HDROP(global.0);
+    let count = unsafe { DragQueryFileW(hdrop, u32::MAX, None) };
+    (0..count)
+        .map(|i| {
+            let len = unsafe { DragQueryFileW(hdrop, i, None) } as usize;
+            let mut buffer = vec![0u16; len + 1];
+            unsafe { DragQueryFileW(hdrop, i, Some(&mut buffer)) };
+            PathBuf::from(utf16_until_nul(&buffer))
+        })
+        .collect()
+}
+
+// The formats the data object offers as content in global memory, without rendering any of them
+fn content_formats(object: &IDataObject) -> Vec<u16> {
+    let Ok(formats) = (unsafe { object.EnumFormatEtc(DATADIR_GET.0 as u32) }) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    loop {
+        let mut format = [FORMATETC::default()];
+        let mut fetched = 0;
+        if unsafe { formats.Next(&mut format, Some(&mut fetched)) } != S_OK || fetched == 0 {
+            break;
+        }
+        let [format] = format;
+        // The target device, if any, belongs to the caller
+        if !format.ptd.is_null() {
+            unsafe { CoTaskMemFree(Some(format.ptd as *const c_void)) };
+        }
+        if format.dwAspect == DVASPECT_CONTENT.0
+            && format.tymed & TYMED_HGLOBAL.0 as u32 != 0
+            && format.cfFormat != 0
+            && !found.contains(&format.cfFormat)
+        {
+            found.push(format.cfFormat);
+        }
+    }
+    found
+}
+
+// Registered formats have names, the remaining standard ones duplicate the files, text and links
+fn format_name(format: u16) -> Option<String> {
+    if format < 0xC000 {
+        return None;
+    }
+    let mut name = [0u16; 256];
+    let len = unsafe { GetClipboardFormatNameW(format as u32, &mut name) };
+    (len > 0).then(|| String::from_utf16_lossy(&name[..len as usize]))
+}
+
+fn read_formats(object: &IDataObject) -> DragFormats {
+    let url_format = unsafe { RegisterClipboardFormatW(URL_FORMAT) } as u16;
+    let mut offered = DragFormats::default();
+    for format in content_formats(object) {
+        match format {
+            f if f == CF_HDROP.0 => offered.files = true,
+            f if f == CF_UNICODETEXT.0 => offered.text = true,
+            f if f == url_format => offered.uris = true,
+            f => offered.formats.extend(format_name(f)),
+        }
+    }
+    offered
+}
+
+// Collect every format the data object renders into global memory
+fn read_data(object: &IDataObject) -> DragData {
+    let url_format = unsafe { RegisterClipboardFormatW(URL_FORMAT) } as u16;
+    let mut data = DragData::default();
+    for cf_format in content_formats(object) {
+        let format = FORMATETC {
+            cfFormat: cf_format,
+            ptd: null_mut(),
+            dwAspect: DVASPECT_CONTENT.0,
+            lindex: -1,
+            tymed: TYMED_HGLOBAL.0 as u32,
+        };
+        let Ok(mut medium) = (unsafe { object.GetData(&format) }) else {
+            continue;
+        };
+        if medium.tymed == TYMED_HGLOBAL.0 as u32 {
+            let global = unsafe { medium.u.hGlobal };
+            match cf_format {
+                f if f == CF_HDROP.0 => data.files = dropped_files(global),
+                f if f == CF_UNICODETEXT.0 => data.text = Some(global_text(global)),
+                f if f == url_format => data.uris.push(global_text(global)),
+                f => {
+                    if let Some(name) = format_name(f) {
+                        data.formats.push((name, global_bytes(global)));
+                    }
+                }
+            }
+        }
+        unsafe { ReleaseStgMedium(&mut medium) };
+    }
+    data
+}
+
+#[implement(IDropTarget)]
+struct DropTarget {
+    hwnd: HWND,
+    response: RefCell<DropResponse>,
+}
+
+impl DropTarget {
+    fn push(&self, event: DragEvent) {
+        let state =
+            unsafe { (GetWindowLongPtrW(self.hwnd, GWLP_USERDATA) as *const WindowState).as_ref() };
+        if let Some(state) = state {
+            state.push(WindowEvent::Drag(event));
+        }
+    }
+
+    fn client_position(&self, pt: &POINTL) -> (f64, f64) {
+        let mut point = POINT { x: pt.x, y: pt.y };
+        let _ = unsafe { ScreenToClient(self.hwnd, &mut point) };
+        let scale = scale_factor(self.hwnd);
+        (point.x as f64 / scale, point.y as f64 / scale)
+    }
+
+    // Answer with what the window accepted, as far as the source allows it
+    fn answer(&self, effect: *mut DROPEFFECT) -> DropEffect {
+        let accepted = to_drop_effect(self.response.borrow().effect());
+        let allowed = unsafe { *effect };
+        let answer = if allowed.contains(accepted) {
+            accepted
+        } else {
+            DROPEFFECT_NONE
+        };
+        unsafe { *effect = answer };
+        from_drop_effect(answer)
+    }
+}
+
+impl IDropTarget_Impl for DropTarget_Impl {
+    fn DragEnter(
+        &self,
+        object: Ref<IDataObject>,
+        _keys: MODIFIERKEYS_FLAGS,
+        pt: &POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        let response = DropResponse::default();
+        *self.response.borrow_mut() = response.clone();
+        // Only look at the formats while hovering, reading them makes delayed-rendering sources render it all
+        let offered = object.as_ref().map(read_formats).unwrap_or_default();
+        let (x, y) = self.client_position(pt);
+        self.answer(effect);
+        self.push(DragEvent::Enter {
+            x,
+            y,
+            offered,
+            response,
+        });
+        Ok(())
+    }
+
+    fn DragOver(
+        &self,
+        _keys: MODIFIERKEYS_FLAGS,
+        pt: &POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        let (x, y) = self.client_position(pt);
+        self.answer(effect);
+        let response = self.response.borrow().clone();
+        self.push(DragEvent::Move { x, y, response });
+        Ok(())
+    }
+
+    fn DragLeave(&self) -> Result<()> {
+        self.push(DragEvent::Leave);
+        Ok(())
+    }
+
+    fn Drop(
+        &self,
+        object: Ref<IDataObject>,
+        _keys: MODIFIERKEYS_FLAGS,
+        pt: &POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        let answer = self.answer(effect);
+        if answer == DropEffect::None {
+            self.push(DragEvent::Leave);
+            return Ok(());
+        }
+        let data = object.as_ref().map(read_data).unwrap_or_default();
+        let (x, y) = self.client_position(pt);
+        self.push(DragEvent::Drop {
+            x,
+            y,
+            data,
+            effect: answer,
+        });
+        Ok(())
+    }
+}
+
+// Make the window a drop target, OLE has to be initialized on the UI thread first
+pub(super) fn register(hwnd: HWND) {
+    let _ = unsafe { OleInitialize(None) };
+    let target: IDropTarget = DropTarget {
+        hwnd,
+        response: Default::default(),
+    }
+    .into();
+    let _ = unsafe { RegisterDragDrop(hwnd, &target) };
+}
+
+pub(super) fn revoke(hwnd: HWND) {
+    let _ = unsafe { RevokeDragDrop(hwnd) };
+}
+
+#[implement(IDropSource)]
+struct DropSource;
+
+impl IDropSource_Impl for DropSource_Impl {
+    fn QueryContinueDrag(&self, escape_pressed: BOOL, keys: MODIFIERKEYS_FLAGS) -> HRESULT {
+        let buttons = MK_LBUTTON.0 | MK_RBUTTON.0 | MK_MBUTTON.0;
+        if escape_pressed.as_bool() {
+            DRAGDROP_S_CANCEL
+        } else if keys.0 & buttons == 0 {
+            DRAGDROP_S_DROP
+        } else {
+            S_OK
+        }
+    }
+
+    fn GiveFeedback(&self, _effect: DROPEFFECT) -> HRESULT {
+        DRAGDROP_S_USEDEFAULTCURSORS
+    }
+}
+
+fn set_global(object: &IDataObject, format: u16, bytes: &[u8]) {
+    let Ok(global) = (unsafe { GlobalAlloc(GMEM_MOVEABLE, bytes.len()) }) else {
+        return;
+    };
+    let ptr = unsafe { GlobalLock(global) } as *mut u8;
+    if ptr.is_null() {
+        let _ = unsafe { GlobalFree(Some(global)) };
+        return;
+    }
+    unsafe { copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len()) };
+    let _ = unsafe { GlobalUnlock(global) };
+
+    let format = FORMATETC {
+        cfFormat: format,
+        ptd: null_mut(),
+        dwAspect: DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL.0 as u32,
+    };
+    let medium = STGMEDIUM {
+        tymed: TYMED_HGLOBAL.0 as u32,
+        u: STGMEDIUM_0 { hGlobal: global },
+        pUnkForRelease: ManuallyDrop::new(None),
+    };
+    // The data object takes ownership of the memory, unless it refuses it
+    if unsafe { object.SetData(&format, &medium, true) }.is_err() {
+        let _ = unsafe { GlobalFree(Some(global)) };
+    }
+}
+
+fn utf16_bytes(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+/// The text on the clipboard, if it holds any.
+pub fn clipboard_text() -> Option<String> {
+    let _ = unsafe { OleInitialize(None) };
+    let object = unsafe { OleGetClipboard() }.ok()?;
+    let format = FORMATETC {
+        cfFormat: CF_UNICODETEXT.0,
+        ptd: null_mut(),
+        dwAspect: DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL.0 as u32,
+    };
+    let mut medium = unsafe { object.GetData(&format) }.ok()?;
+    let text =
+        (medium.tymed == TYMED_HGLOBAL.0 as u32).then(|| global_text(unsafe { medium.u.hGlobal }));
+    unsafe { ReleaseStgMedium(&mut medium) };
+    text
+}
+
+/// Put `text` on the clipboard, in place of what it held.
+pub fn set_clipboard_text(text: &str) {
+    let _ = unsafe { OleInitialize(None) };
+    let Ok(object) = (unsafe { SHCreateDataObject::<_, IDataObject>(None, None, None) }) else {
+        return;
+    };
+    set_global(&object, CF_UNICODETEXT.0, &utf16_bytes(text));
+    if unsafe { OleSetClipboard(&object) }.is_ok() {
+        // Rendered into the clipboard itself, the text stays there after the application exits
+        let _ = unsafe { OleFlushClipboard() };
+    }
+}
+
+/// Start dragging `data` out of the application, e.g. from an `on_pointer_down` handler while the button is
+/// still held.
+///
+/// Blocks until the drag ends and returns what the drop target did with the data, `DropEffect::None` when the
+/// drag was cancelled or rejected.
+pub fn start_drag(data: &DragData) -> DropEffect {
+    let _ = unsafe { OleInitialize(None) };
+    let Ok(object) = (unsafe { SHCreateDataObject::<_, IDataObject>(None, None, None) }) else {
+        return DropEffect::None;
+    };
+
+    if !data.files.is_empty() {
+        // A `DROPFILES` header followed by the paths, each terminated by a nul, and a final nul
+        let mut bytes = vec![0u8; size_of::<DROPFILES>()];
+        let header = DROPFILES {
+            pFiles: size_of::<DROPFILES>() as u32,
+            fWide: true.into(),
+            ..Default::default()
+        };
+        unsafe {
+            copy_nonoverlapping(
+                &header as *const _ as *const u8,
+                bytes.as_mut_ptr(),
+                bytes.len(),
+            )
+        };
+        for file in &data.files {
+            bytes.extend(utf16_bytes(&file.to_string_lossy()));
+        }
+        bytes.extend([0, 0]);
+        set_global(&object, CF_HDROP.0, &bytes);
+    }
+    if let Some(text) = &data.text {
+        set_global(&object, CF_UNICODETEXT.0, &utf16_bytes(text));
+    }
+    if let Some(uri) = data.uris.first() {
+        let format = unsafe { RegisterClipboardFormatW(URL_FORMAT) } as u16;
+        set_global(&object, format, &utf16_bytes(uri));
+    }
+    for (name, bytes) in &data.formats {
+        let name = name.encode_utf16().chain([0]).collect::<Vec<_>>();
+        let format = unsafe { RegisterClipboardFormatW(PCWSTR(name.as_ptr())) } as u16;
+        set_global(&object, format, bytes);
+    }
+
+    let source: IDropSource = DropSource.into();
+    let mut effect = DROPEFFECT_NONE;
+    let allowed = DROPEFFECT_COPY | DROPEFFECT_MOVE | DROPEFFECT_LINK;
+    match unsafe { DoDragDrop(&object, &source, allowed, &mut effect) } {
+        DRAGDROP_S_DROP => from_drop_effect(effect),
+        _ => DropEffect::None,
+    }
+}
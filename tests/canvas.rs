@@ -0,0 +1,103 @@
+#![cfg(headless)]
+
+mod common;
+
+use {
+    common::*,
+    compo_window::{
+        prelude::*,
+        tiny_skia::{FillRule, Paint, PathBuilder, Rect as SkRect},
+    },
+};
+
+fn paint(r: u8, g: u8, b: u8) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(r, g, b, 255);
+    paint
+}
+
+fn draw(painter: &mut Painter) {
+    // A triangle in the top left corner
+    let mut path = PathBuilder::new();
+    path.move_to(10.0, 10.0);
+    path.line_to(40.0, 10.0);
+    path.line_to(10.0, 40.0);
+    path.close();
+    let path = path.finish().unwrap();
+    painter.fill_path(&path, &paint(255, 0, 0), FillRule::Winding);
+
+    // A rect cut down to its overlap with the clip
+    painter.save();
+    painter.clip_rect(SkRect::from_xywh(60.0, 10.0, 20.0, 20.0).unwrap());
+    painter.fill_rect(
+        SkRect::from_xywh(50.0, 0.0, 40.0, 40.0).unwrap(),
+        &paint(0, 0, 255),
+    );
+    painter.restore();
+
+    let style = TextStyle {
+        size: 24.0,
+        ..Default::default()
+    };
+    painter.draw_text(
+        "Hello",
+        &style,
+        SkRect::from_xywh(10.0, 50.0, 80.0, 40.0).unwrap(),
+    );
+}
+
+#[component]
+async fn app() {
+    #[field]
+    let context: WindowContext = WindowContext::default();
+    #[field]
+    let injector: InputInjector = InputInjector::default();
+    #[field]
+    let drawing: Drawing = Drawing::new(draw);
+
+    #[render]
+    test_window {
+        width: 100,
+        height: 100,
+        context: Some(context),
+        injector: Some(injector),
+    };
+    #[render]
+    canvas {
+        context: Some(context),
+        drawing: Some(drawing),
+    };
+
+    context.set_background(0xFFFFFF);
+    settle(context).await;
+    let capture = context.capture().unwrap();
+    assert_eq!((capture.width, capture.height), (100, 100));
+    let pixel = |x, y| capture.pixel(x, y).unwrap();
+
+    // Inside the triangle, and across its diagonal edge
+    assert_eq!(pixel(12, 12), RED);
+    assert_eq!(pixel(20, 15), RED);
+    assert_eq!(pixel(35, 35), WHITE);
+    assert_eq!(pixel(5, 5), WHITE);
+
+    // The rect only shows within the clip
+    assert_eq!(pixel(60, 10), BLUE);
+    assert_eq!(pixel(79, 29), BLUE);
+    assert_eq!(pixel(55, 5), WHITE);
+    assert_eq!(pixel(85, 20), WHITE);
+    assert_eq!(pixel(70, 35), WHITE);
+
+    // Dark glyphs within the text rect, and nothing of them outside it. Skipped without any fonts
+    if !Fonts::global().families().is_empty() {
+        let inked = |x: u32, y: u32| pixel(x, y)[0] < 128;
+        assert!((50..90).any(|y| (10..90).any(|x| inked(x, y))));
+        assert!(!(45..50).any(|y| (0..100).any(|x| inked(x, y))));
+        assert!(!(90..100).any(|y| (0..100).any(|x| inked(x, y))));
+    }
+    injector.request_close();
+}
+
+#[test]
+fn draws_paths_clips_and_text() {
+    run(app);
+}
@@ -0,0 +1,34 @@
+// Shared by the integration tests, which run on the headless backend, e.g. with `--features headless` on
+// Windows. Not every test uses all of it
+#![allow(dead_code)]
+
+use compo_window::prelude::*;
+
+pub const WHITE: [u8; 4] = [255, 255, 255, 255];
+pub const RED: [u8; 4] = [255, 0, 0, 255];
+pub const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+// A window of `width` x `height` showing the layers of `context`, tests close it through `injector`
+#[component]
+pub async fn test_window(
+    width: i32,
+    height: i32,
+    context: Option<&WindowContext>,
+    injector: Option<&InputInjector>,
+) {
+    #[render]
+    window {
+        width: *width,
+        height: *height,
+        context: *context,
+        injector: *injector,
+    };
+}
+
+// Let the window settle on its first frames, so layers added with it are drawn and the next frame only
+// repaints what changed
+pub async fn settle(context: &WindowContext) {
+    for _ in 0..3 {
+        context.frame_clock().next_frame().await;
+    }
+}
@@ -0,0 +1,125 @@
+#![cfg(headless)]
+
+mod common;
+
+use {
+    common::*,
+    compo_window::{prelude::*, tiny_skia::Color},
+};
+
+async fn next_damage(context: &WindowContext) -> Vec<Rect> {
+    context.frame_clock().next_frame().await;
+    context.surface().last_damage()
+}
+
+#[component]
+async fn redraw_layer() {
+    #[field]
+    let context: WindowContext = WindowContext::default();
+    #[field]
+    let injector: InputInjector = InputInjector::default();
+    #[field]
+    let drawing: Drawing = Drawing::new(|painter| painter.clear(Color::BLACK));
+
+    #[render]
+    test_window {
+        width: 200,
+        height: 100,
+        context: Some(context),
+        injector: Some(injector),
+    };
+    #[render]
+    canvas {
+        context: Some(context),
+        x: 10.0,
+        y: 20.0,
+        width: 30.0,
+        height: 40.0,
+        drawing: Some(drawing),
+    };
+
+    settle(context).await;
+    drawing.request_redraw();
+    assert_eq!(next_damage(context).await, [Rect::new(10, 20, 30, 40)]);
+    injector.request_close();
+}
+
+#[test]
+fn layer_redraw_damages_its_bounds() {
+    run(redraw_layer);
+}
+
+#[component]
+async fn move_layer() {
+    #[field]
+    let context: WindowContext = WindowContext::default();
+    #[field]
+    let injector: InputInjector = InputInjector::default();
+    let mut x = 10.0;
+
+    #[render]
+    test_window {
+        width: 200,
+        height: 100,
+        context: Some(context),
+        injector: Some(injector),
+    };
+    #[render]
+    canvas {
+        context: Some(context),
+        x: x,
+        y: 20.0,
+        width: 30.0,
+        height: 40.0,
+    };
+
+    settle(context).await;
+    // The old place shows the background again, the new one the layer
+    x = 100.0;
+    assert_eq!(
+        next_damage(context).await,
+        [Rect::new(10, 20, 30, 40), Rect::new(100, 20, 30, 40)]
+    );
+    injector.request_close();
+}
+
+#[test]
+fn moved_layer_damages_old_and_new_bounds() {
+    run(move_layer);
+}
+
+#[component]
+async fn merge_damage() {
+    #[field]
+    let context: WindowContext = WindowContext::default();
+    #[field]
+    let injector: InputInjector = InputInjector::default();
+
+    #[render]
+    test_window {
+        width: 200,
+        height: 100,
+        context: Some(context),
+        injector: Some(injector),
+    };
+
+    settle(context).await;
+    // Up to 16 areas are kept apart
+    for i in 0..16 {
+        context.damage(Rect::new(i * 10, 0, 5, 5));
+    }
+    let damage = next_damage(context).await;
+    assert_eq!(damage.len(), 16);
+    assert_eq!(damage[15], Rect::new(150, 0, 5, 5));
+    // One more merges them into their bounds
+    for i in 0..17 {
+        context.damage(Rect::new(i * 10, i, 5, 5));
+    }
+    assert_eq!(next_damage(context).await, [Rect::new(0, 0, 165, 21)]);
+    injector.request_close();
+}
+
+#[test]
+fn damage_beyond_limit_merges() {
+    run(merge_damage);
+}
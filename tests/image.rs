@@ -0,0 +1,136 @@
+#![cfg(headless)]
+
+mod common;
+
+use {
+    common::{BLUE as B, RED as R, WHITE as W, *},
+    compo_window::prelude::*,
+    std::cell::Cell,
+};
+
+// 4 x 2 pixels, the left half red and the right half blue
+const HALVES: &[u8] = include_bytes!("fixtures/halves.png");
+
+// Pixels of the window and their colors
+type Expected = &'static [((u32, u32), [u8; 4])];
+
+thread_local! {
+    static FIT: Cell<ImageFit> = const { Cell::new(ImageFit::Contain) };
+    static EXPECTED: Cell<Expected> = const { Cell::new(&[]) };
+}
+
+// Shows the image in the 40 x 40 area at (10, 10) of a white window, and compares the pixels with `EXPECTED`
+#[component]
+async fn app() {
+    #[field]
+    let context: WindowContext = WindowContext::default();
+    #[field]
+    let injector: InputInjector = InputInjector::default();
+    #[field]
+    let source: ImageSource = ImageSource::bytes(HALVES);
+
+    // Decoded up front, so the image shows with the next frames
+    let data = ImageData::Bytes(HALVES.into());
+    ImageCache::global().load(&data).await.unwrap();
+    context.set_background(0xFFFFFF);
+
+    #[render]
+    test_window {
+        width: 60,
+        height: 60,
+        context: Some(context),
+        injector: Some(injector),
+    };
+    #[render]
+    image {
+        context: Some(context),
+        source: Some(source),
+        x: 10.0,
+        y: 10.0,
+        width: 40.0,
+        height: 40.0,
+        fit: FIT.get(),
+        filter: ImageFilter::Nearest,
+    };
+
+    settle(context).await;
+    let capture = context.capture().unwrap();
+    for &((x, y), expected) in EXPECTED.get() {
+        assert_eq!(capture.pixel(x, y), Some(expected), "pixel at ({x}, {y})");
+    }
+    injector.request_close();
+}
+
+fn check(fit: ImageFit, expected: Expected) {
+    FIT.set(fit);
+    EXPECTED.set(expected);
+    run(app);
+}
+
+#[test]
+fn contain() {
+    // 40 x 20 at (10, 20)
+    check(
+        ImageFit::Contain,
+        &[
+            ((11, 21), R),
+            ((29, 38), R),
+            ((31, 21), B),
+            ((48, 38), B),
+            ((30, 19), W),
+            ((30, 40), W),
+            ((5, 30), W),
+        ],
+    );
+}
+
+#[test]
+fn cover() {
+    // 80 x 40 at (-10, 10), cut off to the area
+    check(
+        ImageFit::Cover,
+        &[
+            ((10, 10), R),
+            ((29, 49), R),
+            ((30, 10), B),
+            ((49, 49), B),
+            ((50, 30), W),
+            ((9, 30), W),
+            ((30, 50), W),
+        ],
+    );
+}
+
+#[test]
+fn fill() {
+    // 40 x 40 at (10, 10)
+    check(
+        ImageFit::Fill,
+        &[
+            ((10, 10), R),
+            ((29, 49), R),
+            ((30, 10), B),
+            ((49, 49), B),
+            ((50, 30), W),
+            ((30, 9), W),
+        ],
+    );
+}
+
+#[test]
+fn none() {
+    // 4 x 2 at (28, 29)
+    check(
+        ImageFit::None,
+        &[
+            ((28, 29), R),
+            ((29, 30), R),
+            ((30, 29), B),
+            ((31, 30), B),
+            ((27, 29), W),
+            ((32, 30), W),
+            ((28, 28), W),
+            ((28, 31), W),
+        ],
+    );
+}
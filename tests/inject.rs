@@ -0,0 +1,73 @@
+#![cfg(headless)]
+
+use compo_window::prelude::*;
+
+// The example of the README
+#[component]
+async fn app() {
+    #[field]
+    let injector: InputInjector = InputInjector::default();
+    #[field]
+    let events: WindowEvents = WindowEvents::default();
+
+    #[render]
+    window {
+        title: "Test",
+        injector: Some(injector),
+        events: Some(events),
+    };
+
+    injector.type_text("hi");
+    injector.request_close();
+    assert_eq!(
+        events.next().await,
+        Some(WindowEvent::TextInput(TextInputEvent::Commit(
+            "hi".to_owned()
+        )))
+    );
+}
+
+#[test]
+fn closes_after_typing() {
+    // Returns once the last window is closed
+    run(app);
+}
+
+#[component]
+async fn disabled() {
+    #[field]
+    let injector: InputInjector = InputInjector::default();
+    #[field]
+    let events: WindowEvents = WindowEvents::default();
+    let mut enabled = false;
+
+    #[render]
+    window {
+        enabled: enabled,
+        injector: Some(injector),
+        events: Some(events),
+    };
+
+    // A disabled window only hears about its focus, resizes and closing
+    injector.type_text("dropped");
+    injector.focus(true);
+    assert_eq!(events.next().await, Some(WindowEvent::Focused(true)));
+    enabled = true;
+    // The window is rendered again once the app waits
+    sleep(Duration::from_millis(10)).await;
+    injector.type_text("kept");
+    injector.request_close();
+    assert_eq!(
+        events.next().await,
+        Some(WindowEvent::TextInput(TextInputEvent::Commit(
+            "kept".to_owned()
+        )))
+    );
+    assert_eq!(events.next().await, Some(WindowEvent::CloseRequested));
+    assert_eq!(events.next().await, None);
+}
+
+#[test]
+fn disabled_window_drops_input() {
+    run(disabled);
+}
@@ -0,0 +1,77 @@
+#![cfg(headless)]
+
+mod common;
+
+use {
+    common::*,
+    compo_window::{
+        prelude::*,
+        taffy::{AlignItems, Style},
+    },
+};
+
+#[component]
+async fn app() {
+    #[field]
+    let context: WindowContext = WindowContext::default();
+    #[field]
+    let injector: InputInjector = InputInjector::default();
+    #[field]
+    let root: LayoutNode = LayoutNode::new(Style {
+        align_items: Some(AlignItems::Start),
+        ..Default::default()
+    });
+    #[field]
+    let text: LayoutNode = LayoutNode::default();
+    let mut x = 0.0;
+
+    root.set_children(&[text]);
+    context.set_layout(Some(root));
+
+    #[render]
+    test_window {
+        width: 300,
+        height: 100,
+        context: Some(context),
+        injector: Some(injector),
+    };
+    #[render]
+    label {
+        context: Some(context),
+        text: "Hello, layout",
+        x: x,
+        layout: Some(text),
+    };
+
+    settle(context).await;
+    // Sized to the text in the font of the theme, rounded to whole pixels
+    let theme = context.theme();
+    let style = TextStyle {
+        family: theme.typography.family.clone(),
+        size: theme.typography.size,
+        ..Default::default()
+    };
+    let (width, height) = style.measure("Hello, layout", Some(300.0));
+    assert!(width > 0.0 && height > 0.0);
+    assert_eq!(
+        text.layout(),
+        LayoutRect {
+            x: 0.0,
+            y: 0.0,
+            width: width.round(),
+            height: height.round(),
+        }
+    );
+
+    // Rendering the label again with the same text only repaints the label, not the whole window
+    x = 1.0;
+    context.frame_clock().next_frame().await;
+    let bounds = Rect::new(0, 0, width.round() as i32, height.round() as i32);
+    assert_eq!(context.surface().last_damage(), [bounds]);
+    injector.request_close();
+}
+
+#[test]
+fn label_is_sized_to_its_text() {
+    run(app);
+}
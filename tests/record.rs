@@ -0,0 +1,232 @@
+// Needs the `record` feature, e.g. `cargo test --features record`
+#![cfg(headless)]
+
+use {
+    compo_window::prelude::*,
+    std::{cell::RefCell, env::temp_dir, fs::remove_file, path::PathBuf, process, rc::Rc},
+};
+
+fn path() -> PathBuf {
+    temp_dir().join(format!("compo-window-record-{}.jsonl", process::id()))
+}
+
+fn touch(x: f64, y: f64) -> PointerEvent {
+    PointerEvent {
+        pointer_id: 1,
+        pointer_type: PointerType::Touch,
+        x,
+        y,
+        button: Some(PointerButton::Primary),
+        click_count: 1,
+        modifiers: Modifiers::NONE,
+        pressure: 0.5,
+        tilt_x: 0.0,
+        tilt_y: 0.0,
+    }
+}
+
+// Read the events of a window up to and including the loss of its focus
+async fn until_blur(events: &WindowEvents) -> Vec<WindowEvent> {
+    let mut received = Vec::new();
+    while let Some(event) = events.next().await {
+        let last = event == WindowEvent::Focused(false);
+        received.push(event);
+        if last {
+            break;
+        }
+    }
+    received
+}
+
+#[component]
+async fn app() {
+    #[field]
+    let recorder: Recorder = Recorder::create(path()).unwrap();
+    #[field]
+    let recorded: InputInjector = InputInjector::default();
+    #[field]
+    let recorded_events: WindowEvents = WindowEvents::default();
+    #[field]
+    let replayed: InputInjector = InputInjector::default();
+    #[field]
+    let replayed_events: WindowEvents = WindowEvents::default();
+    #[field]
+    let replayed_clock: FrameClock = FrameClock::default();
+
+    #[render]
+    window {
+        title: "Recorded",
+        injector: Some(recorded),
+        events: Some(recorded_events),
+        recorder: Some(recorder),
+    };
+    #[render]
+    window {
+        title: "Replayed",
+        injector: Some(replayed),
+        events: Some(replayed_events),
+        frame_clock: Some(replayed_clock),
+    };
+
+    recorded.focus(true);
+    recorded.press_key(KeyEvent {
+        physical_key: KeyCode::KeyA,
+        logical_key: Key::Character('a'),
+        text: Some("a".to_owned()),
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    });
+    recorded.click(10.0, 20.0, PointerButton::Primary);
+    recorded.scroll(10.0, 20.0, ScrollDelta::Lines { x: 0.0, y: -3.0 });
+    // Recognized as a tap while recording
+    recorded.inject(WindowEvent::PointerDown(touch(50.0, 60.0)));
+    recorded.inject(WindowEvent::PointerUp(touch(50.0, 60.0)));
+    recorded.resize(640, 480);
+    recorded.focus(false);
+    let expected = until_blur(recorded_events).await;
+    assert!(expected.contains(&WindowEvent::Gesture(GestureEvent::Tap {
+        x: 50.0,
+        y: 60.0
+    })));
+
+    // Through JSON and back
+    let recording = Recording::load(path()).unwrap();
+    let _ = remove_file(path());
+    let events = recording
+        .records
+        .iter()
+        .filter_map(|record| match &record.entry {
+            RecordEntry::Event(event) => Some(event.clone()),
+            RecordEntry::Props(_) => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(events, expected);
+    assert!(matches!(
+        recording.records[0].entry,
+        RecordEntry::Props(WindowProps { ref title, .. }) if title == "Recorded"
+    ));
+
+    // The same events come out of another window, with the tap taken from the recording
+    replay(&recording, replayed, replayed_clock, f64::INFINITY).await;
+    assert_eq!(until_blur(replayed_events).await, expected);
+
+    recorded.request_close();
+    replayed.request_close();
+}
+
+#[test]
+fn replays_recorded_events() {
+    run(app);
+}
+
+fn moved(x: f64) -> WindowEvent {
+    WindowEvent::PointerMove(PointerEvent {
+        pointer_type: PointerType::Mouse,
+        button: None,
+        click_count: 0,
+        ..touch(x, 0.0)
+    })
+}
+
+// Pointer moves spread over a tenth of a second, with two close together
+fn spread_out() -> Recording {
+    [0, 5, 30, 31, 90]
+        .into_iter()
+        .map(|millis| Record {
+            time: Duration::from_millis(millis),
+            entry: RecordEntry::Event(moved(millis as f64)),
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+// A window's frame clock and the events it delivered, each with the frame it came after
+struct Logged {
+    clock: FrameClock,
+    log: Rc<RefCell<Vec<(u64, WindowEvent)>>>,
+    recorder: Recorder,
+}
+
+impl Default for Logged {
+    fn default() -> Self {
+        let clock = FrameClock::default();
+        let log: Rc<RefCell<Vec<_>>> = Default::default();
+        let recorder = Recorder::new({
+            let (clock, log) = (clock.clone(), log.clone());
+            move |record| {
+                if let RecordEntry::Event(event) = &record.entry {
+                    let frame = clock.last_frame().map_or(0, |frame| frame.number);
+                    log.borrow_mut().push((frame, event.clone()));
+                }
+            }
+        });
+        Self {
+            clock,
+            log,
+            recorder,
+        }
+    }
+}
+
+impl Logged {
+    // Replay into `injector`'s window, with frames counted from the start of the replay
+    async fn replay(&self, injector: &InputInjector) -> Vec<(u64, WindowEvent)> {
+        let start = self.clock.last_frame().map_or(0, |frame| frame.number);
+        replay(&spread_out(), injector, &self.clock, 1.0).await;
+        // The last events are delivered after the replay returns
+        self.clock.next_frame().await;
+        let log = self.log.borrow();
+        log.iter()
+            .map(|(frame, event)| (frame - start, event.clone()))
+            .collect()
+    }
+}
+
+#[component]
+async fn replay_twice() {
+    #[field]
+    let first: InputInjector = InputInjector::default();
+    #[field]
+    let first_log: Logged = Logged::default();
+    #[field]
+    let second: InputInjector = InputInjector::default();
+    #[field]
+    let second_log: Logged = Logged::default();
+
+    #[render]
+    window {
+        injector: Some(first),
+        frame_clock: Some(&first_log.clock),
+        recorder: Some(&first_log.recorder),
+    };
+    #[render]
+    window {
+        injector: Some(second),
+        frame_clock: Some(&second_log.clock),
+        recorder: Some(&second_log.recorder),
+    };
+
+    let first_frames = first_log.replay(first).await;
+    // Hold up the second replay, it lands on the same frames all the same
+    sleep(Duration::from_millis(50)).await;
+    let second_frames = second_log.replay(second).await;
+
+    // One frame per 1/60 s of the recording
+    let expected = vec![
+        (1, moved(0.0)),
+        (2, moved(5.0)),
+        (3, moved(30.0)),
+        (3, moved(31.0)),
+        (7, moved(90.0)),
+    ];
+    assert_eq!(first_frames, expected);
+    assert_eq!(second_frames, expected);
+
+    first.request_close();
+    second.request_close();
+}
+
+#[test]
+fn replays_land_on_the_same_frames() {
+    run(replay_twice);
+}